@@ -1,6 +1,6 @@
 #!/bin/bash
 # Launch Minecraft instance via PrismLauncher
-# Usage: launch-minecraft.sh "instance-name"
+# Usage: launch-minecraft.sh "instance-name" [--server address | --world folder]
 
 if [ -z "$1" ]; then
     echo "Usage: $0 <instance-name>"
@@ -8,5 +8,5 @@ if [ -z "$1" ]; then
 fi
 
 # Launch the instance completely detached using nohup
-nohup flatpak run org.prismlauncher.PrismLauncher --launch "$1" >/dev/null 2>&1 &
+nohup flatpak run org.prismlauncher.PrismLauncher --launch "$1" "${@:2}" >/dev/null 2>&1 &
 disown
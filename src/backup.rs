@@ -0,0 +1,612 @@
+//! Compressed backups of worlds and whole instances, plus the backup list screen.
+//!
+//! Backups are `.tar.gz` archives under the configured backup directory, laid out
+//! as `<instance folder>/worlds/<world folder>/` and `<instance folder>/instance/`.
+//! File names are the UTC creation time, so they sort chronologically.
+//! Incremental snapshots (see [`crate::store`]) sit next to the archives as
+//! `.snapshot.json` manifests and are listed, restored and pruned the same way.
+
+use crate::config::{BackupFormat, RetentionPolicy};
+use crate::store::{Manifest, Store, MANIFEST_EXTENSION};
+use crate::{detail_line, format_size, format_timestamp, step_selection, Instance};
+use anyhow::{bail, Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const EXTENSION: &str = ".tar.gz";
+const PRE_RESTORE_SUFFIX: &str = "-pre-restore";
+
+/// Held open by the game while a world is loaded; never archived or restored.
+const SKIPPED_FILES: [&str; 1] = ["session.lock"];
+
+/// Something that can be backed up: a single world or a whole instance folder.
+#[derive(Debug, Clone)]
+pub struct BackupTarget {
+    pub label: String,
+    /// The configured backup directory, shared by every target.
+    pub root: PathBuf,
+    /// Name of the instance the target belongs to.
+    pub instance_name: String,
+    /// Folder of that instance, used for the running check.
+    pub instance_path: PathBuf,
+    /// The folder that is archived and replaced on restore.
+    pub source: PathBuf,
+    /// Where this target's archives live.
+    pub dir: PathBuf,
+}
+
+impl BackupTarget {
+    pub fn world(backup_root: &Path, instance: &Instance, world_folder: &str) -> Self {
+        Self {
+            label: format!("{} / {}", instance.name, world_folder),
+            root: backup_root.to_path_buf(),
+            instance_name: instance.name.clone(),
+            instance_path: instance.path.clone(),
+            source: crate::game_dir(&instance.path).join("saves").join(world_folder),
+            dir: backup_root.join(instance.folder()).join("worlds").join(world_folder),
+        }
+    }
+
+    pub fn instance(backup_root: &Path, instance: &Instance) -> Self {
+        Self {
+            label: instance.name.clone(),
+            root: backup_root.to_path_buf(),
+            instance_name: instance.name.clone(),
+            instance_path: instance.path.clone(),
+            source: instance.path.clone(),
+            dir: backup_root.join(instance.folder()).join("instance"),
+        }
+    }
+
+    fn store(&self) -> Store {
+        store_for(&self.root)
+    }
+}
+
+fn store_for(backup_root: &Path) -> Store {
+    Store::new(&backup_root.join("store"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub name: String,
+    pub format: BackupFormat,
+    /// Archive size, or the total size of the files a snapshot refers to.
+    pub size: u64,
+    /// Seconds since the epoch.
+    pub created: u64,
+}
+
+impl Backup {
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        let (name, format) = if let Some(name) = file_name.strip_suffix(EXTENSION) {
+            (name.to_string(), BackupFormat::Archive)
+        } else {
+            (file_name.strip_suffix(MANIFEST_EXTENSION)?.to_string(), BackupFormat::Snapshot)
+        };
+        let metadata = fs::metadata(&path).ok()?;
+        let size = match format {
+            BackupFormat::Archive => metadata.len(),
+            BackupFormat::Snapshot => Manifest::load(&path).ok()?.total_size(),
+        };
+        let created = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        Some(Self {
+            path,
+            name,
+            format,
+            size,
+            created,
+        })
+    }
+
+    pub fn is_pre_restore(&self) -> bool {
+        self.name.ends_with(PRE_RESTORE_SUFFIX)
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Formats seconds since the epoch as `YYYY-MM-DD_HH-MM-SS` in UTC.
+pub fn timestamp_name(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Every archive for a target, newest first.
+pub fn list_backups(dir: &Path) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| Backup::from_path(e.path()))
+        .collect();
+    backups.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| b.name.cmp(&a.name)));
+    backups
+}
+
+/// Backs up the target's folder in the given format. `suffix` is appended to
+/// the timestamped name. Returns the backup and how many bytes were written.
+pub fn create(target: &BackupTarget, format: BackupFormat, suffix: &str) -> Result<(Backup, u64)> {
+    if !target.source.is_dir() {
+        bail!("{} does not exist", target.source.display());
+    }
+    fs::create_dir_all(&target.dir)
+        .with_context(|| format!("creating {}", target.dir.display()))?;
+
+    let extension = match format {
+        BackupFormat::Archive => EXTENSION,
+        BackupFormat::Snapshot => MANIFEST_EXTENSION,
+    };
+    let stamp = timestamp_name(now_secs());
+    let mut name = format!("{}{}", stamp, suffix);
+    let mut n = 1;
+    while target.dir.join(format!("{}{}", name, extension)).exists() {
+        n += 1;
+        name = format!("{}-{}{}", stamp, n, suffix);
+    }
+    let path = target.dir.join(format!("{}{}", name, extension));
+
+    let written = match format {
+        BackupFormat::Archive => {
+            // Written under a temporary name so an interrupted backup never looks complete
+            let partial = path.with_extension("gz.part");
+            if let Err(err) = write_archive(&target.source, &partial) {
+                let _ = fs::remove_file(&partial);
+                return Err(err);
+            }
+            fs::rename(&partial, &path)?;
+            fs::metadata(&path)?.len()
+        }
+        BackupFormat::Snapshot => {
+            let previous = list_backups(&target.dir)
+                .into_iter()
+                .find(|b| b.format == BackupFormat::Snapshot)
+                .and_then(|b| Manifest::load(&b.path).ok());
+            target
+                .store()
+                .snapshot(&target.source, &path, previous.as_ref(), &SKIPPED_FILES)?
+                .new_bytes
+        }
+    };
+    let backup = Backup::from_path(path).context("backup vanished after writing")?;
+    Ok((backup, written))
+}
+
+fn write_archive(source: &Path, dest: &Path) -> Result<()> {
+    let file = File::create(dest).with_context(|| format!("creating {}", dest.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    builder.follow_symlinks(false);
+    append_dir(&mut builder, source, Path::new(""))?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn append_dir<W: io::Write>(builder: &mut tar::Builder<W>, dir: &Path, rel: &Path) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        if SKIPPED_FILES.iter().any(|skipped| name == *skipped) {
+            continue;
+        }
+        let path = entry.path();
+        let rel = rel.join(&name);
+        if entry.file_type()?.is_dir() {
+            builder.append_dir(&rel, &path)?;
+            append_dir(builder, &path, &rel)?;
+        } else {
+            builder
+                .append_path_with_name(&path, &rel)
+                .with_context(|| format!("archiving {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the target's folder with the contents of `backup`, taking a
+/// pre-restore backup of the current folder first. Returns that backup, if any.
+pub fn restore(target: &BackupTarget, backup: &Backup) -> Result<Option<Backup>> {
+    let safety = if target.source.is_dir() {
+        let (safety, _) = create(target, backup.format, PRE_RESTORE_SUFFIX).context("pre-restore backup failed")?;
+        Some(safety)
+    } else {
+        None
+    };
+
+    let parent = target.source.parent().context("restore target has no parent folder")?;
+    let folder = target
+        .source
+        .file_name()
+        .context("restore target has no folder name")?
+        .to_string_lossy()
+        .into_owned();
+    let staging = parent.join(format!(".{}.restoring", folder));
+    let old = parent.join(format!(".{}.replaced", folder));
+    for leftover in [&staging, &old] {
+        if leftover.exists() {
+            fs::remove_dir_all(leftover)?;
+        }
+    }
+
+    // Extract next to the target first so a corrupt backup leaves it untouched
+    fs::create_dir_all(&staging)?;
+    if let Err(err) = extract(target, backup, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err).with_context(|| format!("extracting {}", backup.name));
+    }
+
+    if target.source.exists() {
+        fs::rename(&target.source, &old)?;
+    }
+    if let Err(err) = fs::rename(&staging, &target.source) {
+        let _ = fs::rename(&old, &target.source);
+        return Err(err.into());
+    }
+    if old.exists() {
+        fs::remove_dir_all(&old)?;
+    }
+    Ok(safety)
+}
+
+fn extract(target: &BackupTarget, backup: &Backup, dest: &Path) -> Result<()> {
+    match backup.format {
+        BackupFormat::Archive => {
+            let file = File::open(&backup.path).with_context(|| format!("opening {}", backup.path.display()))?;
+            tar::Archive::new(GzDecoder::new(file)).unpack(dest)?;
+        }
+        BackupFormat::Snapshot => target.store().extract(&Manifest::load(&backup.path)?, dest)?,
+    }
+    Ok(())
+}
+
+/// Indices of backups (newest first) that fall outside the policy. The newest
+/// backup is always kept.
+fn backups_to_prune(backups: &[Backup], policy: &RetentionPolicy, now: u64) -> Vec<usize> {
+    let max_age = policy.max_age_days.map(|days| days * 86400);
+    (1..backups.len())
+        .filter(|&i| {
+            policy.keep_last.is_some_and(|keep| i >= keep.max(1))
+                || max_age.is_some_and(|max| now.saturating_sub(backups[i].created) > max)
+        })
+        .collect()
+}
+
+/// The backups (newest first) that `policy` says should be deleted.
+pub fn expired_backups(backups: &[Backup], policy: &RetentionPolicy) -> Vec<Backup> {
+    backups_to_prune(backups, policy, now_secs())
+        .into_iter()
+        .map(|i| backups[i].clone())
+        .collect()
+}
+
+/// Deletes backups, then removes store objects that no remaining snapshot uses.
+pub fn delete_backups(backup_root: &Path, backups: &[Backup]) -> Result<()> {
+    for backup in backups {
+        fs::remove_file(&backup.path).with_context(|| format!("deleting {}", backup.path.display()))?;
+    }
+    if backups.iter().any(|b| b.format == BackupFormat::Snapshot) {
+        collect_garbage(backup_root)?;
+    }
+    Ok(())
+}
+
+/// Removes unreferenced objects from the snapshot store, returning the count and bytes freed.
+pub fn collect_garbage(backup_root: &Path) -> Result<(usize, u64)> {
+    store_for(backup_root).collect_garbage(backup_root)
+}
+
+pub fn describe_policy(policy: &RetentionPolicy) -> String {
+    let mut parts = Vec::new();
+    if let Some(keep) = policy.keep_last {
+        parts.push(format!("keep last {}", keep));
+    }
+    if let Some(days) = policy.max_age_days {
+        parts.push(format!("max {} days", days));
+    }
+    if parts.is_empty() {
+        "keep everything".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+pub struct BackupBrowser {
+    pub target: BackupTarget,
+    pub backups: Vec<Backup>,
+    pub state: ListState,
+    /// Bytes and objects in the shared snapshot store.
+    store_usage: (u64, usize),
+}
+
+impl BackupBrowser {
+    pub fn open(target: BackupTarget) -> Self {
+        let mut browser = Self {
+            target,
+            backups: Vec::new(),
+            state: ListState::default(),
+            store_usage: (0, 0),
+        };
+        browser.reload();
+        browser
+    }
+
+    pub fn reload(&mut self) {
+        self.backups = list_backups(&self.target.dir);
+        self.store_usage = self.target.store().usage();
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(if self.backups.is_empty() {
+            None
+        } else {
+            Some(selected.min(self.backups.len() - 1))
+        });
+    }
+
+    pub fn selected(&self) -> Option<&Backup> {
+        self.state.selected().and_then(|i| self.backups.get(i))
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.backups.len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.backups.len(), false);
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, browser: &mut BackupBrowser, policy: &RetentionPolicy) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let title = format!(" Backups — {} ", browser.target.label);
+    let list_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(title);
+
+    if browser.backups.is_empty() {
+        let message = Paragraph::new("No backups yet — press b to create one")
+            .style(Style::default().fg(Color::Yellow))
+            .block(list_block);
+        f.render_widget(message, chunks[0]);
+    } else {
+        let items: Vec<ListItem> = browser
+            .backups
+            .iter()
+            .map(|backup| {
+                let mut title_spans = vec![Span::styled(
+                    backup.name.clone(),
+                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                )];
+                if backup.format == BackupFormat::Snapshot {
+                    title_spans.push(Span::styled(" ◆", Style::default().fg(Color::Cyan)));
+                }
+                if backup.is_pre_restore() {
+                    title_spans.push(Span::styled(" ↺", Style::default().fg(Color::Magenta)));
+                }
+                ListItem::new(vec![
+                    Line::from(title_spans),
+                    Line::from(Span::styled(
+                        format!("  {} • {}", format_timestamp(backup.created * 1000), format_size(backup.size)),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ])
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(list_block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Rgb(50, 50, 80))
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, chunks[0], &mut browser.state);
+    }
+
+    let archives: Vec<&Backup> = browser.backups.iter().filter(|b| b.format == BackupFormat::Archive).collect();
+    let snapshots = browser.backups.len() - archives.len();
+    let archive_total: u64 = archives.iter().map(|b| b.size).sum();
+    let (store_bytes, store_objects) = browser.store_usage;
+    let mut lines = vec![
+        detail_line("Source: ", browser.target.source.display().to_string()),
+        detail_line("Stored In: ", browser.target.dir.display().to_string()),
+        detail_line("Archives: ", format!("{} ({})", archives.len(), format_size(archive_total))),
+        detail_line("Snapshots: ", snapshots.to_string()),
+        detail_line(
+            "Snapshot Store: ",
+            format!("{} in {} objects (shared)", format_size(store_bytes), store_objects),
+        ),
+        detail_line("Retention: ", describe_policy(policy)),
+    ];
+    if let Some(backup) = browser.selected() {
+        lines.push(Line::from(""));
+        lines.push(detail_line("Selected: ", backup.name.clone()));
+        let format = match backup.format {
+            BackupFormat::Archive => "Archive (.tar.gz)",
+            BackupFormat::Snapshot => "Incremental snapshot",
+        };
+        lines.push(detail_line("Format: ", format));
+        lines.push(detail_line("Size: ", format_size(backup.size)));
+        lines.push(detail_line("Created: ", format_timestamp(backup.created * 1000)));
+        if backup.is_pre_restore() {
+            lines.push(Line::from(Span::styled(
+                "Taken automatically before a restore",
+                Style::default().fg(Color::Magenta),
+            )));
+        }
+    }
+    let details = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(" Backup Details "),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minecraft-tui-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_timestamp_name() {
+        assert_eq!(timestamp_name(0), "1970-01-01_00-00-00");
+        assert_eq!(timestamp_name(1_709_251_199), "2024-02-29_23-59-59");
+        assert_eq!(timestamp_name(1_760_745_600), "2025-10-18_00-00-00");
+    }
+
+    fn backup(created: u64) -> Backup {
+        Backup {
+            path: PathBuf::new(),
+            name: timestamp_name(created),
+            format: BackupFormat::Archive,
+            size: 0,
+            created,
+        }
+    }
+
+    #[test]
+    fn test_prune_policy() {
+        let day = 86400;
+        let now = 100 * day;
+        let backups: Vec<Backup> = (0..5).map(|i| backup(now - i * 10 * day)).collect();
+
+        let count = RetentionPolicy { keep_last: Some(2), max_age_days: None };
+        assert_eq!(backups_to_prune(&backups, &count, now), vec![2, 3, 4]);
+
+        let age = RetentionPolicy { keep_last: None, max_age_days: Some(15) };
+        assert_eq!(backups_to_prune(&backups, &age, now), vec![2, 3, 4]);
+
+        // The newest backup survives even when everything is too old
+        let strict = RetentionPolicy { keep_last: Some(0), max_age_days: Some(0) };
+        assert_eq!(backups_to_prune(&backups[1..], &strict, now), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_backup_and_restore_round_trip() {
+        let root = temp_dir("backup");
+        let instance = Instance {
+            name: "Test".to_string(),
+            path: root.join("instances/Test"),
+            ..Default::default()
+        };
+        let world = instance.path.join(".minecraft/saves/World");
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::write(world.join("level.dat"), b"original").unwrap();
+        fs::write(world.join("region/r.0.0.mca"), b"chunks").unwrap();
+        fs::write(world.join("session.lock"), b"lock").unwrap();
+
+        let target = BackupTarget::world(&root.join("backups"), &instance, "World");
+        assert_eq!(target.source, world);
+        let (first, _) = create(&target, BackupFormat::Archive, "").unwrap();
+
+        fs::write(world.join("level.dat"), b"changed").unwrap();
+        fs::write(world.join("extra.dat"), b"new").unwrap();
+        let safety = restore(&target, &first).unwrap().unwrap();
+        assert!(safety.is_pre_restore());
+
+        assert_eq!(fs::read(world.join("level.dat")).unwrap(), b"original");
+        assert_eq!(fs::read(world.join("region/r.0.0.mca")).unwrap(), b"chunks");
+        assert!(!world.join("extra.dat").exists());
+        assert!(!world.join("session.lock").exists());
+        assert_eq!(list_backups(&target.dir).len(), 2);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_restore_and_prune() {
+        let root = temp_dir("snapshot");
+        let instance = Instance {
+            name: "Test".to_string(),
+            path: root.join("instances/Test"),
+            ..Default::default()
+        };
+        let world = instance.path.join(".minecraft/saves/World");
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::write(world.join("region/r.0.0.mca"), b"first").unwrap();
+
+        let backup_root = root.join("backups");
+        let target = BackupTarget::world(&backup_root, &instance, "World");
+        let (first, _) = create(&target, BackupFormat::Snapshot, "").unwrap();
+        assert_eq!(first.format, BackupFormat::Snapshot);
+        assert_eq!(first.size, 5);
+
+        fs::write(world.join("region/r.0.0.mca"), b"second").unwrap();
+        restore(&target, &first).unwrap();
+        assert_eq!(fs::read(world.join("region/r.0.0.mca")).unwrap(), b"first");
+
+        // Deleting the pre-restore snapshot frees the "second" blob
+        let backups = list_backups(&target.dir);
+        let pre_restore: Vec<Backup> = backups.into_iter().filter(|b| b.is_pre_restore()).collect();
+        delete_backups(&backup_root, &pre_restore).unwrap();
+        assert_eq!(target.store().usage().1, 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! User settings stored as JSON in `~/.config/minecraft-tui/config.json`.
+//!
+//! Every field has a default, so a missing or partial file is fine. The file is
+//! only written when a setting is changed from inside the TUI.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Where world and instance backups go. Defaults to `~/.local/share/minecraft-tui/backups`.
+    pub backup_dir: Option<PathBuf>,
+    pub retention: RetentionPolicy,
+    /// Format for backups started with `b` and for auto-backups.
+    pub backup_format: BackupFormat,
+    /// Per-instance settings, keyed by the instance's folder name.
+    pub instances: BTreeMap<String, InstanceSettings>,
+    /// Saved RCON credentials, keyed by the game server's address as `host:port`
+    /// (see [`crate::ping::normalize_address`]), which the server list looks them up by.
+    pub rcon: BTreeMap<String, RconServer>,
+    /// Dedicated server folders managed from the TUI, keyed by display name.
+    pub dedicated_servers: BTreeMap<String, DedicatedServerSettings>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DedicatedServerSettings {
+    pub path: PathBuf,
+    /// Java executable and JVM options, such as `java -Xmx4G`. Defaults to `java`.
+    pub java: Option<String>,
+    /// The jar to run, for folders holding more than one.
+    pub jar: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RconServer {
+    /// `host[:port]`; the port defaults to 25575.
+    pub address: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupFormat {
+    /// A self-contained `.tar.gz` of the whole folder.
+    #[default]
+    Archive,
+    /// An incremental snapshot in the deduplicating store.
+    Snapshot,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceSettings {
+    /// Back up the worlds played in a session when a game launched from the TUI exits.
+    pub auto_backup: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Keep at most this many backups per world/instance.
+    pub keep_last: Option<usize>,
+    /// Delete backups older than this many days.
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: Some(10),
+            max_age_days: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        config_dir().join("config.json")
+    }
+
+    /// Loads the config, falling back to defaults if the file is missing or invalid.
+    pub fn load() -> Self {
+        Self::load_from(&Self::path()).unwrap_or_default()
+    }
+
+    fn load_from(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        // RCON passwords are stored in the clear, so the file is never readable
+        // by others, not even while it's being written
+        let temp = path.with_extension("json.tmp");
+        let _ = fs::remove_file(&temp);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&temp)
+            .with_context(|| format!("creating {}", temp.display()))?;
+        file.write_all(content.as_bytes())
+            .and_then(|()| file.sync_all())
+            .with_context(|| format!("writing {}", temp.display()))?;
+        fs::rename(&temp, &path).with_context(|| format!("replacing {}", path.display()))
+    }
+
+    pub fn instance(&self, folder: &str) -> InstanceSettings {
+        self.instances.get(folder).cloned().unwrap_or_default()
+    }
+
+    pub fn instance_mut(&mut self, folder: &str) -> &mut InstanceSettings {
+        self.instances.entry(folder.to_string()).or_default()
+    }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        self.backup_dir
+            .clone()
+            .unwrap_or_else(|| data_dir().join("backups"))
+    }
+}
+
+fn home_dir() -> PathBuf {
+    env::var_os("HOME").map(PathBuf::from).unwrap_or_default()
+}
+
+/// `$XDG_CONFIG_HOME/minecraft-tui`, usually `~/.config/minecraft-tui`.
+pub fn config_dir() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home_dir().join(".config"))
+        .join("minecraft-tui")
+}
+
+/// `$XDG_DATA_HOME/minecraft-tui`, usually `~/.local/share/minecraft-tui`.
+pub fn data_dir() -> PathBuf {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home_dir().join(".local/share"))
+        .join("minecraft-tui")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config_uses_defaults() {
+        let config: Config = serde_json::from_str(r#"{"backup_dir": "/mnt/backups"}"#).unwrap();
+        assert_eq!(config.backup_dir(), PathBuf::from("/mnt/backups"));
+        assert_eq!(config.retention, RetentionPolicy::default());
+
+        assert_eq!(config.backup_format, BackupFormat::Archive);
+
+        let config: Config =
+            serde_json::from_str(r#"{"retention": {"max_age_days": 30}, "backup_format": "snapshot"}"#).unwrap();
+        assert_eq!(config.backup_format, BackupFormat::Snapshot);
+        assert_eq!(config.retention.keep_last, Some(10));
+        assert_eq!(config.retention.max_age_days, Some(30));
+        assert!(!config.instance("Create").auto_backup);
+    }
+
+    #[test]
+    fn test_instance_settings() {
+        let config: Config = serde_json::from_str(r#"{"instances": {"Create": {"auto_backup": true}}}"#).unwrap();
+        assert!(config.instance("Create").auto_backup);
+        assert!(!config.instance("Vanilla").auto_backup);
+    }
+
+    #[test]
+    fn test_rcon_servers() {
+        let config: Config =
+            serde_json::from_str(r#"{"rcon": {"10.0.0.5:25565": {"address": "10.0.0.5", "password": "hunter2"}}}"#)
+                .unwrap();
+        assert_eq!(config.rcon["10.0.0.5:25565"].address, "10.0.0.5");
+        assert_eq!(config.rcon["10.0.0.5:25565"].password, "hunter2");
+        assert!(Config::default().rcon.is_empty());
+    }
+
+    #[test]
+    fn test_dedicated_servers() {
+        let config: Config =
+            serde_json::from_str(r#"{"dedicated_servers": {"Survival": {"path": "/srv/survival", "java": "java -Xmx4G"}}}"#)
+                .unwrap();
+        let server = &config.dedicated_servers["Survival"];
+        assert_eq!(server.path, PathBuf::from("/srv/survival"));
+        assert_eq!(server.java.as_deref(), Some("java -Xmx4G"));
+        assert_eq!(server.jar, None);
+    }
+}
@@ -0,0 +1,623 @@
+//! Reading crash reports and explaining them.
+//!
+//! Two kinds of file are understood: the game's `crash-reports/crash-*.txt`
+//! and the JVM's `hs_err_pid*.log`, written to the game folder when Java
+//! itself dies. Each is split into its description, exception chain,
+//! suspected mods, system details and mod list.
+//!
+//! The diagnosis comes from a list of rules, each a regex over the whole file
+//! with plain-language advice that can use the regex's captures (`$1`). The
+//! bundled rules are in `crash-rules.json`; rules in
+//! `~/.config/minecraft-tui/crash-rules.json` are checked first, and replace a
+//! bundled rule with the same id.
+
+use crate::config::config_dir;
+use crate::detail_line;
+use crate::logfiles::read_text;
+use anyhow::{Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const BUNDLED_RULES: &str = include_str!("crash-rules.json");
+/// Frames shown under each exception.
+const SHOWN_FRAMES: usize = 6;
+/// Matches of one rule listed at most.
+const MAX_MATCHES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportKind {
+    /// `crash-reports/crash-*.txt`
+    Game,
+    /// `hs_err_pid*.log`
+    Jvm,
+}
+
+/// Whether a file in the game folder is something [`parse`] understands.
+pub fn is_crash_file(name: &str) -> bool {
+    (name.starts_with("crash-") && name.ends_with(".txt")) || (name.starts_with("hs_err_pid") && name.ends_with(".log"))
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Exception {
+    /// `java.lang.Foo: message`
+    pub header: String,
+    pub frames: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrashReport {
+    pub kind: ReportKind,
+    pub time: Option<String>,
+    pub description: Option<String>,
+    /// The thrown exception first, then its causes.
+    pub exceptions: Vec<Exception>,
+    pub suspected_mods: Vec<String>,
+    pub system: Vec<(String, String)>,
+    /// `id: Name version`, as far as the report gives them.
+    pub mods: Vec<String>,
+    pub text: String,
+}
+
+pub fn load(path: &Path) -> Result<CrashReport> {
+    Ok(parse(&read_text(path)?))
+}
+
+pub fn parse(text: &str) -> CrashReport {
+    let mut report = CrashReport {
+        kind: ReportKind::Game,
+        time: None,
+        description: None,
+        exceptions: Vec::new(),
+        suspected_mods: Vec::new(),
+        system: Vec::new(),
+        mods: Vec::new(),
+        text: text.to_string(),
+    };
+    if text.contains("# A fatal error has been detected by the Java Runtime Environment")
+        || text.contains("# There is insufficient memory for the Java Runtime Environment")
+    {
+        report.kind = ReportKind::Jvm;
+        parse_jvm(text, &mut report);
+    } else {
+        parse_game(text, &mut report);
+    }
+    report
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn parse_game(text: &str, report: &mut CrashReport) {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some(time) = line.strip_prefix("Time: ") {
+            report.time.get_or_insert_with(|| time.trim().to_string());
+        } else if let Some(description) = line.strip_prefix("Description: ") {
+            report.description = Some(description.trim().to_string());
+            i = parse_exceptions(&lines, i + 1, &mut report.exceptions);
+            continue;
+        } else if line.trim_start().starts_with("Suspected Mod") {
+            i = parse_suspected(&lines, i, &mut report.suspected_mods);
+            continue;
+        } else if line.starts_with("-- System Details --") {
+            parse_system(&lines[i + 1..], report);
+            break;
+        }
+        i += 1;
+    }
+}
+
+/// Reads the stack trace after the description. Returns the line after it.
+fn parse_exceptions(lines: &[&str], mut i: usize, exceptions: &mut Vec<Exception>) -> usize {
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if line.starts_with("A detailed walkthrough of the error") || line.starts_with("-- ") {
+            break;
+        }
+        if line.starts_with("at ") || (line.starts_with("... ") && line.ends_with("more")) {
+            if let Some(exception) = exceptions.last_mut() {
+                exception.frames.push(line.to_string());
+            }
+        } else if !line.is_empty() {
+            let header = line
+                .strip_prefix("Caused by: ")
+                .or_else(|| line.strip_prefix("Suppressed: "))
+                .unwrap_or(line);
+            exceptions.push(Exception {
+                header: header.to_string(),
+                frames: Vec::new(),
+            });
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Reads `Suspected Mod(s): X` and the mods indented under it. Forge and
+/// Fabric repeat it per section, so duplicates are skipped.
+fn parse_suspected(lines: &[&str], i: usize, mods: &mut Vec<String>) -> usize {
+    let mut add = |name: &str| {
+        let name = name.trim();
+        if !name.is_empty() && !matches!(name, "NONE" | "Unknown") && !mods.iter().any(|m| m == name) {
+            mods.push(name.to_string());
+        }
+    };
+    if let Some((_, value)) = lines[i].split_once(':') {
+        add(value);
+    }
+    let mut next = i + 1;
+    let mut level = None;
+    while let Some(line) = lines.get(next) {
+        let depth = indent(line);
+        if depth == 0 || line.trim().is_empty() {
+            break;
+        }
+        if *level.get_or_insert(depth) == depth {
+            add(line);
+        }
+        next += 1;
+    }
+    next
+}
+
+/// Reads `Key: Value` pairs, collecting the mod list nested under
+/// `Fabric Mods`, `Mod List` or `Mods`.
+fn parse_system(lines: &[&str], report: &mut CrashReport) {
+    // The pairs sit under a `Details:` line
+    let Some(start) = lines.iter().position(|l| indent(l) > 0 && l.contains(':')) else {
+        return;
+    };
+    let top = indent(lines[start]);
+    let mut in_mods = false;
+    let mut mod_depth = None;
+    for line in &lines[start..] {
+        let depth = indent(line);
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if depth == top {
+            let (key, value) = trimmed.split_once(':').unwrap_or((trimmed, ""));
+            in_mods = matches!(key, "Fabric Mods" | "Mod List" | "Mods" | "Quilt Mods");
+            mod_depth = None;
+            if !in_mods {
+                report.system.push((key.to_string(), value.trim().to_string()));
+            }
+        } else if depth > top && in_mods && *mod_depth.get_or_insert(depth) == depth {
+            report.mods.push(mod_entry(trimmed));
+        } else if depth < top {
+            break;
+        }
+    }
+}
+
+/// Forge lists mods as a table, `file.jar |Name |id |version |state |…`.
+fn mod_entry(line: &str) -> String {
+    let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+    match fields.as_slice() {
+        [_, name, id, version, ..] => format!("{}: {} {}", id, name, version),
+        _ => line.to_string(),
+    }
+}
+
+fn parse_jvm(text: &str, report: &mut CrashReport) {
+    let mut lines = text.lines().peekable();
+    let mut exception = Exception::default();
+    while let Some(line) = lines.next() {
+        let comment = line.strip_prefix('#').map(str::trim);
+        match comment {
+            Some(c) if c.starts_with("A fatal error has been detected") || c.starts_with("There is insufficient memory") => {
+                report.description = Some(c.trim_end_matches(':').to_string());
+            }
+            Some(c) if exception.header.is_empty() && report.description.is_some() && !c.is_empty() => {
+                exception.header = c.to_string();
+            }
+            Some(c) if c.starts_with("Problematic frame:") => {
+                if let Some(frame) = lines.next_if(|l| l.starts_with('#')) {
+                    exception.frames.push(frame.trim_start_matches('#').trim().to_string());
+                }
+            }
+            Some(c) => {
+                for key in ["JRE version", "Java VM"] {
+                    if let Some(value) = c.strip_prefix(key).and_then(|v| v.strip_prefix(':')) {
+                        report.system.push((key.to_string(), value.trim().to_string()));
+                    }
+                }
+            }
+            None => {
+                if let Some(time) = line.strip_prefix("Time: ") {
+                    let time = time.split(" elapsed time").next().unwrap_or(time);
+                    report.time = Some(time.trim().to_string());
+                } else if let Some(host) = line.strip_prefix("Host: ") {
+                    report.system.push(("Host".to_string(), host.trim().to_string()));
+                } else if line == "OS:" {
+                    // Newer JVMs put the OS name on the next line
+                    if let Some(os) = lines.next() {
+                        report.system.push(("OS".to_string(), os.trim().to_string()));
+                    }
+                } else if let Some(memory) = line.strip_prefix("Memory: ") {
+                    report.system.push(("Memory".to_string(), memory.trim().to_string()));
+                }
+            }
+        }
+    }
+    if !exception.header.is_empty() {
+        report.exceptions.push(exception);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub pattern: String,
+    pub title: String,
+    pub advice: String,
+}
+
+pub struct CompiledRule {
+    pub rule: Rule,
+    regex: Regex,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis {
+    pub rule: String,
+    pub title: String,
+    /// The advice for each distinct match.
+    pub advice: Vec<String>,
+}
+
+pub fn user_rules_path() -> PathBuf {
+    config_dir().join("crash-rules.json")
+}
+
+/// The user's rules followed by the bundled ones they don't replace, and a
+/// message for each rule or file that couldn't be used.
+pub fn load_rules() -> (Vec<CompiledRule>, Vec<String>) {
+    let mut problems = Vec::new();
+    let path = user_rules_path();
+    let user = match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .with_context(|| format!("reading {}", path.display()))
+            .unwrap_or_else(|e| {
+                problems.push(format!("{:#}", e));
+                Vec::new()
+            }),
+        Err(_) => Vec::new(),
+    };
+    let bundled: Vec<Rule> = serde_json::from_str(BUNDLED_RULES).expect("bundled crash rules are valid JSON");
+    let (rules, mut errors) = compile_rules(user, bundled);
+    problems.append(&mut errors);
+    (rules, problems)
+}
+
+fn compile_rules(user: Vec<Rule>, bundled: Vec<Rule>) -> (Vec<CompiledRule>, Vec<String>) {
+    let mut errors = Vec::new();
+    let overridden: Vec<String> = user.iter().map(|r| r.id.clone()).collect();
+    let rules = user
+        .into_iter()
+        .chain(bundled.into_iter().filter(|r| !overridden.contains(&r.id)))
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(regex) => Some(CompiledRule { rule, regex }),
+            Err(e) => {
+                errors.push(format!("Rule {}: {}", rule.id, e));
+                None
+            }
+        })
+        .collect();
+    (rules, errors)
+}
+
+/// Runs the rules over a report. A rule whose title an earlier match already
+/// used is skipped, so a specific rule can shadow a general one.
+pub fn diagnose(report: &CrashReport, rules: &[CompiledRule]) -> Vec<Diagnosis> {
+    let mut diagnoses: Vec<Diagnosis> = Vec::new();
+    for CompiledRule { rule, regex } in rules {
+        if diagnoses.iter().any(|d| d.title == rule.title) {
+            continue;
+        }
+        let mut advice: Vec<String> = Vec::new();
+        for captures in regex.captures_iter(&report.text) {
+            let mut text = String::new();
+            captures.expand(&rule.advice, &mut text);
+            if !advice.contains(&text) {
+                advice.push(text);
+            }
+            if advice.len() == MAX_MATCHES {
+                break;
+            }
+        }
+        if !advice.is_empty() {
+            diagnoses.push(Diagnosis {
+                rule: rule.id.clone(),
+                title: rule.title.clone(),
+                advice,
+            });
+        }
+    }
+    diagnoses
+}
+
+pub struct CrashView {
+    /// Where the report came from, relative to the game folder.
+    pub name: String,
+    pub instance_name: String,
+    pub report: CrashReport,
+    pub diagnoses: Vec<Diagnosis>,
+    /// Rules that couldn't be loaded.
+    pub problems: Vec<String>,
+    pub scroll: u16,
+}
+
+impl CrashView {
+    pub fn open(instance_name: &str, name: &str, path: &Path) -> Result<Self> {
+        let report = load(path)?;
+        let (rules, problems) = load_rules();
+        Ok(Self {
+            name: name.to_string(),
+            instance_name: instance_name.to_string(),
+            diagnoses: diagnose(&report, &rules),
+            report,
+            problems,
+            scroll: 0,
+        })
+    }
+
+    pub fn scroll_by(&mut self, delta: i32) {
+        self.scroll = (self.scroll as i32 + delta).max(0) as u16;
+    }
+}
+
+fn heading(text: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        text.to_string(),
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+    ))
+}
+
+fn details(report: &CrashReport) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    if let Some(ref time) = report.time {
+        lines.push(detail_line("Time: ", time.clone()));
+    }
+    if let Some(ref description) = report.description {
+        lines.push(detail_line("Description: ", description.clone()));
+    }
+    lines.push(Line::from(""));
+
+    lines.push(heading(if report.kind == ReportKind::Jvm { "Error" } else { "Exceptions" }));
+    if report.exceptions.is_empty() {
+        lines.push(Line::from(Span::styled("  none found", dim)));
+    }
+    for (i, exception) in report.exceptions.iter().enumerate() {
+        let prefix = if i == 0 { "  " } else { "  Caused by: " };
+        lines.push(Line::from(vec![
+            Span::raw(prefix),
+            Span::styled(exception.header.clone(), Style::default().fg(Color::Red)),
+        ]));
+        for frame in exception.frames.iter().take(SHOWN_FRAMES) {
+            lines.push(Line::from(Span::styled(format!("      {}", frame), dim)));
+        }
+        if exception.frames.len() > SHOWN_FRAMES {
+            lines.push(Line::from(Span::styled(
+                format!("      … {} more frames", exception.frames.len() - SHOWN_FRAMES),
+                dim,
+            )));
+        }
+    }
+    lines.push(Line::from(""));
+
+    if report.kind == ReportKind::Game {
+        lines.push(heading("Suspected Mods"));
+        if report.suspected_mods.is_empty() {
+            lines.push(Line::from(Span::styled("  none named", dim)));
+        }
+        for name in &report.suspected_mods {
+            lines.push(Line::from(Span::styled(format!("  {}", name), Style::default().fg(Color::Yellow))));
+        }
+        lines.push(Line::from(""));
+    }
+
+    if !report.system.is_empty() {
+        lines.push(heading("System Details"));
+        for (key, value) in &report.system {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}: ", key), Style::default().fg(Color::Gray)),
+                Span::raw(value.clone()),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+
+    if !report.mods.is_empty() {
+        lines.push(heading(&format!("Mods ({})", report.mods.len())));
+        for entry in &report.mods {
+            lines.push(Line::from(format!("  {}", entry)));
+        }
+    }
+    lines
+}
+
+pub fn render(f: &mut Frame, area: Rect, view: &mut CrashView) {
+    let mut diagnosis: Vec<Line> = Vec::new();
+    if view.diagnoses.is_empty() {
+        diagnosis.push(Line::from(Span::styled(
+            "No known pattern matched. The exception and suspected mods below are the best leads.",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    for d in &view.diagnoses {
+        diagnosis.push(Line::from(Span::styled(
+            format!("● {}", d.title),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )));
+        for advice in &d.advice {
+            diagnosis.push(Line::from(format!("  {}", advice)));
+        }
+    }
+    for problem in &view.problems {
+        diagnosis.push(Line::from(Span::styled(problem.clone(), Style::default().fg(Color::Red))));
+    }
+    // Room for wrapped advice, leaving most of the screen to the details
+    let wanted = diagnosis.len() as u16 * 2 + 2;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(wanted.min(area.height / 2)), Constraint::Min(0)])
+        .split(area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title(format!(" Diagnosis — {} ", view.instance_name));
+    f.render_widget(Paragraph::new(diagnosis).wrap(Wrap { trim: false }).block(block), chunks[0]);
+
+    let lines = details(&view.report);
+    view.scroll = view.scroll.min(lines.len().saturating_sub(1) as u16);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} ", view.name));
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((view.scroll, 0))
+        .block(block);
+    f.render_widget(paragraph, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME_REPORT: &str = "---- Minecraft Crash Report ----
+// Don't be sad, have a hug! <3
+
+Time: 2024-05-01 10:00:03
+Description: Mod loading error has occurred
+
+java.lang.RuntimeException: Mixin apply for mod sodiumextra failed
+\tat org.spongepowered.asm.mixin.transformer.MixinProcessor.applyMixins(MixinProcessor.java:392)
+\tat net.minecraft.client.Main.main(Main.java:1)
+Caused by: org.spongepowered.asm.mixin.injection.throwables.InvalidInjectionException: Critical injection failure
+\t... 12 more
+
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- Head --
+Thread: Render thread
+Suspected Mods:
+\tSodium Extra (sodiumextra), Version: 0.5.1
+\t\tIssue tracker URL: https://example.org/issues
+Stacktrace:
+\tat net.minecraft.client.Main.main(Main.java:1)
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.20.1
+\tJava Version: 17.0.8, Eclipse Adoptium
+\tFabric Mods:
+\t\tfabric-api: Fabric API 0.86.0+1.20.1
+\t\t\tfabric-api-base: Fabric API Base 0.4.29
+\t\tsodium: Sodium 0.5.3
+\tMod List:
+\t\tcreate-1.20.1.jar |Create |create |0.5.1.f |DONE |Manifest: NOSIGNATURE
+\tLaunched Version: fabric-loader-0.14.22-1.20.1
+";
+
+    const JVM_REPORT: &str = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f, pid=4242, tid=4243
+#
+# JRE version: OpenJDK Runtime Environment Temurin-17.0.8+7 (17.0.8+7) (build 17.0.8+7)
+# Java VM: OpenJDK 64-Bit Server VM Temurin-17.0.8+7 (17.0.8+7, mixed mode, tiered, compressed oops, g1 gc, linux-amd64)
+# Problematic frame:
+# C  [libnvidia-glcore.so.535.54.03+0xe4a1b0]
+#
+
+---------------  S U M M A R Y ------------
+
+Host: AMD Ryzen 7 5800X, 16 cores, 31G, Ubuntu 22.04.3 LTS
+Time: Wed May  1 10:00:03 2024 CEST elapsed time: 312.5 seconds (0d 0h 5m 12s)
+";
+
+    #[test]
+    fn test_parse_game_report() {
+        let report = parse(GAME_REPORT);
+        assert_eq!(report.kind, ReportKind::Game);
+        assert_eq!(report.time.as_deref(), Some("2024-05-01 10:00:03"));
+        assert_eq!(report.description.as_deref(), Some("Mod loading error has occurred"));
+        assert_eq!(report.exceptions.len(), 2);
+        assert_eq!(report.exceptions[0].frames.len(), 2);
+        assert!(report.exceptions[1].header.starts_with("org.spongepowered"));
+        assert_eq!(report.suspected_mods, ["Sodium Extra (sodiumextra), Version: 0.5.1"]);
+        assert!(report.system.contains(&("Minecraft Version".to_string(), "1.20.1".to_string())));
+        assert!(report.system.iter().any(|(k, _)| k == "Launched Version"));
+        assert_eq!(
+            report.mods,
+            ["fabric-api: Fabric API 0.86.0+1.20.1", "sodium: Sodium 0.5.3", "create: Create 0.5.1.f"]
+        );
+    }
+
+    #[test]
+    fn test_parse_jvm_report() {
+        let report = parse(JVM_REPORT);
+        assert_eq!(report.kind, ReportKind::Jvm);
+        assert_eq!(
+            report.description.as_deref(),
+            Some("A fatal error has been detected by the Java Runtime Environment")
+        );
+        assert_eq!(report.exceptions[0].header, "SIGSEGV (0xb) at pc=0x00007f, pid=4242, tid=4243");
+        assert_eq!(report.exceptions[0].frames, ["C  [libnvidia-glcore.so.535.54.03+0xe4a1b0]"]);
+        assert_eq!(report.time.as_deref(), Some("Wed May  1 10:00:03 2024 CEST"));
+        assert!(report.system.iter().any(|(k, v)| k == "JRE version" && v.contains("17.0.8")));
+    }
+
+    #[test]
+    fn test_rules() {
+        let bundled: Vec<Rule> = serde_json::from_str(BUNDLED_RULES).unwrap();
+        let (rules, errors) = compile_rules(Vec::new(), bundled.clone());
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let diagnoses = diagnose(&parse(GAME_REPORT), &rules);
+        assert_eq!(diagnoses.len(), 1);
+        assert_eq!(diagnoses[0].rule, "mixin-apply-failed-mod");
+        assert!(diagnoses[0].advice[0].starts_with("sodiumextra tried"));
+
+        let diagnoses = diagnose(&parse(JVM_REPORT), &rules);
+        let ids: Vec<&str> = diagnoses.iter().map(|d| d.rule.as_str()).collect();
+        assert_eq!(ids, ["native-graphics-crash", "jvm-native-crash"]);
+        assert!(diagnoses[0].advice[0].contains("libnvidia-glcore.so.535.54.03"));
+
+        let log = "- Mod 'Sodium Extra' (sodium-extra) 0.5.1 requires any version of sodium, which is missing!";
+        let diagnoses = diagnose(&parse(log), &rules);
+        assert_eq!(diagnoses[0].advice, ["Sodium Extra (sodium-extra) requires sodium, which is missing. Install sodium or remove Sodium Extra."]);
+
+        // A user rule replaces the bundled one with the same id
+        let user = vec![Rule {
+            id: "mixin-apply-failed-mod".to_string(),
+            pattern: "Mixin apply for mod (\\w+) failed".to_string(),
+            title: "Mixin".to_string(),
+            advice: "Blame $1".to_string(),
+        }];
+        let (rules, _) = compile_rules(user, bundled);
+        let diagnoses = diagnose(&parse(GAME_REPORT), &rules);
+        assert_eq!(diagnoses[0].advice, ["Blame sodiumextra"]);
+    }
+}
@@ -0,0 +1,462 @@
+//! Crash history per instance.
+//!
+//! A crash is either a report in the game's `crash-reports/` (or a JVM
+//! `hs_err_pid*.log`), or a game launched from the TUI exiting with a non-zero
+//! code. PrismLauncher runs the game detached from us, so the exit code is
+//! taken from the "Process exited with code" line it writes to its own log.
+//! Those exits are kept in `~/.local/share/minecraft-tui/crashes.json`, along
+//! with when each instance's crashes were last looked at.
+
+use crate::config::data_dir;
+use crate::logfiles::{crash_files, LogFile};
+use crate::logs::launcher_log;
+use crate::{crash, detail_line, format_timestamp, game_dir, step_selection, Instance};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Instances' crash folders are checked for new reports at most this often.
+const SCAN_INTERVAL: Duration = Duration::from_secs(5);
+/// Reports parsed for the history list.
+const MAX_HISTORY: usize = 100;
+
+/// A game launched from the TUI that exited with a non-zero code and left no report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitRecord {
+    /// The instance's folder name.
+    pub instance: String,
+    /// Milliseconds since the epoch.
+    pub time: u64,
+    pub code: i32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Store {
+    exits: Vec<ExitRecord>,
+    /// When each instance's crash history was last opened, keyed by folder name.
+    seen: BTreeMap<String, u64>,
+}
+
+impl Store {
+    fn path() -> PathBuf {
+        data_dir().join("crashes.json")
+    }
+
+    fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(text) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(path, text);
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// The code from the last "exited with code N" line, as written by
+/// PrismLauncher's `LoggedProcess`.
+fn exit_code(log: &str) -> Option<i32> {
+    log.lines().rev().find_map(|line| {
+        let (_, rest) = line.split_once("exited with code ")?;
+        rest.trim_end().trim_end_matches('.').parse().ok()
+    })
+}
+
+#[derive(Default)]
+pub struct CrashTracker {
+    store: Store,
+    /// The newest crash report of each instance, keyed by instance path, as of the last scan.
+    newest: HashMap<PathBuf, Option<u64>>,
+    /// How long the launcher log was when each tracked game was launched.
+    log_offsets: HashMap<PathBuf, u64>,
+    last_scan: Option<Instant>,
+}
+
+impl CrashTracker {
+    pub fn load() -> Self {
+        Self {
+            store: Store::load(),
+            ..Self::default()
+        }
+    }
+
+    /// Looks for crash reports written since the last scan. The first scan of
+    /// an instance only notes what's there. Returns a notification per crash.
+    pub fn scan(&mut self, instances: &[Instance]) -> Vec<String> {
+        let now = Instant::now();
+        if self.last_scan.is_some_and(|last| now.duration_since(last) < SCAN_INTERVAL) {
+            return Vec::new();
+        }
+        self.last_scan = Some(now);
+
+        let mut messages = Vec::new();
+        for instance in instances {
+            let newest = crash_files(&game_dir(&instance.path)).into_iter().max_by_key(|f| f.modified);
+            let time = newest.as_ref().and_then(|f| f.modified);
+            let previous = self.newest.insert(instance.path.clone(), time);
+            if let (Some(previous), Some(file)) = (previous, newest)
+                && time > previous
+            {
+                messages.push(match crash::load(&file.path).ok().and_then(|r| r.description) {
+                    Some(description) => format!("{} crashed: {}; C shows its crashes", instance.name, description),
+                    None => format!("{} crashed; C shows its crashes", instance.name),
+                });
+            }
+        }
+        messages
+    }
+
+    /// Remembers where the launcher log ends, so the exit of this launch can be found later.
+    pub fn launched(&mut self, instance: &Instance) {
+        let offset = launcher_log()
+            .and_then(|path| fs::metadata(path).ok())
+            .map(|meta| meta.len())
+            .unwrap_or(0);
+        self.log_offsets.insert(instance.path.clone(), offset);
+    }
+
+    /// Stops tracking a launch that never got as far as starting the game.
+    pub fn forget(&mut self, instance: &Instance) {
+        self.log_offsets.remove(&instance.path);
+    }
+
+    /// Checks how a game launched from the TUI ended, returning the exit code
+    /// PrismLauncher logged for it. A non-zero exit that left no crash report
+    /// is recorded as a crash; those with a report are picked up by
+    /// [`CrashTracker::scan`].
+    ///
+    /// The exit line doesn't say which instance it's for, so while another
+    /// tracked game is still running the code is unknown.
+    pub fn exited(&mut self, instance: &Instance, started: SystemTime) -> Option<i32> {
+        let offset = self.log_offsets.remove(&instance.path)?;
+        if !self.log_offsets.is_empty() {
+            return None;
+        }
+        let code = exit_code(&read_from(&launcher_log()?, offset))?;
+        if code == 0 {
+            return Some(code);
+        }
+        let started_ms = started.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let reported = crash_files(&game_dir(&instance.path))
+            .iter()
+            .any(|f| f.modified.is_some_and(|m| m >= started_ms));
+        if !reported {
+            self.store.exits.push(ExitRecord {
+                instance: instance.folder(),
+                time: now_ms(),
+                code,
+            });
+            self.store.save();
+        }
+        Some(code)
+    }
+
+    /// When the instance last crashed, in milliseconds since the epoch.
+    pub fn last_crash(&self, instance: &Instance) -> Option<u64> {
+        let report = self.newest.get(&instance.path).copied().flatten();
+        let folder = instance.folder();
+        let exit = self.store.exits.iter().filter(|e| e.instance == folder).map(|e| e.time).max();
+        report.max(exit)
+    }
+
+    /// Whether the instance crashed after it was last launched, and its
+    /// history hasn't been opened since.
+    pub fn is_unseen(&self, instance: &Instance) -> bool {
+        let Some(last) = self.last_crash(instance) else {
+            return false;
+        };
+        let seen = self.store.seen.get(&instance.folder()).copied();
+        last >= instance.last_played_ts.unwrap_or(0) && seen.is_none_or(|seen| last > seen)
+    }
+
+    pub fn mark_seen(&mut self, instance: &Instance) {
+        self.store.seen.insert(instance.folder(), now_ms());
+        self.store.save();
+    }
+
+    /// The instance's crash reports and recorded exits, newest first.
+    pub fn history(&self, instance: &Instance) -> Vec<Crash> {
+        let mut crashes: Vec<Crash> = crash_files(&game_dir(&instance.path))
+            .into_iter()
+            .map(|file| Crash {
+                time: file.modified,
+                kind: CrashKind::Report(file),
+                summary: None,
+            })
+            .collect();
+        let folder = instance.folder();
+        crashes.extend(self.store.exits.iter().filter(|e| e.instance == folder).map(|e| Crash {
+            time: Some(e.time),
+            kind: CrashKind::Exit(e.code),
+            summary: None,
+        }));
+        crashes.sort_by_key(|c| Reverse(c.time));
+        crashes.truncate(MAX_HISTORY);
+        for crash in &mut crashes {
+            if let CrashKind::Report(ref file) = crash.kind {
+                crash.summary = crash::load(&file.path).ok().map(|report| Summary {
+                    description: report.description,
+                    exception: report.exceptions.into_iter().next().map(|e| e.header),
+                    suspected_mods: report.suspected_mods,
+                });
+            }
+        }
+        crashes
+    }
+}
+
+fn read_from(path: &Path, offset: u64) -> String {
+    let mut bytes = Vec::new();
+    if let Ok(mut file) = File::open(path) {
+        // A log shorter than the offset was rotated; read the new one whole
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let start = if len < offset { 0 } else { offset };
+        if file.seek(SeekFrom::Start(start)).is_ok() {
+            let _ = file.read_to_end(&mut bytes);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[derive(Debug, Clone)]
+pub enum CrashKind {
+    Report(LogFile),
+    /// A non-zero exit code with no report.
+    Exit(i32),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub description: Option<String>,
+    pub exception: Option<String>,
+    pub suspected_mods: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Crash {
+    /// Milliseconds since the epoch.
+    pub time: Option<u64>,
+    pub kind: CrashKind,
+    pub summary: Option<Summary>,
+}
+
+impl Crash {
+    fn title(&self) -> String {
+        match self.kind {
+            CrashKind::Report(ref file) => file.name.clone(),
+            CrashKind::Exit(code) => format!("Exited with code {}", code),
+        }
+    }
+}
+
+pub struct CrashHistory {
+    pub instance: Instance,
+    pub crashes: Vec<Crash>,
+    pub state: ListState,
+}
+
+impl CrashHistory {
+    pub fn new(instance: Instance, crashes: Vec<Crash>) -> Self {
+        let mut state = ListState::default();
+        if !crashes.is_empty() {
+            state.select(Some(0));
+        }
+        Self { instance, crashes, state }
+    }
+
+    pub fn selected(&self) -> Option<&Crash> {
+        self.state.selected().and_then(|i| self.crashes.get(i))
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.crashes.len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.crashes.len(), false);
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, history: &mut CrashHistory) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let list_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" Crashes — {} ", history.instance.name));
+
+    if history.crashes.is_empty() {
+        let message = Paragraph::new("No crashes recorded for this instance")
+            .style(Style::default().fg(Color::Green))
+            .block(list_block);
+        f.render_widget(message, chunks[0]);
+    } else {
+        let items: Vec<ListItem> = history
+            .crashes
+            .iter()
+            .map(|crash| {
+                let mut info = vec![crash.time.map(format_timestamp).unwrap_or_else(|| "Unknown time".to_string())];
+                if let Some(description) = crash.summary.as_ref().and_then(|s| s.description.clone()) {
+                    info.push(description);
+                }
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled("✖ ", Style::default().fg(Color::Red)),
+                        Span::styled(crash.title(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+                    ]),
+                    Line::from(Span::styled(
+                        format!("  {}", info.join(" • ")),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(list_block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Rgb(50, 50, 80))
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, chunks[0], &mut history.state);
+    }
+
+    let mut lines = Vec::new();
+    if let Some(crash) = history.selected() {
+        if let Some(time) = crash.time {
+            lines.push(detail_line("When: ", format_timestamp(time)));
+        }
+        match crash.kind {
+            CrashKind::Report(ref file) => {
+                lines.push(detail_line("File: ", file.name.clone()));
+                let summary = crash.summary.clone().unwrap_or_default();
+                if let Some(description) = summary.description {
+                    lines.push(detail_line("Description: ", description));
+                }
+                if let Some(exception) = summary.exception {
+                    lines.push(detail_line("Exception: ", exception));
+                }
+                if !summary.suspected_mods.is_empty() {
+                    lines.push(detail_line("Suspected Mods: ", summary.suspected_mods.join(", ")));
+                }
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Enter opens it in the crash analyzer",
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            CrashKind::Exit(code) => {
+                lines.push(detail_line("Exit Code: ", code.to_string()));
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "The game left no crash report. Its log (L, then Files) usually shows why it stopped.",
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+        }
+    }
+    let details = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::White))
+                .title(" Details "),
+        );
+    f.render_widget(details, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_from_launcher_log() {
+        let log = "\
+2024-05-01 09:00:00.000 I | Process exited with code 0.
+2024-05-01 10:00:00.000 I | Launched instance in offline mode
+2024-05-01 10:05:00.000 C | Process exited with code -1073740791.
+";
+        assert_eq!(exit_code(log), Some(-1073740791));
+        assert_eq!(exit_code("Process exited with code 1"), Some(1));
+        assert_eq!(exit_code("Launched instance"), None);
+    }
+
+    #[test]
+    fn test_exit_code_unknown_with_another_game_running() {
+        let first = Instance {
+            path: PathBuf::from("/instances/Create"),
+            ..Instance::default()
+        };
+        let second = Instance {
+            path: PathBuf::from("/instances/Vanilla"),
+            ..Instance::default()
+        };
+        let mut tracker = CrashTracker::default();
+        tracker.log_offsets.insert(first.path.clone(), 0);
+        tracker.log_offsets.insert(second.path.clone(), 10);
+        assert_eq!(tracker.exited(&first, SystemTime::now()), None);
+        assert!(!tracker.log_offsets.contains_key(&first.path));
+        tracker.forget(&second);
+        assert!(tracker.log_offsets.is_empty());
+    }
+
+    #[test]
+    fn test_unseen_crash() {
+        let instance = Instance {
+            name: "Create".into(),
+            path: PathBuf::from("/instances/Create"),
+            last_played_ts: Some(1_000),
+            ..Instance::default()
+        };
+        let mut tracker = CrashTracker::default();
+        tracker.newest.insert(instance.path.clone(), Some(500));
+        // A crash from before the last launch is old news
+        assert_eq!(tracker.last_crash(&instance), Some(500));
+        assert!(!tracker.is_unseen(&instance));
+
+        tracker.store.exits.push(ExitRecord {
+            instance: "Create".into(),
+            time: 2_000,
+            code: 1,
+        });
+        assert_eq!(tracker.last_crash(&instance), Some(2_000));
+        assert!(tracker.is_unseen(&instance));
+
+        tracker.store.seen.insert("Create".into(), 3_000);
+        assert!(!tracker.is_unseen(&instance));
+    }
+}
@@ -0,0 +1,639 @@
+//! Data packs in a world's `datapacks/` folder and the data pack screen.
+//!
+//! Which packs are active is recorded in `level.dat` under `Data.DataPacks`,
+//! as `Enabled` and `Disabled` lists of ids like `file/MyPack.zip`. Packs in
+//! neither list are new and get enabled by the game the next time the world loads.
+
+use crate::nbt::{Document, Tag};
+use crate::version::McVersion;
+use crate::world::World;
+use crate::{detail_line, step_selection, Instance};
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use serde_json::Value;
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Data pack format introduced by each release, with the DataVersion of that release.
+const PACK_FORMATS: &[(i64, &str, i64)] = &[
+    (1519, "1.13", 4),
+    (2225, "1.15", 5),
+    (2578, "1.16.2", 6),
+    (2724, "1.17", 7),
+    (2860, "1.18", 8),
+    (2975, "1.18.2", 9),
+    (3105, "1.19", 10),
+    (3337, "1.19.4", 12),
+    (3463, "1.20", 15),
+    (3578, "1.20.2", 18),
+    (3698, "1.20.3", 26),
+    (3837, "1.20.5", 41),
+    (3953, "1.21", 48),
+    (4080, "1.21.2", 57),
+    (4189, "1.21.4", 61),
+    (4325, "1.21.5", 71),
+    (4435, "1.21.6", 80),
+    (4438, "1.21.7", 81),
+    (4554, "1.21.9", 88),
+];
+
+/// The data pack format a world's game version expects.
+pub fn world_pack_format(world: &World) -> Option<i64> {
+    if let Some(data_version) = world.data_version {
+        return PACK_FORMATS
+            .iter()
+            .rev()
+            .find(|(dv, _, _)| data_version >= *dv)
+            .map(|(_, _, format)| *format);
+    }
+    let version = McVersion::parse(world.version.as_deref()?);
+    PACK_FORMATS
+        .iter()
+        .rev()
+        .find(|(_, release, _)| version >= McVersion::parse(release))
+        .map(|(_, _, format)| *format)
+}
+
+/// The first release using `format`, for messages like "made for 1.20.2".
+fn release_for_format(format: i64) -> Option<&'static str> {
+    PACK_FORMATS
+        .iter()
+        .rev()
+        .find(|(_, _, f)| *f <= format)
+        .map(|(_, release, _)| *release)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackState {
+    Enabled,
+    Disabled,
+    /// In the folder but in neither list yet.
+    New,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PackMeta {
+    pub description: String,
+    pub pack_format: Option<i64>,
+    /// Inclusive range of formats the pack declares support for, if wider than `pack_format`.
+    pub supported: Option<(i64, i64)>,
+}
+
+impl PackMeta {
+    pub fn parse(json: &Value) -> Self {
+        let pack = &json["pack"];
+        let pack_format = pack["pack_format"].as_i64();
+        // 1.21.9 replaced pack_format with min_format/max_format, each a number or [major, minor]
+        let major = |v: &Value| v.as_i64().or_else(|| v.get(0)?.as_i64());
+        let supported = match (major(&pack["min_format"]), major(&pack["max_format"])) {
+            (Some(min), Some(max)) => Some((min, max)),
+            _ => format_range(&pack["supported_formats"]),
+        };
+        Self {
+            description: text_component(&pack["description"]),
+            pack_format: pack_format.or(supported.map(|(min, _)| min)),
+            supported,
+        }
+    }
+
+    pub fn supports(&self, format: i64) -> bool {
+        match (self.supported, self.pack_format) {
+            (Some((min, max)), _) => (min..=max).contains(&format),
+            (None, Some(pack_format)) => pack_format == format,
+            (None, None) => true,
+        }
+    }
+}
+
+/// `supported_formats`: a single format, `[min, max]` or `{min_inclusive, max_inclusive}`.
+fn format_range(value: &Value) -> Option<(i64, i64)> {
+    match value {
+        Value::Number(n) => n.as_i64().map(|f| (f, f)),
+        Value::Array(range) => Some((range.first()?.as_i64()?, range.get(1)?.as_i64()?)),
+        Value::Object(range) => Some((
+            range.get("min_inclusive")?.as_i64()?,
+            range.get("max_inclusive")?.as_i64()?,
+        )),
+        _ => None,
+    }
+}
+
+/// Flattens a JSON text component to plain text, dropping § formatting codes.
+fn text_component(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts.iter().map(text_component).collect(),
+        Value::Object(obj) => {
+            let mut text = obj
+                .get("text")
+                .or(obj.get("translate"))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if let Some(extra) = obj.get("extra") {
+                text.push_str(&text_component(extra));
+            }
+            text
+        }
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => String::new(),
+    };
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+pub struct DataPack {
+    /// File or folder name inside `datapacks/`.
+    pub name: String,
+    pub is_zip: bool,
+    pub meta: Result<PackMeta, String>,
+    pub state: PackState,
+}
+
+impl DataPack {
+    /// The id `level.dat` uses for this pack.
+    pub fn id(&self) -> String {
+        format!("file/{}", self.name)
+    }
+}
+
+fn read_pack_meta(path: &Path, is_zip: bool) -> Result<PackMeta> {
+    let content = if is_zip {
+        let file = fs::File::open(path)?;
+        let mut archive = zip::ZipArchive::new(file).context("not a valid zip")?;
+        let mut entry = archive.by_name("pack.mcmeta").context("no pack.mcmeta in zip")?;
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        content
+    } else {
+        fs::read_to_string(path.join("pack.mcmeta")).context("no pack.mcmeta")?
+    };
+    // Some packs are saved with a byte order mark
+    let json: Value = serde_json::from_str(content.trim_start_matches('\u{feff}')).context("invalid pack.mcmeta")?;
+    Ok(PackMeta::parse(&json))
+}
+
+/// Reads `Data.DataPacks.Enabled` and `Disabled`.
+fn pack_lists(level: &Tag) -> (Vec<String>, Vec<String>) {
+    let list = |key: &str| -> Vec<String> {
+        level
+            .path(&["Data", "DataPacks", key])
+            .and_then(Tag::as_list)
+            .map(|items| items.iter().filter_map(Tag::as_str).map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    (list("Enabled"), list("Disabled"))
+}
+
+/// Moves `id` into the `Enabled` or `Disabled` list, creating the lists if needed.
+fn set_pack_enabled(level: &mut Tag, id: &str, enabled: bool) -> Result<()> {
+    let Tag::Compound(root) = level else {
+        bail!("level.dat root is not a compound");
+    };
+    let Some((_, Tag::Compound(data))) = root.iter_mut().find(|(k, _)| k == "Data") else {
+        bail!("level.dat has no Data compound");
+    };
+    if !data.iter().any(|(k, _)| k == "DataPacks") {
+        data.push(("DataPacks".to_string(), Tag::Compound(Vec::new())));
+    }
+    let Some((_, Tag::Compound(packs))) = data.iter_mut().find(|(k, _)| k == "DataPacks") else {
+        bail!("DataPacks in level.dat is not a compound");
+    };
+    for key in ["Enabled", "Disabled"] {
+        if !packs.iter().any(|(k, _)| k == key) {
+            packs.push((key.to_string(), Tag::List(8, Vec::new())));
+        }
+    }
+    let (add_to, remove_from) = if enabled { ("Enabled", "Disabled") } else { ("Disabled", "Enabled") };
+    for (key, value) in packs.iter_mut() {
+        let Tag::List(element, items) = value else {
+            bail!("DataPacks.{} in level.dat is not a list", key);
+        };
+        if key == remove_from {
+            items.retain(|item| item.as_str() != Some(id));
+        } else if key == add_to && !items.iter().any(|item| item.as_str() == Some(id)) {
+            // Empty lists may carry the End type id; they become string lists now
+            *element = 8;
+            items.push(Tag::String(id.to_string()));
+        }
+    }
+    Ok(())
+}
+
+pub fn load_packs(world_dir: &Path, level: Option<&Tag>) -> Vec<DataPack> {
+    let (enabled, disabled) = level.map(pack_lists).unwrap_or_default();
+    let Ok(entries) = fs::read_dir(world_dir.join("datapacks")) else {
+        return Vec::new();
+    };
+    let mut packs: Vec<DataPack> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let is_zip = path.is_file() && name.to_lowercase().ends_with(".zip");
+            if !is_zip && !path.is_dir() {
+                return None;
+            }
+            let id = format!("file/{}", name);
+            let state = if enabled.contains(&id) {
+                PackState::Enabled
+            } else if disabled.contains(&id) {
+                PackState::Disabled
+            } else {
+                PackState::New
+            };
+            Some(DataPack {
+                meta: read_pack_meta(&path, is_zip).map_err(|e| format!("{:#}", e)),
+                name,
+                is_zip,
+                state,
+            })
+        })
+        .collect();
+    packs.sort_by_key(|pack| pack.name.to_lowercase());
+    packs
+}
+
+pub struct DatapackView {
+    pub instance_name: String,
+    /// Used to check whether the game is running before `level.dat` is rewritten.
+    pub instance_path: PathBuf,
+    pub world_name: String,
+    world_dir: PathBuf,
+    pub world_format: Option<i64>,
+    pub world_version: Option<String>,
+    pub packs: Vec<DataPack>,
+    /// Enabled packs that are not files, such as `vanilla` or mod-provided packs.
+    pub builtin: Vec<String>,
+    pub error: Option<String>,
+    pub state: ListState,
+    /// Whether `level.dat` has been copied aside yet this session.
+    backed_up: bool,
+}
+
+impl DatapackView {
+    pub fn open(instance: &Instance, world_dir: &Path, world: &World) -> Self {
+        let mut view = Self {
+            instance_name: instance.name.clone(),
+            instance_path: instance.path.clone(),
+            world_name: world.display_name().to_string(),
+            world_dir: world_dir.to_path_buf(),
+            world_format: world_pack_format(world),
+            world_version: world.version.clone(),
+            packs: Vec::new(),
+            builtin: Vec::new(),
+            error: None,
+            state: ListState::default(),
+            backed_up: false,
+        };
+        view.reload();
+        view
+    }
+
+    pub fn reload(&mut self) {
+        let level = Document::load(&self.world_dir.join("level.dat"));
+        let root = level.as_ref().ok().map(|doc| &doc.root);
+        self.error = level.as_ref().err().map(|e| format!("{:#}", e));
+        self.builtin = root
+            .map(|root| pack_lists(root).0)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|id| !id.starts_with("file/"))
+            .collect();
+        self.packs = load_packs(&self.world_dir, root);
+        let index = self.state.selected().unwrap_or(0).min(self.packs.len().saturating_sub(1));
+        self.state.select((!self.packs.is_empty()).then_some(index));
+    }
+
+    pub fn selected(&self) -> Option<&DataPack> {
+        self.state.selected().and_then(|i| self.packs.get(i))
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.packs.len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.packs.len(), false);
+    }
+
+    /// Enables the selected pack if it is disabled, otherwise disables it. The
+    /// first change of a session copies the old `level.dat` aside.
+    pub fn toggle(&mut self) -> Result<String> {
+        let pack = self.selected().context("No data pack selected")?;
+        let enable = pack.state == PackState::Disabled;
+        let (id, name) = (pack.id(), pack.name.clone());
+        let path = self.world_dir.join("level.dat");
+        let mut level = Document::load(&path)?;
+        set_pack_enabled(&mut level.root, &id, enable)?;
+        if self.backed_up {
+            level.save(&path)?;
+        } else {
+            level.save_with_backup(&path)?;
+            self.backed_up = true;
+        }
+        self.reload();
+        Ok(format!("{} {}", if enable { "Enabled" } else { "Disabled" }, name))
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, view: &mut DatapackView) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+
+    let title = format!(" Data Packs — {} ", view.world_name);
+    if view.packs.is_empty() {
+        let message = Paragraph::new("No data packs in this world's datapacks folder")
+            .style(Style::default().fg(Color::Yellow))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan))
+                    .title(title),
+            );
+        f.render_widget(message, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = view
+        .packs
+        .iter()
+        .map(|pack| {
+            let (mark, color) = match pack.state {
+                PackState::Enabled => ("● ", Color::Green),
+                PackState::Disabled => ("○ ", Color::DarkGray),
+                PackState::New => ("◌ ", Color::Cyan),
+            };
+            let mut spans = vec![
+                Span::styled(mark, Style::default().fg(color)),
+                Span::styled(pack.name.clone(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+            ];
+            match (&pack.meta, view.world_format) {
+                (Err(_), _) => spans.push(Span::styled(" ⚠", Style::default().fg(Color::Red))),
+                (Ok(meta), Some(format)) if !meta.supports(format) => {
+                    spans.push(Span::styled(" ⚠ format", Style::default().fg(Color::Yellow)))
+                }
+                _ => {}
+            }
+            let description = match &pack.meta {
+                Ok(meta) => meta.description.lines().next().unwrap_or_default().to_string(),
+                Err(e) => e.clone(),
+            };
+            ListItem::new(vec![
+                Line::from(spans),
+                Line::from(Span::styled(format!("  {}", description), Style::default().fg(Color::DarkGray))),
+            ])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 80))
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[0], &mut view.state);
+
+    let details = Paragraph::new(pack_details(view))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(" Pack Details "),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, chunks[1]);
+}
+
+fn pack_details(view: &DatapackView) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let world_format = match (view.world_format, &view.world_version) {
+        (Some(format), Some(version)) => format!("{} ({})", format, version),
+        (Some(format), None) => format.to_string(),
+        (None, _) => "Unknown".to_string(),
+    };
+    lines.push(detail_line("World Pack Format: ", world_format));
+    if !view.builtin.is_empty() {
+        lines.push(detail_line("Built-in: ", view.builtin.join(", ")));
+    }
+    if let Some(ref error) = view.error {
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+    lines.push(Line::from(""));
+
+    let Some(pack) = view.selected() else {
+        return lines;
+    };
+    lines.push(detail_line("Name: ", pack.name.clone()));
+    lines.push(detail_line("Type: ", if pack.is_zip { "Zip file" } else { "Folder" }));
+    lines.push(detail_line(
+        "Status: ",
+        match pack.state {
+            PackState::Enabled => "Enabled",
+            PackState::Disabled => "Disabled",
+            PackState::New => "New (enabled when the world next loads)",
+        },
+    ));
+
+    match &pack.meta {
+        Ok(meta) => {
+            let format = match (meta.supported, meta.pack_format) {
+                (Some((min, max)), _) if min != max => format!("{}–{}", min, max),
+                (_, Some(format)) => format!(
+                    "{}{}",
+                    format,
+                    release_for_format(format).map(|r| format!(" ({})", r)).unwrap_or_default()
+                ),
+                _ => "Unknown".to_string(),
+            };
+            lines.push(detail_line("Pack Format: ", format));
+            if let Some(world_format) = view.world_format
+                && !meta.supports(world_format)
+            {
+                lines.push(Line::from(Span::styled(
+                    format!("⚠ Made for a different version; this world uses format {}", world_format),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            lines.push(Line::from(""));
+            for line in meta.description.lines() {
+                lines.push(Line::from(line.to_string()));
+            }
+        }
+        Err(e) => lines.push(Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red)))),
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    #[test]
+    fn test_pack_meta() {
+        let meta = PackMeta::parse(&json!({"pack": {"pack_format": 15, "description": "§aGreen §rpack"}}));
+        assert_eq!(meta.description, "Green pack");
+        assert!(meta.supports(15));
+        assert!(!meta.supports(18));
+
+        let meta = PackMeta::parse(&json!({"pack": {
+            "pack_format": 15,
+            "supported_formats": {"min_inclusive": 15, "max_inclusive": 26},
+            "description": [{"text": "Multi"}, {"text": "part", "extra": ["!"]}]
+        }}));
+        assert_eq!(meta.description, "Multipart!");
+        assert!(meta.supports(18));
+
+        let meta = PackMeta::parse(&json!({"pack": {"min_format": [88, 0], "max_format": 90, "description": ""}}));
+        assert_eq!(meta.pack_format, Some(88));
+        assert!(meta.supports(90));
+    }
+
+    #[test]
+    fn test_world_pack_format() {
+        let world = World {
+            data_version: Some(3465),
+            ..Default::default()
+        };
+        assert_eq!(world_pack_format(&world), Some(15));
+        let world = World {
+            version: Some("1.20.4".into()),
+            ..Default::default()
+        };
+        assert_eq!(world_pack_format(&world), Some(26));
+    }
+
+    #[test]
+    fn test_load_and_toggle() {
+        let dir = std::env::temp_dir().join(format!("mctui-datapacks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let packs = dir.join("datapacks");
+        fs::create_dir_all(packs.join("folder_pack")).unwrap();
+        fs::write(
+            packs.join("folder_pack/pack.mcmeta"),
+            r#"{"pack": {"pack_format": 15, "description": "Folder"}}"#,
+        )
+        .unwrap();
+        let mut zip = zip::ZipWriter::new(fs::File::create(packs.join("zipped.zip")).unwrap());
+        zip.start_file("pack.mcmeta", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(br#"{"pack": {"pack_format": 10, "description": "Zipped"}}"#).unwrap();
+        zip.finish().unwrap();
+
+        let level = Document {
+            name: String::new(),
+            root: Tag::Compound(vec![(
+                "Data".into(),
+                Tag::Compound(vec![
+                    ("DataVersion".into(), Tag::Int(3465)),
+                    (
+                        "DataPacks".into(),
+                        Tag::Compound(vec![
+                            (
+                                "Enabled".into(),
+                                Tag::List(8, vec![Tag::String("vanilla".into()), Tag::String("file/zipped.zip".into())]),
+                            ),
+                            ("Disabled".into(), Tag::List(0, vec![])),
+                        ]),
+                    ),
+                ]),
+            )]),
+            compression: crate::nbt::Compression::Gzip,
+        };
+        level.save(&dir.join("level.dat")).unwrap();
+
+        let world = World::load(&dir);
+        let instance = Instance {
+            name: "Test".into(),
+            ..Instance::default()
+        };
+        let mut view = DatapackView::open(&instance, &dir, &world);
+        assert_eq!(view.world_format, Some(15));
+        assert_eq!(view.builtin, vec!["vanilla".to_string()]);
+        assert_eq!(view.packs.len(), 2);
+        assert_eq!(view.packs[0].state, PackState::New);
+        let zipped = &view.packs[1];
+        assert_eq!(zipped.state, PackState::Enabled);
+        assert_eq!(zipped.meta.as_ref().unwrap().description, "Zipped");
+        assert!(!zipped.meta.as_ref().unwrap().supports(15));
+
+        view.state.select(Some(1));
+        view.toggle().unwrap();
+        assert_eq!(view.packs[1].state, PackState::Disabled);
+        view.toggle().unwrap();
+        assert_eq!(view.packs[1].state, PackState::Enabled);
+        view.state.select(Some(0));
+        view.toggle().unwrap();
+        assert_eq!(view.packs[0].state, PackState::Disabled);
+
+        let saved = Document::load(&dir.join("level.dat")).unwrap();
+        assert_eq!(
+            pack_lists(&saved.root),
+            (
+                vec!["vanilla".to_string(), "file/zipped.zip".to_string()],
+                vec!["file/folder_pack".to_string()]
+            )
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_toggle_backs_up_level_dat_once() {
+        let dir = std::env::temp_dir().join(format!("mctui-datapacks-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("datapacks/pack")).unwrap();
+        fs::write(dir.join("datapacks/pack/pack.mcmeta"), r#"{"pack": {"pack_format": 15, "description": ""}}"#).unwrap();
+        let level = Document {
+            name: String::new(),
+            root: Tag::Compound(vec![("Data".into(), Tag::Compound(Vec::new()))]),
+            compression: crate::nbt::Compression::Gzip,
+        };
+        level.save(&dir.join("level.dat")).unwrap();
+
+        let world = World::load(&dir);
+        let mut view = DatapackView::open(&Instance::default(), &dir, &world);
+        let backups = || -> Vec<PathBuf> {
+            fs::read_dir(&dir)
+                .unwrap()
+                .map(|e| e.unwrap().path())
+                .filter(|path| path.to_string_lossy().ends_with(".bak"))
+                .collect()
+        };
+        view.toggle().unwrap();
+        assert_eq!(backups().len(), 1);
+        // Backups are named by the second, so move the first aside to tell a second one apart
+        fs::rename(&backups()[0], dir.join("level.dat.first.bak")).unwrap();
+        view.toggle().unwrap();
+        assert_eq!(backups().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,1080 @@
+//! Dedicated server folders managed alongside the launcher's instances.
+//!
+//! A server folder holds a server jar (vanilla, Paper, Fabric's launcher…) or,
+//! for Forge and NeoForge, the `unix_args.txt` their installer leaves behind,
+//! plus `server.properties`. Servers started from here run as children of the
+//! TUI with their console piped, so output keeps being collected while other
+//! screens are open; they are asked to stop when the TUI quits. Servers started
+//! some other way are still spotted by the same `/proc` scan used for games.
+
+use crate::config::DedicatedServerSettings;
+use crate::{detail_line, format_duration, ping, process, step_selection};
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Console lines kept per server.
+const LOG_LINES: usize = 5000;
+/// How often folders are checked for servers started outside the TUI.
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a server gets to save and stop when the TUI quits.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `server.properties`, keeping comments, order and untouched lines as they were.
+#[derive(Debug, Clone, Default)]
+pub struct Properties {
+    lines: Vec<PropertyLine>,
+}
+
+#[derive(Debug, Clone)]
+enum PropertyLine {
+    Other(String),
+    /// `raw` is the line as read, written back until the value changes.
+    Entry { key: String, value: String, raw: Option<String> },
+}
+
+impl Properties {
+    pub fn parse(text: &str) -> Self {
+        let lines = text
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                    return PropertyLine::Other(line.to_string());
+                }
+                let (key, value) = split_property(trimmed);
+                PropertyLine::Entry {
+                    key: unescape(key),
+                    value: unescape(value),
+                    raw: Some(line.to_string()),
+                }
+            })
+            .collect();
+        Properties { lines }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        Ok(Properties::parse(&String::from_utf8_lossy(&text)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let part = path.with_extension("properties.part");
+        fs::write(&part, self.to_text()).with_context(|| format!("writing {}", part.display()))?;
+        fs::rename(&part, path).with_context(|| format!("replacing {}", path.display()))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            match line {
+                PropertyLine::Other(line) | PropertyLine::Entry { raw: Some(line), .. } => text.push_str(line),
+                PropertyLine::Entry { key, value, raw: None } => {
+                    text.push_str(&escape(key, true));
+                    text.push('=');
+                    text.push_str(&escape(value, false));
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            PropertyLine::Entry { key: k, value, .. } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn set(&mut self, key: &str, new_value: &str) {
+        for line in &mut self.lines {
+            if let PropertyLine::Entry { key: k, value, raw } = line
+                && k == key
+            {
+                if value != new_value {
+                    *value = new_value.to_string();
+                    *raw = None;
+                }
+                return;
+            }
+        }
+        self.lines.push(PropertyLine::Entry {
+            key: key.to_string(),
+            value: new_value.to_string(),
+            raw: None,
+        });
+    }
+
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PropertyLine::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+                PropertyLine::Other(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Splits at the first unescaped `=`, `:` or whitespace, as Java's `Properties` does.
+fn split_property(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            let rest = line[i..].trim_start();
+            let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest).trim_start();
+            return (&line[..i], rest);
+        }
+    }
+    (line, "")
+}
+
+/// Undoes backslash escapes. Works in UTF-16 so `\uD83D\uDE00` pairs combine.
+fn unescape(text: &str) -> String {
+    let mut units: Vec<u16> = Vec::with_capacity(text.len());
+    let push = |units: &mut Vec<u16>, c: char| units.extend_from_slice(c.encode_utf16(&mut [0; 2]));
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            push(&mut units, c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => push(&mut units, '\t'),
+            Some('n') => push(&mut units, '\n'),
+            Some('r') => push(&mut units, '\r'),
+            Some('f') => push(&mut units, '\x0c'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                units.push(u16::from_str_radix(&hex, 16).unwrap_or(0xFFFD));
+            }
+            Some(c) => push(&mut units, c),
+            None => {}
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+/// Escapes as `Properties.store` would, with non-ASCII as `\uXXXX` so older
+/// servers that read the file as Latin-1 get it right.
+fn escape(text: &str, key: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            ' ' if key || i == 0 => out.push_str("\\ "),
+            '=' | ':' | '#' | '!' if key => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_ascii() && !c.is_ascii_control() => out.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// How a server folder is started.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartMethod {
+    /// `java -jar <jar> nogui`
+    Jar(String),
+    /// Forge and NeoForge since 1.17: `java @user_jvm_args.txt @<unix_args.txt> nogui`.
+    ArgsFile(PathBuf),
+    /// Only a loader installer is present; it has to be run first.
+    Installer(String),
+}
+
+impl StartMethod {
+    pub fn describe(&self) -> String {
+        match self {
+            StartMethod::Jar(jar) => jar.clone(),
+            StartMethod::ArgsFile(args) => {
+                let loader = if args.starts_with("libraries/net/neoforged") { "NeoForge" } else { "Forge" };
+                let version = args.parent().and_then(Path::file_name).unwrap_or_default();
+                format!("{} {}", loader, version.to_string_lossy())
+            }
+            StartMethod::Installer(jar) => format!("{} (not installed yet)", jar),
+        }
+    }
+}
+
+fn is_installer(jar: &str) -> bool {
+    jar.to_ascii_lowercase().contains("installer")
+}
+
+/// Works out how to start the server in `dir`; `jar` overrides the guess.
+pub fn start_method(dir: &Path, jar: Option<&str>) -> Result<StartMethod> {
+    if let Some(jar) = jar {
+        return Ok(StartMethod::Jar(jar.to_string()));
+    }
+    for loader in ["net/minecraftforge/forge", "net/neoforged/neoforge", "net/neoforged/forge"] {
+        let versions = dir.join("libraries").join(loader);
+        let Ok(entries) = fs::read_dir(&versions) else {
+            continue;
+        };
+        let mut found: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path().join("unix_args.txt"))
+            .filter(|args| args.is_file())
+            .collect();
+        found.sort();
+        if let Some(args) = found.pop() {
+            return Ok(StartMethod::ArgsFile(args.strip_prefix(dir).unwrap_or(&args).to_path_buf()));
+        }
+    }
+
+    let mut jars: Vec<String> = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".jar"))
+        .collect();
+    jars.sort();
+    for preferred in ["fabric-server-launch.jar", "quilt-server-launch.jar", "server.jar"] {
+        if jars.iter().any(|jar| jar == preferred) {
+            return Ok(StartMethod::Jar(preferred.to_string()));
+        }
+    }
+    let (installers, servers): (Vec<String>, Vec<String>) = jars.into_iter().partition(|jar| is_installer(jar));
+    match (servers.as_slice(), installers.first()) {
+        ([jar], _) => Ok(StartMethod::Jar(jar.clone())),
+        ([], Some(installer)) => Ok(StartMethod::Installer(installer.clone())),
+        ([], None) => bail!("No server jar in {}", dir.display()),
+        (several, _) => bail!(
+            "Several jars ({}); set \"jar\" for this server in config.json",
+            several.join(", ")
+        ),
+    }
+}
+
+/// Arguments that make a loader installer set up a server in the current folder.
+fn installer_args(jar: &str) -> Option<&'static [&'static str]> {
+    let jar = jar.to_ascii_lowercase();
+    if jar.starts_with("fabric-installer") {
+        Some(&["server", "-downloadMinecraft"])
+    } else if jar.starts_with("forge") || jar.starts_with("neoforge") {
+        Some(&["--installServer"])
+    } else {
+        None
+    }
+}
+
+/// `java` from the settings, split into program and JVM options.
+fn java_command(settings: &DedicatedServerSettings) -> Command {
+    let java = settings.java.as_deref().unwrap_or("java");
+    let mut words = java.split_whitespace();
+    let mut command = Command::new(words.next().unwrap_or("java"));
+    command.args(words).current_dir(&settings.path);
+    command
+}
+
+/// Runs the folder's loader installer to completion. Meant for a background task.
+pub fn run_installer(settings: &DedicatedServerSettings) -> Result<String> {
+    let StartMethod::Installer(jar) = start_method(&settings.path, settings.jar.as_deref())? else {
+        bail!("Nothing to install; the server is ready to start");
+    };
+    let Some(args) = installer_args(&jar) else {
+        bail!("Don't know how to run {}; run it by hand", jar);
+    };
+    let output = java_command(settings)
+        .arg("-jar")
+        .arg(&jar)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("running {}", jar))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last = stderr.lines().last().unwrap_or("no output");
+        bail!("{} failed ({}): {}", jar, output.status, last);
+    }
+    Ok(format!("Ran {}", jar))
+}
+
+/// `eula.txt`: `None` if the server hasn't written it yet.
+pub fn eula_accepted(dir: &Path) -> Option<bool> {
+    let text = fs::read_to_string(dir.join("eula.txt")).ok()?;
+    Some(Properties::parse(&text).get("eula") == Some("true"))
+}
+
+pub fn accept_eula(dir: &Path) -> Result<()> {
+    let path = dir.join("eula.txt");
+    let mut eula = fs::read_to_string(&path).map(|text| Properties::parse(&text)).unwrap_or_default();
+    eula.set("eula", "true");
+    fs::write(&path, eula.to_text()).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Removes ANSI escape sequences some servers print even into a pipe.
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+struct RunningServer {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    started: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Stopped,
+    /// Started from the TUI; its console is attached.
+    Running(Instant),
+    /// Running, but started some other way.
+    External,
+}
+
+/// Owns the servers started from the TUI and their console output.
+pub struct Supervisor {
+    running: HashMap<PathBuf, RunningServer>,
+    /// Console output by server folder, kept after the server stops.
+    logs: HashMap<PathBuf, VecDeque<String>>,
+    sender: mpsc::Sender<(PathBuf, String)>,
+    lines: mpsc::Receiver<(PathBuf, String)>,
+    external: HashSet<PathBuf>,
+    last_scan: Option<Instant>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        let (sender, lines) = mpsc::channel();
+        Self {
+            running: HashMap::new(),
+            logs: HashMap::new(),
+            sender,
+            lines,
+            external: HashSet::new(),
+            last_scan: None,
+        }
+    }
+}
+
+impl Supervisor {
+    pub fn status(&self, dir: &Path) -> Status {
+        match self.running.get(dir) {
+            Some(server) => Status::Running(server.started),
+            None if self.external.contains(dir) => Status::External,
+            None => Status::Stopped,
+        }
+    }
+
+    pub fn log(&self, dir: &Path) -> Option<&VecDeque<String>> {
+        self.logs.get(dir)
+    }
+
+    fn push_log(&mut self, dir: &Path, line: String) {
+        let log = self.logs.entry(dir.to_path_buf()).or_default();
+        if log.len() >= LOG_LINES {
+            log.pop_front();
+        }
+        log.push_back(line);
+    }
+
+    pub fn start(&mut self, name: &str, settings: &DedicatedServerSettings) -> Result<()> {
+        let dir = &settings.path;
+        if self.status(dir) != Status::Stopped {
+            bail!("{} is already running", name);
+        }
+        let args = match start_method(dir, settings.jar.as_deref())? {
+            StartMethod::Jar(jar) => vec!["-jar".to_string(), jar],
+            StartMethod::ArgsFile(args) => {
+                let mut command = Vec::new();
+                if dir.join("user_jvm_args.txt").is_file() {
+                    command.push("@user_jvm_args.txt".to_string());
+                }
+                command.push(format!("@{}", args.display()));
+                command
+            }
+            StartMethod::Installer(jar) => bail!("Run the installer first ({}, press I)", jar),
+        };
+        let mut child = java_command(settings)
+            .args(args)
+            .arg("nogui")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("starting Java (set \"java\" for this server in config.json)")?;
+        let stdin = child.stdin.take().context("no stdin")?;
+        for stream in [
+            child.stdout.take().map(|s| Box::new(s) as Box<dyn Read + Send>),
+            child.stderr.take().map(|s| Box::new(s) as Box<dyn Read + Send>),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let (sender, dir) = (self.sender.clone(), dir.clone());
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream);
+                let mut line = Vec::new();
+                while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+                    let text = strip_ansi(String::from_utf8_lossy(&line).trim_end());
+                    if sender.send((dir.clone(), text)).is_err() {
+                        break;
+                    }
+                    line.clear();
+                }
+            });
+        }
+        self.push_log(dir, format!("[minecraft-tui] Starting {}", name));
+        self.running.insert(
+            dir.clone(),
+            RunningServer {
+                name: name.to_string(),
+                child,
+                stdin,
+                started: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Types `command` into the server console.
+    pub fn send(&mut self, dir: &Path, command: &str) -> Result<()> {
+        let server = self.running.get_mut(dir).context("Server is not running")?;
+        writeln!(server.stdin, "{}", command)
+            .and_then(|_| server.stdin.flush())
+            .context("writing to the server console")
+    }
+
+    /// Asks the server to save and stop.
+    pub fn stop(&mut self, dir: &Path) -> Result<()> {
+        self.send(dir, "stop")
+    }
+
+    pub fn kill(&mut self, dir: &Path) -> Result<()> {
+        let server = self.running.get_mut(dir).context("Server is not running")?;
+        server.child.kill().context("killing the server")
+    }
+
+    /// Collects console output and notices servers exiting, returning a
+    /// message for each. `watched` folders are rescanned for outside servers.
+    pub fn poll(&mut self, watched: &[PathBuf]) -> (bool, Vec<String>) {
+        let mut changed = false;
+        while let Ok((dir, line)) = self.lines.try_recv() {
+            self.push_log(&dir, line);
+            changed = true;
+        }
+
+        let mut exited = Vec::new();
+        for (dir, server) in &mut self.running {
+            if let Ok(Some(status)) = server.child.try_wait() {
+                let message = match status.code() {
+                    Some(0) => format!("{} stopped", server.name),
+                    Some(code) => format!("{} stopped with exit code {}", server.name, code),
+                    None => format!("{} was killed", server.name),
+                };
+                exited.push((dir.clone(), message));
+            }
+        }
+        let mut messages = Vec::new();
+        for (dir, message) in exited {
+            self.running.remove(&dir);
+            self.push_log(&dir, format!("[minecraft-tui] {}", message));
+            messages.push(message);
+            changed = true;
+        }
+
+        let now = Instant::now();
+        if !watched.is_empty() && self.last_scan.is_none_or(|last| now.duration_since(last) >= SCAN_INTERVAL) {
+            self.last_scan = Some(now);
+            let running = process::java_running_in(watched);
+            let external: HashSet<PathBuf> = watched
+                .iter()
+                .zip(running)
+                .filter(|(dir, running)| *running && !self.running.contains_key(*dir))
+                .map(|(dir, _)| dir.clone())
+                .collect();
+            if external != self.external {
+                self.external = external;
+                changed = true;
+            }
+        }
+        (changed, messages)
+    }
+
+    /// Stops every attached server, waiting for each to save. Used when the
+    /// TUI quits, after the terminal has been restored.
+    pub fn shutdown(&mut self) {
+        for (dir, server) in &mut self.running {
+            println!("Stopping {}…", server.name);
+            let _ = writeln!(server.stdin, "stop").and_then(|_| server.stdin.flush());
+            let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+            loop {
+                match server.child.try_wait() {
+                    Ok(Some(_)) => break,
+                    Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(200)),
+                    _ => {
+                        eprintln!("{} did not stop in time; killing it ({})", server.name, dir.display());
+                        let _ = server.child.kill();
+                        let _ = server.child.wait();
+                        break;
+                    }
+                }
+            }
+        }
+        self.running.clear();
+    }
+}
+
+/// What the screen knows about each folder, read when it is (re)loaded.
+pub struct ServerEntry {
+    pub name: String,
+    pub settings: DedicatedServerSettings,
+    pub properties: Option<Properties>,
+    pub method: Result<StartMethod, String>,
+    pub eula: Option<bool>,
+}
+
+impl ServerEntry {
+    fn load(name: &str, settings: &DedicatedServerSettings) -> Self {
+        Self {
+            name: name.to_string(),
+            settings: settings.clone(),
+            properties: Properties::load(&settings.path.join("server.properties")).ok(),
+            method: start_method(&settings.path, settings.jar.as_deref()).map_err(|e| format!("{:#}", e)),
+            eula: eula_accepted(&settings.path),
+        }
+    }
+
+    /// The RCON address and password from `server.properties`, if RCON is on.
+    pub fn rcon(&self) -> Option<(String, String)> {
+        let properties = self.properties.as_ref()?;
+        if properties.get("enable-rcon") != Some("true") {
+            return None;
+        }
+        let port = properties.get("rcon.port").unwrap_or("25575");
+        let password = properties.get("rcon.password").unwrap_or_default();
+        Some((format!("localhost:{}", port), password.to_string()))
+    }
+}
+
+/// Which single-line prompt is open over the list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prompt {
+    AddFolder,
+    JavaCommand,
+}
+
+pub struct PropertiesEditor {
+    pub path: PathBuf,
+    pub properties: Properties,
+    pub state: ListState,
+    /// The value being typed for the selected key.
+    pub editing: Option<String>,
+    pub dirty: bool,
+}
+
+impl PropertiesEditor {
+    pub fn open(dir: &Path) -> Result<Self> {
+        let path = dir.join("server.properties");
+        let properties = if path.exists() {
+            Properties::load(&path)?
+        } else {
+            bail!("No server.properties yet; start the server once to create it");
+        };
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Ok(Self {
+            path,
+            properties,
+            state,
+            editing: None,
+            dirty: false,
+        })
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.properties.entries().len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.properties.entries().len(), false);
+    }
+
+    fn selected(&self) -> Option<(String, String)> {
+        let entries = self.properties.entries();
+        let (key, value) = entries.get(self.state.selected()?)?;
+        Some((key.to_string(), value.to_string()))
+    }
+
+    /// Flips a boolean in place; anything else opens for typing.
+    pub fn begin_edit(&mut self) {
+        let Some((key, value)) = self.selected() else {
+            return;
+        };
+        match value.as_str() {
+            "true" => self.set(&key, "false"),
+            "false" => self.set(&key, "true"),
+            _ => self.editing = Some(value),
+        }
+    }
+
+    pub fn apply_edit(&mut self) {
+        if let (Some(value), Some((key, _))) = (self.editing.take(), self.selected()) {
+            self.set(&key, &value);
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        if self.properties.get(key) != Some(value) {
+            self.properties.set(key, value);
+            self.dirty = true;
+        }
+    }
+
+    pub fn save(&mut self) -> Result<String> {
+        self.properties.save(&self.path)?;
+        self.dirty = false;
+        Ok("Saved server.properties; restart the server to apply it".to_string())
+    }
+}
+
+pub struct DedicatedView {
+    pub servers: Vec<ServerEntry>,
+    pub state: ListState,
+    pub prompt: Option<(Prompt, String)>,
+    pub editor: Option<PropertiesEditor>,
+    /// The console of the selected server is open.
+    pub console: bool,
+    pub input: String,
+    history: Vec<String>,
+    history_pos: Option<usize>,
+    /// Lines scrolled back from the bottom of the console.
+    pub scroll: usize,
+}
+
+impl DedicatedView {
+    pub fn open(servers: &std::collections::BTreeMap<String, DedicatedServerSettings>) -> Self {
+        let mut view = Self {
+            servers: Vec::new(),
+            state: ListState::default(),
+            prompt: None,
+            editor: None,
+            console: false,
+            input: String::new(),
+            history: Vec::new(),
+            history_pos: None,
+            scroll: 0,
+        };
+        view.reload(servers);
+        view
+    }
+
+    pub fn reload(&mut self, servers: &std::collections::BTreeMap<String, DedicatedServerSettings>) {
+        let selected = self.selected().map(|s| s.name.clone());
+        self.servers = servers.iter().map(|(name, settings)| ServerEntry::load(name, settings)).collect();
+        let index = selected
+            .and_then(|name| self.servers.iter().position(|s| s.name == name))
+            .or(if self.servers.is_empty() { None } else { Some(0) });
+        self.state.select(index.map(|i| i.min(self.servers.len().saturating_sub(1))));
+    }
+
+    pub fn selected(&self) -> Option<&ServerEntry> {
+        self.state.selected().and_then(|i| self.servers.get(i))
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.servers.len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.servers.len(), false);
+    }
+
+    pub fn watched(&self) -> Vec<PathBuf> {
+        self.servers.iter().map(|s| s.settings.path.clone()).collect()
+    }
+
+    /// Takes the typed console command, remembering it for ↑.
+    pub fn take_input(&mut self) -> Option<String> {
+        let input = std::mem::take(&mut self.input);
+        let command = input.trim();
+        let command = command.strip_prefix('/').unwrap_or(command).to_string();
+        self.history_pos = None;
+        self.scroll = 0;
+        if command.is_empty() {
+            return None;
+        }
+        if self.history.last() != Some(&command) {
+            self.history.push(command.clone());
+        }
+        Some(command)
+    }
+
+    pub fn history_previous(&mut self) {
+        let pos = match self.history_pos {
+            Some(0) => 0,
+            Some(pos) => pos - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.history_pos = Some(pos);
+        self.input = self.history[pos].clone();
+    }
+
+    pub fn history_next(&mut self) {
+        let Some(pos) = self.history_pos else {
+            return;
+        };
+        if pos + 1 < self.history.len() {
+            self.history_pos = Some(pos + 1);
+            self.input = self.history[pos + 1].clone();
+        } else {
+            self.history_pos = None;
+            self.input.clear();
+        }
+    }
+}
+
+fn status_span(status: Status) -> Span<'static> {
+    match status {
+        Status::Running(started) => Span::styled(
+            format!("● Running {}", format_duration(started.elapsed().as_secs())),
+            Style::default().fg(Color::Green),
+        ),
+        Status::External => Span::styled("● Running outside minecraft-tui", Style::default().fg(Color::Yellow)),
+        Status::Stopped => Span::styled("○ Stopped", Style::default().fg(Color::DarkGray)),
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, view: &mut DedicatedView, supervisor: &Supervisor) {
+    if view.console {
+        render_console(f, area, view, supervisor);
+        return;
+    }
+    if let Some(ref mut editor) = view.editor {
+        render_properties(f, area, editor);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Dedicated Servers ");
+    if view.servers.is_empty() {
+        let paragraph = Paragraph::new("No server folders yet; press a to add one")
+            .style(Style::default().fg(Color::Yellow))
+            .block(block)
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, chunks[0]);
+    } else {
+        let items: Vec<ListItem> = view
+            .servers
+            .iter()
+            .map(|server| {
+                ListItem::new(vec![
+                    Line::from(Span::styled(
+                        server.name.clone(),
+                        Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(vec![Span::raw("  "), status_span(supervisor.status(&server.settings.path))]),
+                ])
+            })
+            .collect();
+        let widget = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Rgb(50, 50, 80))
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(widget, chunks[0], &mut view.state);
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Blue))
+        .title(" Server Details ");
+    let mut lines = Vec::new();
+    if let Some(server) = view.selected() {
+        let label = Style::default().fg(Color::Cyan);
+        lines.push(Line::from(vec![
+            Span::styled("Status: ", label),
+            status_span(supervisor.status(&server.settings.path)),
+        ]));
+        lines.push(detail_line("Folder: ", server.settings.path.display().to_string()));
+        match server.method {
+            Ok(ref method) => lines.push(detail_line("Starts: ", method.describe())),
+            Err(ref error) => lines.push(Line::from(vec![
+                Span::styled("Starts: ", label),
+                Span::styled(error.clone(), Style::default().fg(Color::Red)),
+            ])),
+        }
+        lines.push(detail_line("Java: ", server.settings.java.clone().unwrap_or_else(|| "java".to_string())));
+        match server.eula {
+            Some(true) => {}
+            Some(false) => lines.push(Line::from(Span::styled(
+                "EULA not accepted yet (E to accept)",
+                Style::default().fg(Color::Yellow),
+            ))),
+            None => lines.push(Line::from(Span::styled(
+                "No eula.txt yet; the first start creates it",
+                Style::default().fg(Color::DarkGray),
+            ))),
+        }
+        lines.push(Line::from(""));
+        match server.properties {
+            Some(ref properties) => {
+                let get = |key: &str| properties.get(key).unwrap_or_default().to_string();
+                let mut motd = vec![Span::styled("MOTD: ", label)];
+                if let Some(line) = ping::styled_lines(&Value::String(get("motd")), Style::default().fg(Color::Gray))
+                    .into_iter()
+                    .next()
+                {
+                    motd.extend(line.spans);
+                }
+                lines.push(Line::from(motd));
+                lines.push(detail_line("Port: ", get("server-port")));
+                lines.push(detail_line("World: ", get("level-name")));
+                lines.push(detail_line("Game Mode: ", get("gamemode")));
+                lines.push(detail_line("Difficulty: ", get("difficulty")));
+                lines.push(detail_line("Max Players: ", get("max-players")));
+                lines.push(detail_line("Whitelist: ", get("white-list")));
+                let rcon = match server.rcon() {
+                    Some((address, _)) => address,
+                    None => "off".to_string(),
+                };
+                lines.push(detail_line("RCON: ", rcon));
+            }
+            None => lines.push(Line::from(Span::styled(
+                "No server.properties yet",
+                Style::default().fg(Color::DarkGray),
+            ))),
+        }
+    }
+    f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), chunks[1]);
+
+    if let Some((prompt, ref text)) = view.prompt {
+        let title = match prompt {
+            Prompt::AddFolder => " Add Server Folder ",
+            Prompt::JavaCommand => " Java Command ",
+        };
+        let popup = crate::centered_rect(70, 3, area);
+        let input = Paragraph::new(format!("{}█", text))
+            .style(Style::default().fg(Color::Yellow))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(title),
+            );
+        f.render_widget(Clear, popup);
+        f.render_widget(input, popup);
+    }
+}
+
+fn log_style(line: &str) -> Style {
+    if line.starts_with("[minecraft-tui]") {
+        Style::default().fg(Color::DarkGray)
+    } else if line.contains("ERROR]") || line.contains("/ERROR") {
+        Style::default().fg(Color::Red)
+    } else if line.contains("WARN]") || line.contains("/WARN") {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::Gray)
+    }
+}
+
+fn render_console(f: &mut Frame, area: Rect, view: &mut DedicatedView, supervisor: &Supervisor) {
+    let Some(server) = view.selected() else {
+        return;
+    };
+    let dir = server.settings.path.clone();
+    let status = supervisor.status(&dir);
+    let mut title = format!(" Console — {} ", server.name);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(area);
+
+    let lines: Vec<Line> = supervisor
+        .log(&dir)
+        .map(|log| {
+            log.iter()
+                .map(|line| Line::from(Span::styled(line.clone(), log_style(line))))
+                .collect()
+        })
+        .unwrap_or_default();
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    view.scroll = view.scroll.min(lines.len().saturating_sub(height));
+    let top = lines.len().saturating_sub(height + view.scroll);
+    if view.scroll > 0 {
+        title.push_str(&format!("↑{} ", view.scroll));
+    }
+    let output = Paragraph::new(lines).scroll((top as u16, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(title),
+    );
+    f.render_widget(output, chunks[0]);
+
+    let (prompt, color) = match status {
+        Status::Running(_) => (format!("> {}█", view.input), Color::White),
+        _ => ("Not running (s to start)".to_string(), Color::DarkGray),
+    };
+    let input = Paragraph::new(prompt).style(Style::default().fg(color)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(" Command "),
+    );
+    f.render_widget(input, chunks[1]);
+}
+
+fn render_properties(f: &mut Frame, area: Rect, editor: &mut PropertiesEditor) {
+    let entries = editor.properties.entries();
+    let width = entries.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    let selected = editor.state.selected();
+    let items: Vec<ListItem> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, (key, value))| {
+            let value = match editor.editing {
+                Some(ref text) if selected == Some(i) => Span::styled(
+                    format!("{}█", text),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
+                _ => Span::styled(value.to_string(), Style::default().fg(Color::White)),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<width$} = ", key, width = width), Style::default().fg(Color::Cyan)),
+                value,
+            ]))
+        })
+        .collect();
+    let title = format!(
+        " {}{} ",
+        editor.path.display(),
+        if editor.dirty { " (modified)" } else { "" }
+    );
+    let widget = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title),
+        )
+        .highlight_style(Style::default().bg(Color::Rgb(50, 50, 80)).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(widget, area, &mut editor.state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROPERTIES: &str = "#Minecraft server properties\n#Sat Oct 18 12:00:00 UTC 2025\nenable-rcon=false\nmotd=\\u00A7aA Minecraft Server\nlevel-seed=\nserver-port = 25565\n";
+
+    #[test]
+    fn test_properties_round_trip() {
+        let mut properties = Properties::parse(PROPERTIES);
+        assert_eq!(properties.to_text(), PROPERTIES);
+        assert_eq!(properties.get("motd"), Some("§aA Minecraft Server"));
+        assert_eq!(properties.get("level-seed"), Some(""));
+        assert_eq!(properties.get("server-port"), Some("25565"));
+
+        properties.set("motd", "§bHello, C:\\world");
+        properties.set("enable-rcon", "true");
+        properties.set("rcon.password", "pw");
+        let text = properties.to_text();
+        assert!(text.contains("motd=\\u00A7bHello, C:\\\\world\n"));
+        assert!(text.contains("enable-rcon=true\n"));
+        assert!(text.ends_with("rcon.password=pw\n"));
+        assert!(text.contains("server-port = 25565\n"));
+        assert_eq!(Properties::parse(&text).get("motd"), Some("§bHello, C:\\world"));
+    }
+
+    #[test]
+    fn test_start_method() {
+        let dir = std::env::temp_dir().join(format!("mctui-dedicated-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert!(start_method(&dir, None).is_err());
+
+        fs::write(dir.join("forge-1.20.1-47.2.0-installer.jar"), b"").unwrap();
+        assert_eq!(
+            start_method(&dir, None).unwrap(),
+            StartMethod::Installer("forge-1.20.1-47.2.0-installer.jar".into())
+        );
+        assert_eq!(installer_args("forge-1.20.1-47.2.0-installer.jar"), Some(&["--installServer"][..]));
+
+        let args = dir.join("libraries/net/minecraftforge/forge/1.20.1-47.2.0");
+        fs::create_dir_all(&args).unwrap();
+        fs::write(args.join("unix_args.txt"), b"").unwrap();
+        let method = start_method(&dir, None).unwrap();
+        assert_eq!(method.describe(), "Forge 1.20.1-47.2.0");
+        fs::remove_dir_all(dir.join("libraries")).unwrap();
+
+        fs::write(dir.join("paper-1.21.4-100.jar"), b"").unwrap();
+        assert_eq!(start_method(&dir, None).unwrap(), StartMethod::Jar("paper-1.21.4-100.jar".into()));
+        fs::write(dir.join("other.jar"), b"").unwrap();
+        assert!(start_method(&dir, None).is_err());
+        assert_eq!(
+            start_method(&dir, Some("other.jar")).unwrap(),
+            StartMethod::Jar("other.jar".into())
+        );
+        fs::write(dir.join("server.jar"), b"").unwrap();
+        assert_eq!(start_method(&dir, None).unwrap(), StartMethod::Jar("server.jar".into()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[0;32m[12:00:00 INFO]: Done\x1b[m"), "[12:00:00 INFO]: Done");
+    }
+}
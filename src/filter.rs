@@ -0,0 +1,191 @@
+//! Search query parsing for the instance list.
+//!
+//! Plain words match against the instance name. `after:` and `before:` take a
+//! date (`2023`, `2023-06`, `2023-06-12`) and match against the release
+//! date of the instance's Minecraft version from the meta catalog. `after:` is
+//! inclusive of the given period, `before:` is exclusive. `mc:` compares the
+//! Minecraft version itself: `mc:1.20` matches 1.20 and its point releases,
+//! `mc:>=1.20.5`, `mc:<1.19` and friends compare semantically.
+
+use crate::Instance;
+use crate::version::McVersion;
+use anyhow::{bail, Result};
+use std::cmp::Ordering;
+
+/// Checks that `value` is a `YYYY`, `YYYY-MM` or `YYYY-MM-DD` date, which
+/// compares against ISO release times as a plain string prefix.
+fn parse_date(key: &str, value: &str) -> Result<String> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let valid = parts.len() <= 3
+        && parts.iter().enumerate().all(|(i, part)| {
+            let (digits, range) = match i {
+                0 => (4, 0..=9999),
+                1 => (2, 1..=12),
+                _ => (2, 1..=31),
+            };
+            part.len() == digits
+                && part.bytes().all(|b| b.is_ascii_digit())
+                && part.parse().is_ok_and(|n: u32| range.contains(&n))
+        });
+    if !valid {
+        bail!("{} needs a date like 2023, 2023-06 or 2023-06-12, not \"{}\"", key, value);
+    }
+    Ok(value.to_string())
+}
+
+#[derive(Debug, Default)]
+pub struct Filter {
+    terms: Vec<String>,
+    released_after: Option<String>,
+    released_before: Option<String>,
+    versions: Vec<VersionFilter>,
+}
+
+#[derive(Debug)]
+enum VersionFilter {
+    Prefix(McVersion),
+    Compare(Vec<Ordering>, McVersion),
+}
+
+impl VersionFilter {
+    fn parse(spec: &str) -> Self {
+        let operators: [(&str, &[Ordering]); 5] = [
+            (">=", &[Ordering::Greater, Ordering::Equal]),
+            ("<=", &[Ordering::Less, Ordering::Equal]),
+            (">", &[Ordering::Greater]),
+            ("<", &[Ordering::Less]),
+            ("=", &[Ordering::Equal]),
+        ];
+        for (operator, orderings) in operators {
+            if let Some(version) = spec.strip_prefix(operator) {
+                return VersionFilter::Compare(orderings.to_vec(), McVersion::parse(version));
+            }
+        }
+        VersionFilter::Prefix(McVersion::parse(spec))
+    }
+
+    fn matches(&self, version: &McVersion) -> bool {
+        match self {
+            VersionFilter::Prefix(prefix) => version.starts_with(prefix),
+            VersionFilter::Compare(orderings, other) => orderings.contains(&version.cmp(other)),
+        }
+    }
+}
+
+impl Filter {
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut filter = Filter::default();
+        for token in query.split_whitespace() {
+            if let Some(date) = token.strip_prefix("after:") {
+                filter.released_after = Some(parse_date("after:", date)?);
+            } else if let Some(date) = token.strip_prefix("before:") {
+                filter.released_before = Some(parse_date("before:", date)?);
+            } else if let Some(spec) = token.strip_prefix("mc:") {
+                filter.versions.push(VersionFilter::parse(spec));
+            } else {
+                filter.terms.push(token.to_lowercase());
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, instance: &Instance) -> bool {
+        let name = instance.name.to_lowercase();
+        if !self.terms.iter().all(|term| name.contains(term.as_str())) {
+            return false;
+        }
+
+        let release_date = instance.version_status.release_date();
+        if let Some(ref after) = self.released_after
+            && release_date.is_none_or(|date| date < after.as_str())
+        {
+            return false;
+        }
+        if let Some(ref before) = self.released_before
+            && release_date.is_none_or(|date| date >= before.as_str())
+        {
+            return false;
+        }
+
+        if !self.versions.is_empty() {
+            let Some(version) = instance.mc_version.as_deref().map(McVersion::parse) else {
+                return false;
+            };
+            if !self.versions.iter().all(|filter| filter.matches(&version)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mixed_query() {
+        let filter = Filter::parse("Create after:2023 before:2024-06").unwrap();
+        assert_eq!(filter.terms, vec!["create".to_string()]);
+        assert_eq!(filter.released_after.as_deref(), Some("2023"));
+        assert_eq!(filter.released_before.as_deref(), Some("2024-06"));
+    }
+
+    #[test]
+    fn test_invalid_dates_are_errors() {
+        assert!(Filter::parse("after:2023-06-12").is_ok());
+        let invalid = ["after:foo", "after:2024-5", "before:2024-13", "before:2024-06-00", "after:", "after:2024-06-12-1"];
+        for query in invalid {
+            assert!(Filter::parse(query).is_err(), "{}", query);
+        }
+    }
+
+    fn instance_with_version(version: &str) -> Instance {
+        Instance {
+            name: version.to_string(),
+            mc_version: Some(version.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn instance(name: &str, release_time: Option<&str>) -> Instance {
+        let mut instance = Instance {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        instance.version_status.release_time = release_time.map(str::to_string);
+        instance
+    }
+
+    #[test]
+    fn test_matches_name_terms() {
+        let filter = Filter::parse("create mod").unwrap();
+        assert!(filter.matches(&instance("Create Modpack", None)));
+        assert!(!filter.matches(&instance("Create", None)));
+    }
+
+    #[test]
+    fn test_matches_release_dates() {
+        let filter = Filter::parse("after:2023 before:2024").unwrap();
+        assert!(filter.matches(&instance("a", Some("2023-01-01T00:00:00+00:00"))));
+        assert!(filter.matches(&instance("a", Some("2023-12-31T00:00:00+00:00"))));
+        assert!(!filter.matches(&instance("a", Some("2022-12-31T00:00:00+00:00"))));
+        assert!(!filter.matches(&instance("a", Some("2024-01-01T00:00:00+00:00"))));
+        assert!(!filter.matches(&instance("a", None)));
+    }
+
+    #[test]
+    fn test_matches_minecraft_version() {
+        let prefix = Filter::parse("mc:1.20").unwrap();
+        assert!(prefix.matches(&instance_with_version("1.20.4")));
+        assert!(!prefix.matches(&instance_with_version("1.2.5")));
+
+        let range = Filter::parse("mc:>=1.9 mc:<1.20.5").unwrap();
+        assert!(range.matches(&instance_with_version("1.20.4")));
+        assert!(range.matches(&instance_with_version("1.12.2")));
+        assert!(!range.matches(&instance_with_version("1.8.9")));
+        assert!(range.matches(&instance_with_version("24w14a")));
+        assert!(!range.matches(&instance_with_version("24w18a")));
+        assert!(!range.matches(&instance("no version", None)));
+    }
+}
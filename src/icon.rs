@@ -0,0 +1,455 @@
+//! Instance icons: loading PNGs from Prism's `icons/` folder and drawing them.
+//!
+//! Terminals that speak a graphics protocol (Kitty, iTerm2, Sixel) get the real
+//! image, painted straight to the terminal after ratatui has drawn the frame.
+//! Everywhere else the icon is rendered with half-block characters, two pixels
+//! per cell, in true color.
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Cursor, Write},
+    path::Path,
+    sync::Arc,
+};
+
+/// The most common opaque color of RGBA pixels; see [`Icon::dominant_color`].
+fn dominant_color(rgba: &[u8]) -> Option<Color> {
+    let mut buckets: HashMap<u16, (u32, [u32; 3])> = HashMap::new();
+    for p in rgba.chunks_exact(4).filter(|p| p[3] >= 128) {
+        let key = ((p[0] as u16 >> 4) << 8) | ((p[1] as u16 >> 4) << 4) | (p[2] as u16 >> 4);
+        let entry = buckets.entry(key).or_default();
+        entry.0 += 1;
+        for (sum, &channel) in entry.1.iter_mut().zip(p) {
+            *sum += channel as u32;
+        }
+    }
+    let (count, sum) = buckets.into_values().max_by_key(|(count, _)| *count)?;
+    Some(Color::Rgb(
+        (sum[0] / count) as u8,
+        (sum[1] / count) as u8,
+        (sum[2] / count) as u8,
+    ))
+}
+
+/// Size of the icon box in the details panel, in terminal cells.
+pub const ICON_COLS: u16 = 16;
+pub const ICON_ROWS: u16 = 8;
+
+pub struct Icon {
+    pub width: u32,
+    pub height: u32,
+    rgba: Vec<u8>,
+    png: Vec<u8>,
+    /// Worked out once on load, since the instance list shows it on every frame.
+    dominant_color: Option<Color>,
+}
+
+impl std::fmt::Debug for Icon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Icon({}x{})", self.width, self.height)
+    }
+}
+
+impl Icon {
+    /// Looks up `<icons_dir>/<key>.png`. Prism's built-in icons have no file and yield `None`.
+    pub fn load(icons_dir: &Path, key: &str) -> Option<Icon> {
+        let bytes = fs::read(icons_dir.join(format!("{}.png", key))).ok()?;
+        Icon::decode(bytes)
+    }
+
+    /// Decodes a base64 PNG as stored in `servers.dat`, with or without a `data:` URL prefix.
+    pub fn from_base64(text: &str) -> Option<Icon> {
+        let data = text.strip_prefix("data:image/png;base64,").unwrap_or(text);
+        Icon::decode(base64_decode(data)?)
+    }
+
+    pub fn decode(png: Vec<u8>) -> Option<Icon> {
+        let mut decoder = png::Decoder::new(Cursor::new(&png));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().ok()?;
+        let mut buf = vec![0; reader.output_buffer_size()?];
+        let info = reader.next_frame(&mut buf).ok()?;
+        buf.truncate(info.buffer_size());
+
+        let rgba = match info.color_type {
+            png::ColorType::Rgba => buf,
+            png::ColorType::Rgb => buf.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => buf.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            png::ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+            png::ColorType::Indexed => return None,
+        };
+
+        Some(Icon {
+            width: info.width,
+            height: info.height,
+            dominant_color: dominant_color(&rgba),
+            rgba,
+            png,
+        })
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.rgba[i], self.rgba[i + 1], self.rgba[i + 2], self.rgba[i + 3]]
+    }
+
+    /// Nearest-neighbour sample at `(x, y)` of a `cols` x `rows` grid laid over the image.
+    fn sample(&self, x: u32, y: u32, cols: u32, rows: u32) -> [u8; 4] {
+        let px = (x * self.width / cols).min(self.width - 1);
+        let py = (y * self.height / rows).min(self.height - 1);
+        self.pixel(px, py)
+    }
+
+    /// The most common opaque color, bucketed to 4 bits per channel and averaged within the bucket.
+    pub fn dominant_color(&self) -> Option<Color> {
+        self.dominant_color
+    }
+
+    /// Renders the icon as `rows` lines of `cols` half-block cells.
+    pub fn half_blocks(&self, cols: u16, rows: u16) -> Vec<Line<'static>> {
+        let (cols, pixel_rows) = (cols as u32, rows as u32 * 2);
+        (0..rows as u32)
+            .map(|row| {
+                let spans: Vec<Span> = (0..cols)
+                    .map(|col| {
+                        let top = self.sample(col, row * 2, cols, pixel_rows);
+                        let bottom = self.sample(col, row * 2 + 1, cols, pixel_rows);
+                        half_block_cell(top, bottom)
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect()
+    }
+
+    fn scaled(&self, width: u32, height: u32) -> Vec<[u8; 4]> {
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.sample(x, y, width, height))
+            .collect()
+    }
+}
+
+fn half_block_cell(top: [u8; 4], bottom: [u8; 4]) -> Span<'static> {
+    let rgb = |p: [u8; 4]| Color::Rgb(p[0], p[1], p[2]);
+    match (top[3] >= 128, bottom[3] >= 128) {
+        (true, true) => Span::styled("▀", Style::default().fg(rgb(top)).bg(rgb(bottom))),
+        (true, false) => Span::styled("▀", Style::default().fg(rgb(top))),
+        (false, true) => Span::styled("▄", Style::default().fg(rgb(bottom))),
+        (false, false) => Span::raw(" "),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Iterm,
+    Sixel,
+    HalfBlocks,
+}
+
+impl GraphicsProtocol {
+    /// Picks a protocol from the environment. `MINECRAFT_TUI_GRAPHICS` (kitty, iterm,
+    /// sixel, halfblocks) overrides detection; inside tmux we stay with half blocks.
+    pub fn detect() -> Self {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        match var("MINECRAFT_TUI_GRAPHICS").to_lowercase().as_str() {
+            "kitty" => return GraphicsProtocol::Kitty,
+            "iterm" => return GraphicsProtocol::Iterm,
+            "sixel" => return GraphicsProtocol::Sixel,
+            "halfblocks" | "none" => return GraphicsProtocol::HalfBlocks,
+            _ => {}
+        }
+
+        if env::var_os("TMUX").is_some() {
+            return GraphicsProtocol::HalfBlocks;
+        }
+
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+        if env::var_os("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || term_program == "ghostty" {
+            GraphicsProtocol::Kitty
+        } else if term_program == "iTerm.app" || term_program == "WezTerm" {
+            GraphicsProtocol::Iterm
+        } else if term.contains("foot") || term.contains("mlterm") || term.contains("sixel") {
+            GraphicsProtocol::Sixel
+        } else {
+            GraphicsProtocol::HalfBlocks
+        }
+    }
+}
+
+/// Keeps track of what image is on screen so it is only re-sent when it changes.
+pub struct IconPainter {
+    pub protocol: GraphicsProtocol,
+    shown: Option<(Rect, usize)>,
+}
+
+impl IconPainter {
+    pub fn new(protocol: GraphicsProtocol) -> Self {
+        Self { protocol, shown: None }
+    }
+
+    fn identity(placement: Option<&(Rect, Arc<Icon>)>) -> Option<(Rect, usize)> {
+        placement.map(|(rect, icon)| (*rect, Arc::as_ptr(icon) as usize))
+    }
+
+    /// Sixel and iTerm images live in the cells they were drawn over, and ratatui only
+    /// rewrites cells whose content changed. When the image moves or goes away the
+    /// screen has to be cleared and redrawn to get rid of it.
+    pub fn needs_clear(&self, placement: Option<&(Rect, Arc<Icon>)>) -> bool {
+        matches!(self.protocol, GraphicsProtocol::Sixel | GraphicsProtocol::Iterm)
+            && self.shown.is_some()
+            && self.shown != Self::identity(placement)
+    }
+
+    /// Forgets what is on screen, e.g. after the terminal was cleared.
+    pub fn reset(&mut self) {
+        self.shown = None;
+    }
+
+    pub fn paint(&mut self, out: &mut impl Write, placement: Option<&(Rect, Arc<Icon>)>) -> io::Result<()> {
+        let identity = Self::identity(placement);
+        if self.protocol == GraphicsProtocol::HalfBlocks || identity == self.shown {
+            return Ok(());
+        }
+
+        if self.protocol == GraphicsProtocol::Kitty && self.shown.is_some() {
+            write!(out, "\x1b_Ga=d,d=i,i=1,q=2\x1b\\")?;
+        }
+
+        if let Some((rect, icon)) = placement {
+            write!(out, "\x1b7\x1b[{};{}H", rect.y + 1, rect.x + 1)?;
+            match self.protocol {
+                GraphicsProtocol::Kitty => write_kitty(out, icon, *rect)?,
+                GraphicsProtocol::Iterm => write_iterm(out, icon, *rect)?,
+                GraphicsProtocol::Sixel => write_sixel(out, icon, *rect)?,
+                GraphicsProtocol::HalfBlocks => {}
+            }
+            write!(out, "\x1b8")?;
+        }
+        out.flush()?;
+        self.shown = identity;
+        Ok(())
+    }
+}
+
+fn write_kitty(out: &mut impl Write, icon: &Icon, rect: Rect) -> io::Result<()> {
+    let encoded = base64_encode(&icon.png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            write!(out, "\x1b_Ga=T,f=100,i=1,q=2,C=1,c={},r={},m={};", rect.width, rect.height, more)?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+fn write_iterm(out: &mut impl Write, icon: &Icon, rect: Rect) -> io::Result<()> {
+    write!(
+        out,
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        icon.png.len(),
+        rect.width,
+        rect.height,
+        base64_encode(&icon.png)
+    )
+}
+
+fn write_sixel(out: &mut impl Write, icon: &Icon, rect: Rect) -> io::Result<()> {
+    // Terminals that report their pixel size let us fill the box exactly
+    let (cell_w, cell_h) = match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.columns > 0 && size.height > 0 && size.rows > 0 => {
+            ((size.width / size.columns) as u32, (size.height / size.rows) as u32)
+        }
+        _ => (10, 20),
+    };
+    let side = (rect.width as u32 * cell_w).min(rect.height as u32 * cell_h);
+    out.write_all(sixel_encode(&icon.scaled(side, side), side, side).as_bytes())
+}
+
+/// Encodes RGBA pixels as a Sixel image using a fixed 6x6x6 color cube.
+/// Transparent pixels are left unpainted.
+fn sixel_encode(pixels: &[[u8; 4]], width: u32, height: u32) -> String {
+    let level = |c: u8| (c as u32 * 5 + 127) / 255;
+    let index = |p: [u8; 4]| -> Option<u32> {
+        (p[3] >= 128).then(|| level(p[0]) * 36 + level(p[1]) * 6 + level(p[2]))
+    };
+
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for i in 0..216 {
+        let percent = |l: u32| l * 100 / 5;
+        out.push_str(&format!("#{};2;{};{};{}", i, percent(i / 36), percent(i / 6 % 6), percent(i % 6)));
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut colors: Vec<u32> = rows
+            .clone()
+            .flat_map(|y| (0..width).filter_map(move |x| index(pixels[(y * width + x) as usize])))
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+
+        for color in colors {
+            out.push_str(&format!("#{}", color));
+            let sixels: Vec<u8> = (0..width)
+                .map(|x| {
+                    let bits = rows.clone().fold(0u8, |bits, y| {
+                        if index(pixels[(y * width + x) as usize]) == Some(color) {
+                            bits | 1 << (y - band)
+                        } else {
+                            bits
+                        }
+                    });
+                    b'?' + bits
+                })
+                .collect();
+            push_sixel_run_length(&mut out, &sixels);
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+fn push_sixel_run_length(out: &mut String, sixels: &[u8]) {
+    let mut i = 0;
+    while i < sixels.len() {
+        let run = sixels[i..].iter().take_while(|&&s| s == sixels[i]).count();
+        if run > 3 {
+            out.push_str(&format!("!{}{}", run, sixels[i] as char));
+        } else {
+            out.extend(std::iter::repeat_n(sixels[i] as char, run));
+        }
+        i += run;
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - i * 6) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes standard base64, skipping line breaks. `None` on any other invalid character.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for &c in text.as_bytes() {
+        let value = match c {
+            b'=' => break,
+            b'\r' | b'\n' => continue,
+            _ => BASE64_ALPHABET.iter().position(|&a| a == c)? as u32,
+        };
+        bits = bits << 6 | value;
+        count += 1;
+        if count == 4 {
+            out.extend([(bits >> 16) as u8, (bits >> 8) as u8, bits as u8]);
+            bits = 0;
+            count = 0;
+        }
+    }
+    match count {
+        2 => out.push((bits >> 4) as u8),
+        3 => out.extend([(bits >> 10) as u8, (bits >> 2) as u8]),
+        _ => {}
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_png(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        let data: Vec<u8> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| pixel(x, y))
+            .collect();
+        writer.write_image_data(&data).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_decode_and_dominant_color() {
+        // Three quarters green, one quarter red, plus a transparent corner that must be ignored
+        let png = test_png(4, 4, |x, y| match (x, y) {
+            (0, 0) => [0, 0, 0, 0],
+            (_, 0) => [200, 0, 0, 255],
+            _ => [0, 160, 0, 255],
+        });
+        let icon = Icon::decode(png).unwrap();
+        assert_eq!((icon.width, icon.height), (4, 4));
+        assert_eq!(icon.dominant_color(), Some(Color::Rgb(0, 160, 0)));
+    }
+
+    #[test]
+    fn test_half_blocks_shape() {
+        let icon = Icon::decode(test_png(8, 8, |_, y| if y < 4 { [255, 0, 0, 255] } else { [0, 0, 0, 0] })).unwrap();
+        let lines = icon.half_blocks(4, 2);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].spans.len(), 4);
+        assert_eq!(lines[0].spans[0].content, "▀");
+        assert_eq!(lines[1].spans[0].content, " ");
+    }
+
+    #[test]
+    fn test_sixel_encode() {
+        let pixels = vec![[255, 255, 255, 255]; 2 * 6];
+        let sixel = sixel_encode(&pixels, 2, 6);
+        assert!(sixel.starts_with("\x1bP0;1;0q\"1;1;2;6"));
+        // White is the last entry of the cube, all six bits set in both columns
+        assert!(sixel.ends_with("#215~~$-\x1b\\"));
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foobar", &[0, 255, 128, 7]] {
+            assert_eq!(base64_decode(&base64_encode(data)).as_deref(), Some(data));
+        }
+        let png = test_png(2, 2, |_, _| [1, 2, 3, 255]);
+        let text = format!("data:image/png;base64,{}", base64_encode(&png));
+        assert_eq!(Icon::from_base64(&text).map(|icon| icon.width), Some(2));
+        assert!(base64_decode("Zm9v!").is_none());
+    }
+}
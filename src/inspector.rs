@@ -0,0 +1,594 @@
+//! Tree view and scalar editor for NBT files: `level.dat`, `playerdata/*.dat`
+//! and the instance's `servers.dat`.
+//!
+//! Edits stay in memory until saved. Saving first copies the file on disk to
+//! `<name>.<timestamp>.bak`, then writes the tree back with the file's original
+//! compression and tag order.
+
+use crate::nbt::{Document, Tag};
+use crate::world::{difficulty_name, format_day_time, game_mode_name};
+use crate::Instance;
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Where a node sits in the tree, from the root down.
+type NodePath = Vec<Segment>;
+
+pub struct NbtFile {
+    pub label: String,
+    pub path: PathBuf,
+}
+
+/// The NBT files that belong to a world, plus the instance's server list.
+pub fn world_files(game_dir: &Path, world_folder: &str) -> Vec<NbtFile> {
+    let world_dir = game_dir.join("saves").join(world_folder);
+    let mut files = vec![NbtFile {
+        label: "level.dat".to_string(),
+        path: world_dir.join("level.dat"),
+    }];
+
+    let mut players: Vec<PathBuf> = fs::read_dir(world_dir.join("playerdata"))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "dat"))
+                .collect()
+        })
+        .unwrap_or_default();
+    players.sort();
+    files.extend(players.into_iter().map(|path| NbtFile {
+        label: format!("playerdata/{}", path.file_name().unwrap_or_default().to_string_lossy()),
+        path,
+    }));
+
+    let servers = game_dir.join("servers.dat");
+    if servers.is_file() {
+        files.push(NbtFile {
+            label: "servers.dat".to_string(),
+            path: servers,
+        });
+    }
+    files
+}
+
+pub enum Input {
+    Search(String),
+    Edit(String),
+}
+
+pub struct Inspector {
+    pub instance_name: String,
+    /// Used to check whether the game is running before a file is saved.
+    pub instance_path: PathBuf,
+    pub files: Vec<NbtFile>,
+    pub file: usize,
+    pub document: Option<Document>,
+    pub error: Option<String>,
+    /// The tree differs from the file on disk.
+    pub dirty: bool,
+    pub input: Option<Input>,
+    pub query: String,
+    expanded: HashSet<NodePath>,
+    /// Paths of the rows currently visible, in display order.
+    rows: Vec<NodePath>,
+    pub state: ListState,
+}
+
+impl Inspector {
+    pub fn open(instance: &Instance, files: Vec<NbtFile>) -> Self {
+        let mut inspector = Self {
+            instance_name: instance.name.clone(),
+            instance_path: instance.path.clone(),
+            files,
+            file: 0,
+            document: None,
+            error: None,
+            dirty: false,
+            input: None,
+            query: String::new(),
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            state: ListState::default(),
+        };
+        inspector.load();
+        inspector
+    }
+
+    pub fn current_file(&self) -> &NbtFile {
+        &self.files[self.file]
+    }
+
+    /// (Re)reads the current file, dropping any unsaved edits.
+    pub fn load(&mut self) {
+        self.dirty = false;
+        self.expanded.clear();
+        match Document::load(&self.current_file().path) {
+            Ok(document) => {
+                // level.dat keeps everything under a single "Data" compound; open it straight away
+                if let Tag::Compound(entries) = &document.root
+                    && let [(key, Tag::Compound(_))] = entries.as_slice()
+                {
+                    self.expanded.insert(vec![Segment::Key(key.clone())]);
+                }
+                self.document = Some(document);
+                self.error = None;
+            }
+            Err(e) => {
+                self.document = None;
+                self.error = Some(format!("{:#}", e));
+            }
+        }
+        self.state.select(None);
+        self.rebuild_rows();
+    }
+
+    pub fn next_file(&mut self) -> Result<()> {
+        if self.dirty {
+            bail!("Unsaved changes: w to save or u to revert first");
+        }
+        self.file = (self.file + 1) % self.files.len();
+        self.load();
+        Ok(())
+    }
+
+    fn rebuild_rows(&mut self) {
+        let selected = self.selected_path().cloned();
+        self.rows.clear();
+        if let Some(document) = &self.document {
+            walk(&document.root, &mut Vec::new(), Some(&self.expanded), &mut self.rows);
+        }
+        let index = selected
+            .and_then(|path| self.rows.iter().position(|row| *row == path))
+            .or_else(|| (!self.rows.is_empty()).then(|| self.state.selected().unwrap_or(0).min(self.rows.len() - 1)));
+        self.state.select(index);
+    }
+
+    fn selected_path(&self) -> Option<&NodePath> {
+        self.state.selected().and_then(|i| self.rows.get(i))
+    }
+
+    fn selected_tag(&self) -> Option<&Tag> {
+        node(&self.document.as_ref()?.root, self.selected_path()?)
+    }
+
+    pub fn next(&mut self) {
+        crate::step_selection(&mut self.state, self.rows.len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        crate::step_selection(&mut self.state, self.rows.len(), false);
+    }
+
+    /// Expands or collapses the selected compound or list.
+    pub fn toggle(&mut self) {
+        let Some(path) = self.selected_path().cloned() else {
+            return;
+        };
+        if !self.selected_tag().is_some_and(is_container) {
+            return;
+        }
+        if !self.expanded.remove(&path) {
+            self.expanded.insert(path);
+        }
+        self.rebuild_rows();
+    }
+
+    /// Collapses the selected node, or moves up to its parent if it is already closed.
+    pub fn collapse(&mut self) {
+        let Some(path) = self.selected_path().cloned() else {
+            return;
+        };
+        if !self.expanded.remove(&path) && path.len() > 1 {
+            let parent = path[..path.len() - 1].to_vec();
+            self.state.select(self.rows.iter().position(|row| *row == parent));
+        }
+        self.rebuild_rows();
+    }
+
+    /// Selects the next node after the current one whose key or value contains
+    /// the search query, opening its parents.
+    pub fn find_next(&mut self) -> Result<()> {
+        let Some(document) = &self.document else {
+            return Ok(());
+        };
+        let query = self.query.to_lowercase();
+        if query.is_empty() {
+            return Ok(());
+        }
+        let mut all = Vec::new();
+        walk(&document.root, &mut Vec::new(), None, &mut all);
+        let start = self
+            .selected_path()
+            .and_then(|current| all.iter().position(|path| path == current))
+            .map_or(0, |i| i + 1);
+        let found = (0..all.len())
+            .map(|offset| &all[(start + offset) % all.len()])
+            .find(|path| matches(&document.root, path, &query))
+            .cloned();
+        let Some(found) = found else {
+            bail!("No match for \"{}\"", self.query);
+        };
+        for depth in 1..found.len() {
+            self.expanded.insert(found[..depth].to_vec());
+        }
+        self.rebuild_rows();
+        self.state.select(self.rows.iter().position(|row| *row == found));
+        Ok(())
+    }
+
+    /// Starts editing the selected value if it is a number or string.
+    pub fn begin_edit(&mut self) -> Result<()> {
+        let Some(tag) = self.selected_tag() else {
+            return Ok(());
+        };
+        if !tag.is_scalar() {
+            bail!("Only numbers and strings can be edited");
+        }
+        self.input = Some(Input::Edit(edit_text(tag)));
+        Ok(())
+    }
+
+    /// Replaces the selected value with `text` parsed as the same tag type.
+    pub fn apply_edit(&mut self, text: &str) -> Result<String> {
+        let path = self.selected_path().cloned().context("Nothing selected")?;
+        let document = self.document.as_mut().context("No file loaded")?;
+        let tag = node_mut(&mut document.root, &path).context("Value no longer exists")?;
+        let value = tag.parse_like(text)?;
+        if *tag == value {
+            return Ok("Value unchanged".to_string());
+        }
+        *tag = value;
+        self.dirty = true;
+        Ok(format!("Set {} = {} (w to save)", path_label(&path), text.trim()))
+    }
+
+    /// Copies the file on disk aside, then writes the edited tree over it.
+    pub fn save(&mut self) -> Result<String> {
+        let document = self.document.as_ref().context("No file loaded")?;
+        if !self.dirty {
+            return Ok("No changes to save".to_string());
+        }
+        let file = &self.files[self.file];
+        let backup = document.save_with_backup(&file.path)?;
+        self.dirty = false;
+        Ok(match backup.as_deref().and_then(Path::file_name) {
+            Some(backup) => format!("Saved {}; the previous version is {}", file.label, backup.to_string_lossy()),
+            None => format!("Saved {}", file.label),
+        })
+    }
+}
+
+fn is_container(tag: &Tag) -> bool {
+    matches!(tag, Tag::Compound(_) | Tag::List(..))
+}
+
+fn child<'a>(tag: &'a Tag, segment: &Segment) -> Option<&'a Tag> {
+    match (tag, segment) {
+        (Tag::Compound(entries), Segment::Key(key)) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+        (Tag::List(_, items), Segment::Index(i)) => items.get(*i),
+        _ => None,
+    }
+}
+
+fn node<'a>(root: &'a Tag, path: &[Segment]) -> Option<&'a Tag> {
+    path.iter().try_fold(root, child)
+}
+
+fn node_mut<'a>(root: &'a mut Tag, path: &[Segment]) -> Option<&'a mut Tag> {
+    let mut tag = root;
+    for segment in path {
+        tag = match (tag, segment) {
+            (Tag::Compound(entries), Segment::Key(key)) => {
+                entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)?
+            }
+            (Tag::List(_, items), Segment::Index(i)) => items.get_mut(*i)?,
+            _ => return None,
+        };
+    }
+    Some(tag)
+}
+
+/// Collects node paths depth-first. With `expanded`, only descends into open
+/// containers (the visible rows); without, visits the whole tree.
+fn walk(tag: &Tag, path: &mut NodePath, expanded: Option<&HashSet<NodePath>>, out: &mut Vec<NodePath>) {
+    let children: Vec<(Segment, &Tag)> = match tag {
+        Tag::Compound(entries) => entries.iter().map(|(k, v)| (Segment::Key(k.clone()), v)).collect(),
+        Tag::List(_, items) => items.iter().enumerate().map(|(i, v)| (Segment::Index(i), v)).collect(),
+        _ => return,
+    };
+    for (segment, value) in children {
+        path.push(segment);
+        out.push(path.clone());
+        if is_container(value) && expanded.is_none_or(|open| open.contains(path)) {
+            walk(value, path, expanded, out);
+        }
+        path.pop();
+    }
+}
+
+fn matches(root: &Tag, path: &[Segment], query: &str) -> bool {
+    let key_matches = matches!(path.last(), Some(Segment::Key(key)) if key.to_lowercase().contains(query));
+    key_matches || node(root, path).is_some_and(|tag| tag.is_scalar() && edit_text(tag).to_lowercase().contains(query))
+}
+
+/// "Data.GameRules.keepInventory", "Inventory[0].id"
+fn path_label(path: &[Segment]) -> String {
+    let mut label = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !label.is_empty() {
+                    label.push('.');
+                }
+                label.push_str(key);
+            }
+            Segment::Index(i) => label.push_str(&format!("[{}]", i)),
+        }
+    }
+    label
+}
+
+/// A scalar's value as the user would type it.
+fn edit_text(tag: &Tag) -> String {
+    match tag {
+        Tag::Float(v) => v.to_string(),
+        Tag::Double(v) => v.to_string(),
+        Tag::String(s) => s.clone(),
+        other => other.as_i64().map(|v| v.to_string()).unwrap_or_default(),
+    }
+}
+
+fn summary(tag: &Tag) -> String {
+    match tag {
+        Tag::String(s) => format!("{:?}", s),
+        Tag::ByteArray(v) => format!("[{} bytes]", v.len()),
+        Tag::IntArray(v) => format!("[{} ints]", v.len()),
+        Tag::LongArray(v) => format!("[{} longs]", v.len()),
+        Tag::List(_, items) => match items.first() {
+            Some(first) => format!("[{} × {}]", items.len(), first.type_name()),
+            None => "[]".to_string(),
+        },
+        Tag::Compound(entries) if entries.len() == 1 => "{1 entry}".to_string(),
+        Tag::Compound(entries) => format!("{{{} entries}}", entries.len()),
+        scalar => edit_text(scalar),
+    }
+}
+
+/// A readable meaning for well-known level.dat and player values.
+fn hint(key: &str, tag: &Tag) -> Option<String> {
+    let value = tag.as_i64()?;
+    Some(match key {
+        "GameType" | "playerGameType" | "previousPlayerGameType" => game_mode_name(value).to_string(),
+        "Difficulty" => difficulty_name(value).to_string(),
+        "DayTime" => format_day_time(value),
+        "raining" | "thundering" | "hardcore" | "allowCommands" | "DifficultyLocked" => {
+            (if value != 0 { "yes" } else { "no" }).to_string()
+        }
+        _ => return None,
+    })
+}
+
+pub fn render(f: &mut Frame, area: Rect, inspector: &mut Inspector) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
+        .split(area);
+
+    let files: Vec<ListItem> = inspector
+        .files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| {
+            let style = if i == inspector.file {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            ListItem::new(Span::styled(file.label.clone(), style))
+        })
+        .collect();
+    let file_list = List::new(files).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(format!(" {} ", inspector.instance_name)),
+    );
+    f.render_widget(file_list, chunks[0]);
+
+    let tree_area = if inspector.input.is_some() {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)])
+            .split(chunks[1]);
+        render_input(f, split[1], inspector);
+        split[0]
+    } else {
+        chunks[1]
+    };
+
+    let title = format!(
+        " {}{} ",
+        inspector.current_file().label,
+        if inspector.dirty { " ● modified" } else { "" }
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if inspector.dirty { Color::Yellow } else { Color::Blue }))
+        .title(title);
+
+    let Some(document) = &inspector.document else {
+        let message = inspector.error.clone().unwrap_or_default();
+        let paragraph = Paragraph::new(message)
+            .style(Style::default().fg(Color::Red))
+            .block(block)
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, tree_area);
+        return;
+    };
+
+    let items: Vec<ListItem> = inspector
+        .rows
+        .iter()
+        .filter_map(|path| {
+            let tag = node(&document.root, path)?;
+            let key = match path.last()? {
+                Segment::Key(key) => key.clone(),
+                Segment::Index(i) => format!("[{}]", i),
+            };
+            let marker = match (is_container(tag), inspector.expanded.contains(path)) {
+                (false, _) => "  ",
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+            };
+            let mut spans = vec![
+                Span::raw("  ".repeat(path.len() - 1)),
+                Span::styled(marker, Style::default().fg(Color::DarkGray)),
+                Span::styled(key.clone(), Style::default().fg(Color::Cyan)),
+                Span::raw(": "),
+                Span::styled(summary(tag), Style::default().fg(Color::White)),
+            ];
+            if let Some(hint) = hint(&key, tag) {
+                spans.push(Span::styled(format!("  ({})", hint), Style::default().fg(Color::Green)));
+            }
+            spans.push(Span::styled(format!("  {}", tag.type_name()), Style::default().fg(Color::DarkGray)));
+            Some(ListItem::new(Line::from(spans)))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 80))
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, tree_area, &mut inspector.state);
+}
+
+fn render_input(f: &mut Frame, area: Rect, inspector: &Inspector) {
+    let (title, text) = match &inspector.input {
+        Some(Input::Search(text)) => (" Search keys and values (Enter to find, Esc to cancel) ".to_string(), text),
+        Some(Input::Edit(text)) => {
+            let target = inspector.selected_path().map(|path| path_label(path)).unwrap_or_default();
+            let kind = inspector.selected_tag().map_or("", Tag::type_name);
+            (format!(" Edit {} ({}) — Enter to apply, Esc to cancel ", target, kind), text)
+        }
+        None => return,
+    };
+    let input = Paragraph::new(format!("{}█", text))
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(title),
+        );
+    f.render_widget(input, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nbt::Compression;
+
+    fn level() -> Document {
+        let data = Tag::Compound(vec![
+            ("LevelName".into(), Tag::String("Test".into())),
+            ("GameType".into(), Tag::Int(0)),
+            (
+                "GameRules".into(),
+                Tag::Compound(vec![("keepInventory".into(), Tag::String("false".into()))]),
+            ),
+            ("Pos".into(), Tag::List(6, vec![Tag::Double(1.5), Tag::Double(64.0)])),
+        ]);
+        Document {
+            name: String::new(),
+            root: Tag::Compound(vec![("Data".into(), data)]),
+            compression: Compression::Gzip,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mctui-inspector-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn open(dir: &Path) -> Inspector {
+        let path = dir.join("level.dat");
+        level().save(&path).unwrap();
+        Inspector::open(
+            &Instance::default(),
+            vec![NbtFile {
+                label: "level.dat".into(),
+                path,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_tree_and_search() {
+        let dir = temp_dir("tree");
+        let mut inspector = open(&dir);
+        // Data is opened automatically, GameRules is not
+        assert_eq!(inspector.rows.len(), 5);
+
+        inspector.query = "keepinv".into();
+        inspector.find_next().unwrap();
+        assert_eq!(path_label(inspector.selected_path().unwrap()), "Data.GameRules.keepInventory");
+        assert_eq!(inspector.rows.len(), 6);
+
+        inspector.collapse();
+        assert_eq!(path_label(inspector.selected_path().unwrap()), "Data.GameRules");
+        inspector.collapse();
+        assert_eq!(inspector.rows.len(), 5);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_edit_and_save() {
+        let dir = temp_dir("edit");
+        let mut inspector = open(&dir);
+        inspector.query = "GameType".into();
+        inspector.find_next().unwrap();
+        assert!(inspector.apply_edit("creative").is_err());
+        inspector.apply_edit("1").unwrap();
+        assert!(inspector.dirty);
+        assert!(inspector.next_file().is_err());
+
+        inspector.save().unwrap();
+        let saved = Document::load(&dir.join("level.dat")).unwrap();
+        assert_eq!(saved.compression, Compression::Gzip);
+        assert_eq!(saved.root.path(&["Data", "GameType"]), Some(&Tag::Int(1)));
+        assert_eq!(saved.root.path(&["Data", "LevelName"]).and_then(Tag::as_str), Some("Test"));
+
+        let backups: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(Document::load(&backups[0].path()).unwrap(), level());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
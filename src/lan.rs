@@ -0,0 +1,189 @@
+//! Finding worlds opened to LAN.
+//!
+//! A game with an open LAN world multicasts `[MOTD]<text>[/MOTD][AD]<port>[/AD]`
+//! to 224.0.2.60:4445 every second and a half. The listener runs on a
+//! background thread while the server list is open; games that stop
+//! announcing drop off after a few seconds.
+
+use anyhow::{Context, Result};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 2, 60);
+pub const PORT: u16 = 4445;
+/// Games not heard from for this long are taken to have closed.
+const EXPIRY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanGame {
+    /// Usually `<player> - <world name>`.
+    pub motd: String,
+    /// `ip:port` to join.
+    pub address: String,
+    pub last_seen: Instant,
+}
+
+/// Whether a failed read only means nothing arrived before the timeout.
+fn is_timeout(error: &std::io::Error) -> bool {
+    matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted)
+}
+
+/// Reads the MOTD and port out of an announcement.
+pub fn parse_announcement(message: &str) -> Option<(String, u16)> {
+    let between = |start: &str, end: &str| {
+        let from = message.find(start)? + start.len();
+        let to = message[from..].find(end)? + from;
+        Some(&message[from..to])
+    };
+    let motd = between("[MOTD]", "[/MOTD]")?;
+    let port = between("[AD]", "[/AD]")?.trim().parse().ok()?;
+    Some((motd.to_string(), port))
+}
+
+pub struct LanListener {
+    pub games: Vec<LanGame>,
+    /// Why the listener stopped, if the socket failed.
+    pub error: Option<String>,
+    announcements: mpsc::Receiver<Result<(String, String), String>>,
+}
+
+impl LanListener {
+    /// Listens on the LAN discovery group.
+    pub fn open() -> Result<Self> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        // The game listens on the same port when its multiplayer screen is open
+        socket.set_reuse_address(true)?;
+        socket
+            .bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, PORT).into())
+            .with_context(|| format!("listening on port {}", PORT))?;
+        socket
+            .join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED)
+            .context("joining the LAN discovery group")?;
+        Ok(Self::listen(socket.into()))
+    }
+
+    /// Collects announcements arriving on `socket` until the listener is dropped.
+    pub fn listen(socket: UdpSocket) -> Self {
+        let (sender, announcements) = mpsc::channel();
+        thread::spawn(move || {
+            // The timeout lets the thread notice the listener is gone
+            let _ = socket.set_read_timeout(Some(Duration::from_secs(1)));
+            let mut buf = [0; 1024];
+            loop {
+                let (len, from) = match socket.recv_from(&mut buf) {
+                    Ok(received) => received,
+                    Err(e) if is_timeout(&e) => {
+                        if sender.send(Ok((String::new(), String::new()))).is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        let _ = sender.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+                let message = String::from_utf8_lossy(&buf[..len]);
+                let Some((motd, port)) = parse_announcement(&message) else {
+                    continue;
+                };
+                let address = match from {
+                    SocketAddr::V4(from) => format!("{}:{}", from.ip(), port),
+                    SocketAddr::V6(from) => format!("[{}]:{}", from.ip(), port),
+                };
+                if sender.send(Ok((motd, address))).is_err() {
+                    return;
+                }
+            }
+        });
+        Self {
+            games: Vec::new(),
+            error: None,
+            announcements,
+        }
+    }
+
+    /// Takes in new announcements and forgets games that went quiet.
+    /// Returns true if the list changed.
+    pub fn poll(&mut self) -> bool {
+        let now = Instant::now();
+        let before = self.games.len();
+        let mut changed = false;
+        while let Ok(announcement) = self.announcements.try_recv() {
+            let (motd, address) = match announcement {
+                Ok(announcement) => announcement,
+                Err(error) => {
+                    self.error = Some(error);
+                    changed = true;
+                    continue;
+                }
+            };
+            // Empty messages are the thread's heartbeat
+            if address.is_empty() {
+                continue;
+            }
+            match self.games.iter_mut().find(|g| g.address == address) {
+                Some(game) => {
+                    changed |= game.motd != motd;
+                    game.motd = motd;
+                    game.last_seen = now;
+                }
+                None => {
+                    self.games.push(LanGame {
+                        motd,
+                        address,
+                        last_seen: now,
+                    });
+                    changed = true;
+                }
+            }
+        }
+        self.games.retain(|g| now.duration_since(g.last_seen) < EXPIRY);
+        changed || self.games.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_announcement() {
+        assert_eq!(
+            parse_announcement("[MOTD]Steve - New World[/MOTD][AD]40321[/AD]"),
+            Some(("Steve - New World".to_string(), 40321))
+        );
+        assert_eq!(parse_announcement("[MOTD]x[/MOTD]"), None);
+        assert_eq!(parse_announcement("[MOTD]x[/MOTD][AD]port[/AD]"), None);
+    }
+
+    #[test]
+    fn test_listener_on_loopback() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let target = socket.local_addr().unwrap();
+        let mut listener = LanListener::listen(socket);
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for message in ["[MOTD]Alex - Skyblock[/MOTD][AD]40321[/AD]", "not an announcement"] {
+            sender.send_to(message.as_bytes(), target).unwrap();
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while listener.games.is_empty() && Instant::now() < deadline {
+            listener.poll();
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(listener.games.len(), 1);
+        assert_eq!(listener.games[0].motd, "Alex - Skyblock");
+        assert_eq!(listener.games[0].address, "127.0.0.1:40321");
+
+        listener.games[0].last_seen -= EXPIRY;
+        assert!(listener.poll());
+        assert!(listener.games.is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+//! Launches made from the TUI, kept in `~/.local/share/minecraft-tui/launches.jsonl`.
+//!
+//! A line is appended when a game is launched, and again, complete, when a
+//! followed game exits; the last line for a launch wins. Games launched with
+//! `Enter` quit the TUI, so only their start is known. The history screen
+//! filters launches by instance and date and adds up the time played, counted
+//! separately from PrismLauncher's own `totalTimePlayed`.
+
+use crate::backup::civil_from_days;
+use crate::config::data_dir;
+use crate::{detail_line, format_duration, format_size, step_selection, Instance};
+use anyhow::{Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const DAY_MS: u64 = 86_400_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Launch {
+    /// The instance's folder name.
+    pub instance: String,
+    pub name: String,
+    /// Milliseconds since the epoch. Together with the instance, identifies the launch.
+    pub start: u64,
+    /// What it was launched into, such as `server play.example.net`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// The most resident memory the game was seen using, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_memory: Option<u64>,
+}
+
+impl Launch {
+    pub fn new(instance: &Instance, target: Option<String>) -> Self {
+        Self {
+            instance: instance.folder(),
+            name: instance.name.clone(),
+            start: now_ms(),
+            target,
+            end: None,
+            exit_code: None,
+            peak_memory: None,
+        }
+    }
+
+    pub fn finish(&mut self, exit_code: Option<i32>, peak_memory: Option<u64>) {
+        self.end = Some(now_ms());
+        self.exit_code = exit_code;
+        self.peak_memory = peak_memory;
+    }
+
+    /// How long the game ran, in seconds, if it was followed to the end.
+    pub fn duration(&self) -> Option<u64> {
+        self.end.map(|end| end.saturating_sub(self.start) / 1000)
+    }
+
+    fn crashed(&self) -> bool {
+        self.exit_code.is_some_and(|code| code != 0)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Formats milliseconds since the epoch as `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_date(ms: u64) -> String {
+    let secs = ms / 1000;
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let rem = secs % 86400;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, rem / 3600, rem % 3600 / 60)
+}
+
+fn path() -> PathBuf {
+    data_dir().join("launches.jsonl")
+}
+
+/// Appends a launch, or the latest state of one, to the history.
+pub fn record(launch: &Launch) -> Result<()> {
+    let path = path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(launch)?).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Every recorded launch, newest first.
+pub fn load() -> Vec<Launch> {
+    parse(&fs::read_to_string(path()).unwrap_or_default())
+}
+
+/// Keeps the last line for each launch; lines that don't parse are skipped.
+fn parse(text: &str) -> Vec<Launch> {
+    let mut latest: HashMap<(String, u64), Launch> = HashMap::new();
+    for launch in text.lines().filter_map(|line| serde_json::from_str::<Launch>(line).ok()) {
+        latest.insert((launch.instance.clone(), launch.start), launch);
+    }
+    let mut launches: Vec<Launch> = latest.into_values().collect();
+    launches.sort_by(|a, b| b.start.cmp(&a.start).then_with(|| a.instance.cmp(&b.instance)));
+    launches
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    All,
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    fn next(self) -> Self {
+        match self {
+            Period::All => Period::Day,
+            Period::Day => Period::Week,
+            Period::Week => Period::Month,
+            Period::Month => Period::All,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Period::All => "All time",
+            Period::Day => "Last 24 hours",
+            Period::Week => "Last 7 days",
+            Period::Month => "Last 30 days",
+        }
+    }
+
+    fn days(self) -> Option<u64> {
+        match self {
+            Period::All => None,
+            Period::Day => Some(1),
+            Period::Week => Some(7),
+            Period::Month => Some(30),
+        }
+    }
+}
+
+pub struct HistoryView {
+    launches: Vec<Launch>,
+    /// Folder and display name of every instance with launches.
+    instances: Vec<(String, String)>,
+    /// Index into `instances`, or `None` for all of them.
+    instance: Option<usize>,
+    pub period: Period,
+    /// Only launches whose start date begins with this, such as `2024-05`.
+    pub date: String,
+    /// The date being typed.
+    pub date_input: Option<String>,
+    /// Indices into `launches` that pass the filters.
+    shown: Vec<usize>,
+    pub state: ListState,
+}
+
+impl HistoryView {
+    /// Opens the history filtered to the given instance, if it has any launches.
+    pub fn new(launches: Vec<Launch>, instance: Option<&Instance>) -> Self {
+        let mut instances: Vec<(String, String)> = Vec::new();
+        for launch in &launches {
+            if !instances.iter().any(|(folder, _)| *folder == launch.instance) {
+                instances.push((launch.instance.clone(), launch.name.clone()));
+            }
+        }
+        instances.sort_by_key(|(_, name)| name.to_lowercase());
+        let folder = instance.map(Instance::folder);
+        let mut view = Self {
+            instance: instances.iter().position(|(f, _)| Some(f) == folder.as_ref()),
+            launches,
+            instances,
+            period: Period::All,
+            date: String::new(),
+            date_input: None,
+            shown: Vec::new(),
+            state: ListState::default(),
+        };
+        view.apply(now_ms());
+        view
+    }
+
+    fn apply(&mut self, now: u64) {
+        let folder = self.instance.map(|i| self.instances[i].0.as_str());
+        let since = self.period.days().map(|days| now.saturating_sub(days * DAY_MS));
+        self.shown = self
+            .launches
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| folder.is_none_or(|f| l.instance == f))
+            .filter(|(_, l)| since.is_none_or(|since| l.start >= since))
+            .filter(|(_, l)| format_date(l.start).starts_with(&self.date))
+            .map(|(i, _)| i)
+            .collect();
+        self.state.select(if self.shown.is_empty() { None } else { Some(0) });
+    }
+
+    pub fn cycle_instance(&mut self) {
+        self.instance = match self.instance {
+            None if !self.instances.is_empty() => Some(0),
+            Some(i) if i + 1 < self.instances.len() => Some(i + 1),
+            _ => None,
+        };
+        self.apply(now_ms());
+    }
+
+    pub fn cycle_period(&mut self) {
+        self.period = self.period.next();
+        self.apply(now_ms());
+    }
+
+    /// Applies the typed date; an empty one clears the filter.
+    pub fn finish_date(&mut self) {
+        if let Some(date) = self.date_input.take() {
+            self.date = date.trim().to_string();
+            self.apply(now_ms());
+        }
+    }
+
+    fn shown(&self) -> impl Iterator<Item = &Launch> {
+        self.shown.iter().map(|&i| &self.launches[i])
+    }
+
+    pub fn selected(&self) -> Option<&Launch> {
+        self.state.selected().and_then(|i| self.shown.get(i)).map(|&i| &self.launches[i])
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.shown.len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.shown.len(), false);
+    }
+
+    fn title(&self) -> String {
+        let instance = self.instance.map_or("All instances", |i| self.instances[i].1.as_str());
+        let mut title = format!(" Launch History — {} • {}", instance, self.period.label());
+        if !self.date.is_empty() {
+            title.push_str(&format!(" • {}", self.date));
+        }
+        title.push(' ');
+        title
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, view: &mut HistoryView) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(area);
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(if view.date_input.is_some() { 3 } else { 0 })])
+        .split(chunks[0]);
+
+    let list_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(view.title());
+
+    if view.shown.is_empty() {
+        let message = if view.launches.is_empty() {
+            "No launches yet — games launched from here are recorded"
+        } else {
+            "No launches match the filters"
+        };
+        let message = Paragraph::new(message).style(Style::default().fg(Color::Yellow)).block(list_block);
+        f.render_widget(message, left[0]);
+    } else {
+        let dim = Style::default().fg(Color::DarkGray);
+        let items: Vec<ListItem> = view
+            .shown()
+            .map(|launch| {
+                let mut title_spans = vec![Span::styled(
+                    launch.name.clone(),
+                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                )];
+                if let Some(ref target) = launch.target {
+                    title_spans.push(Span::styled(format!(" → {}", target), Style::default().fg(Color::Cyan)));
+                }
+                if launch.crashed() {
+                    title_spans.push(Span::styled(
+                        format!(" ✖ exit {}", launch.exit_code.unwrap_or_default()),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                let mut info = vec![format_date(launch.start)];
+                info.push(launch.duration().map_or("not followed".to_string(), format_duration));
+                if let Some(memory) = launch.peak_memory {
+                    info.push(format!("{} peak", format_size(memory)));
+                }
+                ListItem::new(vec![
+                    Line::from(title_spans),
+                    Line::from(Span::styled(format!("  {}", info.join(" • ")), dim)),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(list_block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Rgb(50, 50, 80))
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, left[0], &mut view.state);
+    }
+
+    if let Some(ref input) = view.date_input {
+        let input = Paragraph::new(format!("{}█", input)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(" Date (YYYY, YYYY-MM or YYYY-MM-DD, UTC) "),
+        );
+        f.render_widget(input, left[1]);
+    }
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(8), Constraint::Min(0)])
+        .split(chunks[1]);
+
+    let launches: Vec<&Launch> = view.shown().collect();
+    let played: u64 = launches.iter().filter_map(|l| l.duration()).sum();
+    let followed = launches.iter().filter(|l| l.end.is_some()).count();
+    let crashes = launches.iter().filter(|l| l.crashed()).count();
+    let mut summary = vec![
+        detail_line("Launches: ", launches.len().to_string()),
+        detail_line("Time Played: ", format_duration(played)),
+        detail_line("Followed to the End: ", format!("{} of {}", followed, launches.len())),
+    ];
+    if let Some(longest) = launches.iter().filter_map(|l| l.duration()).max() {
+        summary.push(detail_line("Longest: ", format_duration(longest)));
+    }
+    summary.push(detail_line("Crashes: ", crashes.to_string()));
+    if let Some(memory) = launches.iter().filter_map(|l| l.peak_memory).max() {
+        summary.push(detail_line("Most Memory: ", format_size(memory)));
+    }
+    let summary = Paragraph::new(summary).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title(" Summary "),
+    );
+    f.render_widget(summary, right[0]);
+
+    let mut lines = Vec::new();
+    if let Some(launch) = view.selected() {
+        lines.push(detail_line("Instance: ", launch.name.clone()));
+        if let Some(ref target) = launch.target {
+            lines.push(detail_line("Launched Into: ", target.clone()));
+        }
+        lines.push(detail_line("Started: ", format!("{} UTC", format_date(launch.start))));
+        match launch.end {
+            Some(end) => {
+                lines.push(detail_line("Ended: ", format!("{} UTC", format_date(end))));
+                lines.push(detail_line("Played: ", format_duration(launch.duration().unwrap_or_default())));
+            }
+            None => lines.push(detail_line(
+                "Ended: ",
+                "unknown (launched with Enter, or the TUI was closed first)",
+            )),
+        }
+        if let Some(code) = launch.exit_code {
+            lines.push(detail_line("Exit Code: ", code.to_string()));
+        }
+        if let Some(memory) = launch.peak_memory {
+            lines.push(detail_line("Peak Memory: ", format_size(memory)));
+        }
+    }
+    let details = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title(" Details "),
+    );
+    f.render_widget(details, right[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch(instance: &str, start: u64) -> Launch {
+        Launch {
+            instance: instance.into(),
+            name: instance.to_uppercase(),
+            start,
+            target: None,
+            end: None,
+            exit_code: None,
+            peak_memory: None,
+        }
+    }
+
+    #[test]
+    fn test_last_line_wins() {
+        let started = launch("create", 1_000);
+        let mut finished = started.clone();
+        finished.end = Some(61_000);
+        finished.exit_code = Some(1);
+        let text = [
+            serde_json::to_string(&started).unwrap(),
+            "not json".to_string(),
+            serde_json::to_string(&launch("vanilla", 5_000)).unwrap(),
+            serde_json::to_string(&finished).unwrap(),
+        ]
+        .join("\n");
+
+        let launches = parse(&text);
+        assert_eq!(launches.len(), 2);
+        assert_eq!(launches[0].instance, "vanilla");
+        assert_eq!(launches[1], finished);
+        assert_eq!(launches[1].duration(), Some(60));
+        assert!(launches[1].crashed());
+    }
+
+    #[test]
+    fn test_filters() {
+        // 2024-05-01 12:00 UTC and the two days before it
+        let may_first = 1_714_564_800_000;
+        let launches = vec![
+            launch("create", may_first),
+            launch("vanilla", may_first - DAY_MS),
+            launch("create", may_first - 2 * DAY_MS),
+        ];
+        assert_eq!(format_date(may_first), "2024-05-01 12:00");
+
+        let mut view = HistoryView::new(launches, None);
+        view.apply(may_first + 1000);
+        assert_eq!(view.shown.len(), 3);
+
+        view.period = Period::Day;
+        view.apply(may_first + 1000);
+        assert_eq!(view.shown, vec![0]);
+
+        view.period = Period::All;
+        view.date = "2024-04".into();
+        view.apply(may_first + 1000);
+        assert_eq!(view.shown, vec![1, 2]);
+
+        // Instances are listed by name, so "create" comes first
+        view.instance = Some(0);
+        view.apply(may_first + 1000);
+        assert_eq!(view.shown, vec![2]);
+    }
+}
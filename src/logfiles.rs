@@ -0,0 +1,577 @@
+//! An instance's older logs and crash reports, in the log screen's Files tab.
+//!
+//! The game keeps `logs/latest.log` for the current run and compresses the
+//! previous one to `logs/<date>-<n>.log.gz` when it starts; crashes leave a
+//! report in `crash-reports/`, and a JVM crash an `hs_err_pid*.log` in the
+//! game folder. Archives are decompressed as they are opened,
+//! and a search runs through every file on a background thread.
+
+use crate::logs::{self, Level, Log};
+use crate::{format_size, format_timestamp, step_selection};
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+use regex::Regex;
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::UNIX_EPOCH,
+};
+
+/// A search stops collecting after this many matching lines.
+const MAX_HITS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Log,
+    Archive,
+    CrashReport,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    /// Relative to the game folder, e.g. `logs/2024-05-01-1.log.gz`.
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: Kind,
+    pub size: u64,
+    /// Milliseconds since the epoch.
+    pub modified: Option<u64>,
+}
+
+fn modified_ms(meta: &fs::Metadata) -> Option<u64> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+}
+
+/// The game folder's logs and crash reports, newest first.
+pub fn list_files(game_dir: &Path) -> Vec<LogFile> {
+    let mut files = crash_files(game_dir);
+    if let Ok(entries) = fs::read_dir(game_dir.join("logs")) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let kind = if file_name.ends_with(".log.gz") {
+                Kind::Archive
+            } else if file_name.ends_with(".log") {
+                Kind::Log
+            } else {
+                continue;
+            };
+            if let Some(file) = log_file(&entry, format!("logs/{}", file_name), kind) {
+                files.push(file);
+            }
+        }
+    }
+    files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.name.cmp(&b.name)));
+    files
+}
+
+/// Everything in `crash-reports/`, plus the JVM's own crash logs, which it
+/// writes into the working directory. Unsorted.
+pub fn crash_files(game_dir: &Path) -> Vec<LogFile> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(game_dir.join("crash-reports")) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = format!("crash-reports/{}", entry.file_name().to_string_lossy());
+            files.extend(log_file(&entry, name, Kind::CrashReport));
+        }
+    }
+    if let Ok(entries) = fs::read_dir(game_dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if file_name.starts_with("hs_err_pid") && file_name.ends_with(".log") {
+                files.extend(log_file(&entry, file_name, Kind::CrashReport));
+            }
+        }
+    }
+    files
+}
+
+fn log_file(entry: &fs::DirEntry, name: String, kind: Kind) -> Option<LogFile> {
+    let meta = entry.metadata().ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    Some(LogFile {
+        name,
+        path: entry.path(),
+        kind,
+        size: meta.len(),
+        modified: modified_ms(&meta),
+    })
+}
+
+/// Reads a log, decompressing it if it's gzipped.
+pub fn read_text(path: &Path) -> Result<String> {
+    let mut bytes = Vec::new();
+    let mut file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    if path.extension().is_some_and(|e| e == "gz") {
+        MultiGzDecoder::new(file).read_to_end(&mut bytes)
+    } else {
+        file.read_to_end(&mut bytes)
+    }
+    .with_context(|| format!("reading {}", path.display()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+pub fn load(path: &Path) -> Result<Log> {
+    let mut log = Log::default();
+    for line in read_text(path)?.lines() {
+        log.push_line(line.to_string());
+    }
+    Ok(log)
+}
+
+/// A matching line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    /// Index into the browser's files.
+    pub file: usize,
+    /// Counting from 0.
+    pub line: usize,
+    pub text: String,
+}
+
+pub struct Search {
+    pub hits: Vec<Hit>,
+    pub state: ListState,
+    pub done: bool,
+    /// Files that couldn't be read.
+    pub failed: usize,
+    results: mpsc::Receiver<Result<Vec<Hit>>>,
+}
+
+pub struct OpenFile {
+    /// Index into the browser's files.
+    pub index: usize,
+    pub log: Log,
+    /// First row shown.
+    pub top: usize,
+    /// A line to bring into view at the next draw, once the rows are known.
+    jump_to: Option<usize>,
+    height: usize,
+}
+
+impl OpenFile {
+    /// Scrolls back (`delta` > 0) or forward.
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.top = self.top.saturating_add_signed(-delta);
+    }
+
+    pub fn page(&self) -> isize {
+        self.height.max(1) as isize
+    }
+}
+
+pub struct FileBrowser {
+    game_dir: PathBuf,
+    pub files: Vec<LogFile>,
+    pub state: ListState,
+    pub search: Option<Search>,
+    pub opened: Option<OpenFile>,
+    pub error: Option<String>,
+}
+
+impl FileBrowser {
+    /// The files are listed when the tab is first shown.
+    pub fn new(game_dir: PathBuf) -> Self {
+        Self {
+            game_dir,
+            files: Vec::new(),
+            state: ListState::default(),
+            search: None,
+            opened: None,
+            error: None,
+        }
+    }
+
+    /// Lists the files again, keeping the selection on the same file.
+    /// Search results refer to files by position, so they're dropped.
+    pub fn refresh(&mut self) {
+        let selected = self.selected().map(|f| f.path.clone());
+        self.files = list_files(&self.game_dir);
+        self.search = None;
+        self.opened = None;
+        let index = selected.and_then(|path| self.files.iter().position(|f| f.path == path));
+        self.state.select(index.or((!self.files.is_empty()).then_some(0)));
+    }
+
+    pub fn selected(&self) -> Option<&LogFile> {
+        self.state.selected().and_then(|i| self.files.get(i))
+    }
+
+    /// The open file, else the file of the selected search result, else the selected file.
+    pub fn current(&self) -> Option<&LogFile> {
+        let index = match (&self.opened, &self.search) {
+            (Some(opened), _) => Some(opened.index),
+            (None, Some(search)) => search.state.selected().and_then(|i| search.hits.get(i)).map(|h| h.file),
+            (None, None) => self.state.selected(),
+        };
+        index.and_then(|i| self.files.get(i))
+    }
+
+    /// Moves through the search results if there are any, else the files.
+    pub fn next(&mut self) {
+        match self.search {
+            Some(ref mut search) => step_selection(&mut search.state, search.hits.len(), true),
+            None => step_selection(&mut self.state, self.files.len(), true),
+        }
+    }
+
+    pub fn previous(&mut self) {
+        match self.search {
+            Some(ref mut search) => step_selection(&mut search.state, search.hits.len(), false),
+            None => step_selection(&mut self.state, self.files.len(), false),
+        }
+    }
+
+    /// Opens the selected search result, or the selected file.
+    pub fn open_selected(&mut self) {
+        let target = match self.search {
+            Some(ref search) => search
+                .state
+                .selected()
+                .and_then(|i| search.hits.get(i))
+                .map(|hit| (hit.file, Some(hit.line))),
+            None => self.state.selected().map(|i| (i, None)),
+        };
+        if let Some((index, line)) = target {
+            self.open_file(index, line);
+        }
+    }
+
+    fn open_file(&mut self, index: usize, line: Option<usize>) {
+        let Some(file) = self.files.get(index) else {
+            return;
+        };
+        if let Some(ref mut opened) = self.opened
+            && opened.index == index
+        {
+            opened.jump_to = line;
+            return;
+        }
+        match load(&file.path) {
+            Ok(log) => {
+                self.error = None;
+                self.opened = Some(OpenFile {
+                    index,
+                    log,
+                    top: 0,
+                    jump_to: line,
+                    height: 0,
+                });
+            }
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Closes the open file, then the search results. Returns false if
+    /// there was nothing to close.
+    pub fn close(&mut self) -> bool {
+        if self.opened.take().is_some() {
+            return true;
+        }
+        self.search.take().is_some()
+    }
+
+    /// Searches every listed file for lines matching `regex`.
+    pub fn start_search(&mut self, regex: Regex) {
+        let (sender, results) = mpsc::channel();
+        let paths: Vec<PathBuf> = self.files.iter().map(|f| f.path.clone()).collect();
+        thread::spawn(move || {
+            let mut found = 0;
+            for (file, path) in paths.iter().enumerate() {
+                let hits = read_text(path).map(|text| {
+                    text.lines()
+                        .enumerate()
+                        .filter(|(_, line)| regex.is_match(line))
+                        .take(MAX_HITS - found)
+                        .map(|(line, text)| Hit {
+                            file,
+                            line,
+                            text: text.replace('\t', "    "),
+                        })
+                        .collect::<Vec<_>>()
+                });
+                found += hits.as_ref().map_or(0, Vec::len);
+                if sender.send(hits).is_err() || found >= MAX_HITS {
+                    return;
+                }
+            }
+        });
+        self.opened = None;
+        self.search = Some(Search {
+            hits: Vec::new(),
+            state: ListState::default(),
+            done: false,
+            failed: 0,
+            results,
+        });
+    }
+
+    /// Collects search results. Returns true if there were any.
+    pub fn poll(&mut self) -> bool {
+        let Some(ref mut search) = self.search else {
+            return false;
+        };
+        let mut changed = false;
+        loop {
+            match search.results.try_recv() {
+                Ok(Ok(hits)) => {
+                    changed |= !hits.is_empty();
+                    search.hits.extend(hits);
+                }
+                Ok(Err(_)) => search.failed += 1,
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    changed |= !search.done;
+                    search.done = true;
+                    break;
+                }
+            }
+        }
+        if search.state.selected().is_none() && !search.hits.is_empty() {
+            search.state.select(Some(0));
+        }
+        changed
+    }
+
+    /// Opens the next (`forward`) or previous search result.
+    pub fn step_hit(&mut self, forward: bool) -> bool {
+        let Some(ref mut search) = self.search else {
+            return false;
+        };
+        if search.hits.is_empty() {
+            return false;
+        }
+        step_selection(&mut search.state, search.hits.len(), forward);
+        self.open_selected();
+        true
+    }
+
+    /// Puts the first error of the open file at the top of the pane.
+    pub fn first_error(&mut self, collapse: bool, level: Option<Level>) -> bool {
+        let Some(ref mut opened) = self.opened else {
+            return false;
+        };
+        let rows = opened.log.rows(collapse, level);
+        match rows.iter().position(|r| r.level == Some(Level::Error)) {
+            Some(row) => {
+                opened.top = row;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    browser: &mut FileBrowser,
+    title: Line,
+    query: Option<&Regex>,
+    collapse: bool,
+    level: Option<Level>,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+    let focus_color = |focused: bool| if focused { Color::Cyan } else { Color::DarkGray };
+    let highlight = Style::default()
+        .bg(Color::Rgb(50, 50, 80))
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(focus_color(browser.opened.is_none())))
+        .title(" Logs & Crash Reports ");
+    match browser.search {
+        Some(ref mut search) => {
+            let status = if search.done {
+                format!(" {} matches ", search.hits.len())
+            } else {
+                format!(" Searching… {} matches ", search.hits.len())
+            };
+            let items: Vec<ListItem> = search
+                .hits
+                .iter()
+                .map(|hit| {
+                    let name = browser.files.get(hit.file).map_or("", |f| f.name.as_str());
+                    ListItem::new(vec![
+                        Line::from(Span::styled(
+                            format!("{}:{}", name, hit.line + 1),
+                            Style::default().fg(Color::Cyan),
+                        )),
+                        Line::from(Span::styled(format!("  {}", hit.text.trim()), Style::default().fg(Color::Gray))),
+                    ])
+                })
+                .collect();
+            let mut block = block.title_bottom(status);
+            if search.failed > 0 {
+                block = block.title_bottom(Span::styled(
+                    format!(" {} unreadable ", search.failed),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            if items.is_empty() && search.done {
+                f.render_widget(Paragraph::new("No matches").style(Style::default().fg(Color::Yellow)).block(block), chunks[0]);
+            } else {
+                let list = List::new(items).block(block).highlight_style(highlight).highlight_symbol(">> ");
+                f.render_stateful_widget(list, chunks[0], &mut search.state);
+            }
+        }
+        None if browser.files.is_empty() => {
+            let paragraph = Paragraph::new("No logs or crash reports yet")
+                .style(Style::default().fg(Color::Yellow))
+                .block(block);
+            f.render_widget(paragraph, chunks[0]);
+        }
+        None => {
+            let items: Vec<ListItem> = browser
+                .files
+                .iter()
+                .map(|file| {
+                    let color = match file.kind {
+                        Kind::CrashReport => Color::Red,
+                        Kind::Log => Color::White,
+                        Kind::Archive => Color::Gray,
+                    };
+                    let age = file.modified.map(format_timestamp).unwrap_or_default();
+                    ListItem::new(vec![
+                        Line::from(Span::styled(file.name.clone(), Style::default().fg(color))),
+                        Line::from(Span::styled(
+                            format!("  {} • {}", format_size(file.size), age),
+                            Style::default().fg(Color::DarkGray),
+                        )),
+                    ])
+                })
+                .collect();
+            let list = List::new(items).block(block).highlight_style(highlight).highlight_symbol(">> ");
+            f.render_stateful_widget(list, chunks[0], &mut browser.state);
+        }
+    }
+
+    let name = browser
+        .opened
+        .as_ref()
+        .and_then(|o| browser.files.get(o.index))
+        .map_or(String::new(), |file| format!(" {} ", file.name));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(focus_color(browser.opened.is_some())))
+        .title(title)
+        .title_bottom(name);
+    let inner = block.inner(chunks[1]);
+    f.render_widget(block, chunks[1]);
+    let Some(ref mut opened) = browser.opened else {
+        let message = browser.error.clone().unwrap_or_else(|| "Enter opens the selected log".to_string());
+        let color = if browser.error.is_some() { Color::Red } else { Color::DarkGray };
+        f.render_widget(Paragraph::new(message).style(Style::default().fg(color)), inner);
+        return;
+    };
+    opened.height = inner.height as usize;
+    let rows = opened.log.rows(collapse, level);
+    if let Some(line) = opened.jump_to.take() {
+        // The row holding the line, or the nearest one before it if it's folded or filtered out
+        let row = rows.iter().rposition(|r| r.line <= line).unwrap_or(0);
+        opened.top = row.saturating_sub(opened.height / 3);
+    }
+    opened.top = opened.top.min(rows.len().saturating_sub(opened.height));
+    if rows.is_empty() {
+        let message = if level.is_some() { "Nothing at this level (f to change)" } else { "The file is empty" };
+        f.render_widget(Paragraph::new(message).style(Style::default().fg(Color::DarkGray)), inner);
+    } else {
+        logs::render_rows(f, inner, &rows[opened.top..], query);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    fn temp_game_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minecraft-tui-logfiles-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("logs")).unwrap();
+        fs::create_dir_all(dir.join("crash-reports")).unwrap();
+        dir
+    }
+
+    fn write_gz(path: &Path, text: &str) {
+        let mut encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_lists_and_reads_archives() {
+        let dir = temp_game_dir("list");
+        write_gz(
+            &dir.join("logs/2024-05-01-1.log.gz"),
+            "[10:00:00] [main/INFO]: Starting\n[10:00:01] [main/ERROR]: Broke\n",
+        );
+        fs::write(dir.join("logs/latest.log"), "[11:00:00] [main/INFO]: Again\n").unwrap();
+        fs::write(dir.join("logs/notes.txt"), "not a log").unwrap();
+        fs::write(dir.join("crash-reports/crash-2024-05-01_10.00.02-client.txt"), "---- Minecraft Crash Report ----\n")
+            .unwrap();
+
+        let files = list_files(&dir);
+        let mut names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "crash-reports/crash-2024-05-01_10.00.02-client.txt",
+                "logs/2024-05-01-1.log.gz",
+                "logs/latest.log"
+            ]
+        );
+        let archive = files.iter().find(|f| f.kind == Kind::Archive).unwrap();
+        let log = load(&archive.path).unwrap();
+        assert_eq!(log.entries.len(), 2);
+        assert_eq!(log.rows(false, Some(Level::Error)).len(), 1);
+        assert_eq!(log.rows(false, Some(Level::Error))[0].line, 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_search_across_files() {
+        let dir = temp_game_dir("search");
+        write_gz(&dir.join("logs/2024-05-01-1.log.gz"), "[10:00:00] [main/INFO]: Mixin apply failed\n");
+        fs::write(dir.join("logs/latest.log"), "[11:00:00] [main/INFO]: fine\n[11:00:01] [main/WARN]: mixin apply skipped\n")
+            .unwrap();
+        let mut browser = FileBrowser::new(dir.clone());
+        browser.refresh();
+        browser.start_search(logs::compile("mixin apply").unwrap());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !browser.search.as_ref().unwrap().done && Instant::now() < deadline {
+            browser.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+        let search = browser.search.as_ref().unwrap();
+        assert_eq!(search.hits.len(), 2);
+        let latest = search.hits.iter().find(|h| browser.files[h.file].name == "logs/latest.log").unwrap();
+        assert_eq!(latest.line, 1);
+
+        // Capitals make the search case-sensitive
+        assert!(!logs::compile("Mixin").unwrap().is_match("mixin apply skipped"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
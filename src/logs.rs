@@ -0,0 +1,763 @@
+//! Following an instance's logs while it runs.
+//!
+//! Two logs are tailed: the game's `logs/latest.log` and PrismLauncher's own
+//! log, where launch failures (missing Java, download errors) end up. Lines
+//! are grouped into entries, a leveled line followed by the unleveled lines
+//! under it (stack traces), so a trace takes its entry's color and a trace
+//! already printed once can be folded away.
+//!
+//! When the game restarts it compresses `latest.log` and starts a new file;
+//! the tail notices the file was replaced and starts over on the new one.
+//! Those older files are read in the Files tab (see [`crate::logfiles`]).
+
+use crate::logfiles::FileBrowser;
+use crate::{game_dir, prism_data_dir, process, Instance};
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet, VecDeque},
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    os::unix::fs::MetadataExt,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// How much of an existing log is read when the viewer opens.
+const TAIL_BYTES: u64 = 512 * 1024;
+/// Older entries are dropped past this many.
+const MAX_ENTRIES: usize = 20_000;
+/// The files are checked at most this often.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Whether the game is running is checked at most this often.
+pub const RUNNING_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Finds the level of a log4j line (`[12:00:00] [Render thread/WARN]: …`,
+    /// also Forge's `[main/WARN] [mixin/]: …`) or a PrismLauncher line
+    /// (`12.345 W | …`). Lines without one continue the previous entry.
+    pub fn parse(line: &str) -> Option<Self> {
+        if line.starts_with('[') {
+            let head = line.find("]: ").map_or(line, |i| &line[..=i]);
+            for (tag, level) in [
+                ("/TRACE]", Level::Debug),
+                ("/DEBUG]", Level::Debug),
+                ("/INFO]", Level::Info),
+                ("/WARN]", Level::Warn),
+                ("/ERROR]", Level::Error),
+                ("/FATAL]", Level::Error),
+            ] {
+                if head.contains(tag) {
+                    return Some(level);
+                }
+            }
+            return None;
+        }
+        let (head, _) = line.split_once(" | ")?;
+        match head.split_whitespace().last()? {
+            "D" => Some(Level::Debug),
+            "I" => Some(Level::Info),
+            "W" => Some(Level::Warn),
+            "C" | "F" => Some(Level::Error),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+
+    /// The next minimum level to filter by: none, then INFO, WARN and ERROR.
+    pub fn next_filter(filter: Option<Level>) -> Option<Level> {
+        match filter {
+            None => Some(Level::Info),
+            Some(Level::Debug | Level::Info) => Some(Level::Warn),
+            Some(Level::Warn) => Some(Level::Error),
+            Some(Level::Error) => None,
+        }
+    }
+
+    pub fn style(self) -> Style {
+        match self {
+            Level::Debug => Style::default().fg(Color::DarkGray),
+            Level::Info => Style::default().fg(Color::Gray),
+            Level::Warn => Style::default().fg(Color::Yellow),
+            Level::Error => Style::default().fg(Color::Red),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// `None` for lines before the first leveled one, and for markers.
+    pub level: Option<Level>,
+    pub lines: Vec<String>,
+    /// The lines after the first were seen before in the same order.
+    pub repeated: bool,
+    /// Added by the viewer, not read from the file.
+    pub marker: bool,
+    /// Where the entry starts, counting lines from 0.
+    pub first_line: usize,
+}
+
+/// One displayed row.
+pub struct Row<'a> {
+    pub text: &'a str,
+    pub style: Style,
+    pub level: Option<Level>,
+    /// The line it shows, or the first line folded into it.
+    pub line: usize,
+}
+
+/// A log's lines grouped into entries.
+#[derive(Default)]
+pub struct Log {
+    pub entries: VecDeque<Entry>,
+    traces: HashSet<u64>,
+    lines_read: usize,
+}
+
+impl Log {
+    pub fn push_line(&mut self, line: String) {
+        // Tabs would leave the terminal's tab stops out of step with the layout
+        let line = if line.contains('\t') { line.replace('\t', "    ") } else { line };
+        let level = Level::parse(&line);
+        let first_line = self.lines_read;
+        self.lines_read += 1;
+        if level.is_none()
+            && let Some(last) = self.entries.back_mut()
+            && !last.marker
+        {
+            last.lines.push(line);
+            return;
+        }
+        self.close_last();
+        self.entries.push_back(Entry {
+            level,
+            lines: vec![line],
+            repeated: false,
+            marker: false,
+            first_line,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn push_marker(&mut self, text: impl Into<String>) {
+        self.close_last();
+        self.entries.push_back(Entry {
+            level: None,
+            lines: vec![text.into()],
+            repeated: false,
+            marker: true,
+            first_line: self.lines_read,
+        });
+    }
+
+    /// Checks the finished last entry's trace against the ones seen so far.
+    fn close_last(&mut self) {
+        let Some(last) = self.entries.back_mut() else {
+            return;
+        };
+        if last.lines.len() < 3 {
+            return;
+        }
+        let mut hasher = DefaultHasher::new();
+        last.lines[1..].hash(&mut hasher);
+        last.repeated = !self.traces.insert(hasher.finish());
+    }
+
+    /// The rows to show, with repeated traces folded to one line if `collapse`
+    /// and only entries at `level` or above if given.
+    pub fn rows(&self, collapse: bool, level: Option<Level>) -> Vec<Row<'_>> {
+        let mut rows = Vec::new();
+        for entry in &self.entries {
+            if let Some(min) = level
+                && !entry.marker
+                && entry.level.is_none_or(|l| l < min)
+            {
+                continue;
+            }
+            let style = match (entry.marker, entry.level) {
+                (true, _) => Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                (false, Some(level)) => level.style(),
+                (false, None) => Style::default().fg(Color::Gray),
+            };
+            let row = |text, line| Row {
+                text,
+                style,
+                level: entry.level,
+                line,
+            };
+            if collapse && entry.repeated {
+                rows.push(row(&entry.lines[0], entry.first_line));
+                rows.push(Row {
+                    style: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                    ..row(FOLDED, entry.first_line + 1)
+                });
+                continue;
+            }
+            rows.extend(entry.lines.iter().enumerate().map(|(i, text)| row(text, entry.first_line + i)));
+        }
+        rows
+    }
+}
+
+/// Compiles a search, ignoring case unless it has capitals.
+pub fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(!pattern.chars().any(char::is_uppercase))
+        .build()
+}
+
+const FOLDED: &str = "    ⋯ same stack trace as before (c to expand)";
+
+/// Follows a file as it grows, starting over when it is replaced or truncated.
+pub struct Tail {
+    pub path: PathBuf,
+    offset: u64,
+    /// Device and inode of the file being followed.
+    id: Option<(u64, u64)>,
+    /// The start of a line whose newline hasn't been written yet.
+    partial: Vec<u8>,
+}
+
+impl Tail {
+    /// Starts near the end of the file if it exists, skipping the cut-off first line.
+    pub fn open(path: PathBuf) -> Self {
+        let mut tail = Self {
+            path,
+            offset: 0,
+            id: None,
+            partial: Vec::new(),
+        };
+        if let Ok(meta) = tail.path.metadata() {
+            tail.id = Some((meta.dev(), meta.ino()));
+            tail.offset = meta.len().saturating_sub(TAIL_BYTES);
+            if tail.offset > 0 {
+                tail.offset += tail.skip_line().unwrap_or(0);
+            }
+        }
+        tail
+    }
+
+    /// Returns the complete lines written since the last call, and whether
+    /// the file was replaced first.
+    pub fn read(&mut self) -> io::Result<(bool, Vec<String>)> {
+        let meta = match self.path.metadata() {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((false, Vec::new())),
+            Err(e) => return Err(e),
+        };
+        let id = (meta.dev(), meta.ino());
+        let mut restarted = false;
+        if self.id != Some(id) || meta.len() < self.offset {
+            restarted = self.id.is_some();
+            self.id = Some(id);
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if meta.len() == self.offset {
+            return Ok((restarted, Vec::new()));
+        }
+        Ok((restarted, self.read_new()?))
+    }
+
+    /// The length of the line at the current offset, including its newline.
+    fn skip_line(&self) -> io::Result<u64> {
+        let mut file = BufReader::new(File::open(&self.path)?);
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut line = Vec::new();
+        Ok(file.read_until(b'\n', &mut line)? as u64)
+    }
+
+    fn read_new(&mut self) -> io::Result<Vec<String>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        self.offset += bytes.len() as u64;
+        self.partial.extend_from_slice(&bytes);
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(complete[..end]
+            .split(|&b| b == b'\n')
+            .map(|line| String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)).into_owned())
+            .collect())
+    }
+}
+
+pub struct Source {
+    pub label: &'static str,
+    pub tail: Tail,
+    pub log: Log,
+    pub error: Option<String>,
+}
+
+impl Source {
+    fn new(label: &'static str, path: PathBuf) -> Self {
+        Self {
+            label,
+            tail: Tail::open(path),
+            log: Log::default(),
+            error: None,
+        }
+    }
+
+    /// Reads new lines. Returns how many were added.
+    fn poll(&mut self) -> usize {
+        match self.tail.read() {
+            Ok((restarted, lines)) => {
+                self.error = None;
+                if restarted {
+                    self.log.push_marker("── log restarted ──");
+                }
+                let added = lines.len() + restarted as usize;
+                for line in lines {
+                    self.log.push_line(line);
+                }
+                added
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                0
+            }
+        }
+    }
+}
+
+/// PrismLauncher's log moved into `logs/` in version 9.
+pub fn launcher_log() -> Option<PathBuf> {
+    let data_dir = prism_data_dir().ok()?;
+    let old = data_dir.join("PrismLauncher-0.log");
+    let new = data_dir.join("logs/PrismLauncher-0.log");
+    Some(if !new.exists() && old.exists() { old } else { new })
+}
+
+pub struct LogView {
+    pub instance_name: String,
+    instance_path: PathBuf,
+    pub sources: Vec<Source>,
+    /// Index into `sources`, or `sources.len()` for the Files tab.
+    pub current: usize,
+    pub files: FileBrowser,
+    /// Rows scrolled back from the bottom.
+    pub scroll: usize,
+    /// Holds the view still while new lines arrive.
+    pub paused: bool,
+    pub collapse: bool,
+    /// Only entries at this level or above are shown.
+    pub level: Option<Level>,
+    /// The search being typed.
+    pub search: Option<String>,
+    pub query: Option<Regex>,
+    pub running: bool,
+    /// Rows that fit in the log pane at the last draw.
+    height: usize,
+    last_poll: Option<Instant>,
+    last_running_check: Option<Instant>,
+}
+
+impl LogView {
+    pub fn open(instance: &Instance) -> Self {
+        let game_dir = game_dir(&instance.path);
+        let mut sources = vec![Source::new("latest.log", game_dir.join("logs/latest.log"))];
+        if let Some(path) = launcher_log() {
+            sources.push(Source::new("Launcher", path));
+        }
+        let mut view = Self {
+            instance_name: instance.name.clone(),
+            instance_path: instance.path.clone(),
+            sources,
+            current: 0,
+            files: FileBrowser::new(game_dir),
+            scroll: 0,
+            paused: false,
+            collapse: true,
+            level: None,
+            search: None,
+            query: None,
+            running: false,
+            height: 0,
+            last_poll: None,
+            last_running_check: None,
+        };
+        view.poll();
+        view
+    }
+
+    /// Reads new lines from every log. Returns true if anything changed.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = self.files.poll();
+        let now = Instant::now();
+        if self.last_poll.is_some_and(|last| now.duration_since(last) < POLL_INTERVAL) {
+            return changed;
+        }
+        self.last_poll = Some(now);
+        if self.last_running_check.is_none_or(|last| now.duration_since(last) >= RUNNING_INTERVAL) {
+            self.last_running_check = Some(now);
+            let running = process::java_running_in(std::slice::from_ref(&self.instance_path))[0];
+            changed |= running != self.running;
+            self.running = running;
+        }
+        let following = self.following();
+        for (i, source) in self.sources.iter_mut().enumerate() {
+            // A paused or scrolled-back view stays on the same rows
+            let keep_place = i == self.current && !following;
+            let rows_before = if keep_place { source.log.rows(self.collapse, self.level).len() } else { 0 };
+            let had_error = source.error.is_some();
+            if source.poll() > 0 {
+                changed = true;
+                if keep_place {
+                    self.scroll += source.log.rows(self.collapse, self.level).len().saturating_sub(rows_before);
+                }
+            }
+            changed |= had_error != source.error.is_some();
+        }
+        changed
+    }
+
+    fn following(&self) -> bool {
+        !self.paused && self.scroll == 0
+    }
+
+    pub fn on_files(&self) -> bool {
+        self.current == self.sources.len()
+    }
+
+    fn rows(&self) -> Vec<Row<'_>> {
+        match self.sources.get(self.current) {
+            Some(source) => source.log.rows(self.collapse, self.level),
+            None => Vec::new(),
+        }
+    }
+
+    /// Cycles through the live logs and the Files tab.
+    pub fn next_source(&mut self) {
+        self.current = (self.current + 1) % (self.sources.len() + 1);
+        self.scroll = 0;
+        if self.on_files() {
+            self.files.refresh();
+        }
+    }
+
+    pub fn toggle_collapse(&mut self) {
+        self.collapse = !self.collapse;
+        self.scroll = 0;
+    }
+
+    pub fn cycle_level(&mut self) {
+        self.level = Level::next_filter(self.level);
+        self.scroll = 0;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Scrolls back (`delta` > 0) or forward through the log.
+    pub fn scroll_by(&mut self, delta: isize) {
+        let rows = self.rows().len();
+        let max = rows.saturating_sub(self.height);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn page(&self) -> isize {
+        self.height.max(1) as isize
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll = usize::MAX;
+        self.scroll_by(0);
+    }
+
+    /// Jumps back to the newest line and follows again.
+    pub fn follow(&mut self) {
+        self.scroll = 0;
+        self.paused = false;
+    }
+
+    /// Puts the first error at the top of the pane.
+    pub fn first_error(&mut self) -> bool {
+        if self.on_files() {
+            return self.files.first_error(self.collapse, self.level);
+        }
+        let rows = self.rows();
+        let Some(row) = rows.iter().position(|r| r.level == Some(Level::Error)) else {
+            return false;
+        };
+        self.scroll = rows.len().saturating_sub(row + self.height);
+        self.paused = true;
+        true
+    }
+
+    /// Runs the search that was typed: back from the newest line of a live
+    /// log, or through every file in the Files tab.
+    pub fn finish_search(&mut self) -> Result<bool> {
+        let Some(pattern) = self.search.take() else {
+            return Ok(false);
+        };
+        if pattern.is_empty() {
+            self.query = None;
+            return Ok(false);
+        }
+        let regex = compile(&pattern).context("invalid search")?;
+        self.query = Some(regex.clone());
+        if self.on_files() {
+            self.files.start_search(regex);
+            return Ok(true);
+        }
+        self.scroll = 0;
+        Ok(self.seek(true, true))
+    }
+
+    /// Moves to the next match above the middle of the pane (`older`) or
+    /// below it, and puts it in the middle. In the Files tab, opens the
+    /// next search result (`older`, as `n` means both) or the previous one.
+    pub fn find(&mut self, older: bool) -> bool {
+        if self.on_files() {
+            return self.files.step_hit(older);
+        }
+        self.seek(older, false)
+    }
+
+    fn seek(&mut self, older: bool, from_bottom: bool) -> bool {
+        let Some(ref query) = self.query else {
+            return false;
+        };
+        let rows = self.rows();
+        if rows.is_empty() {
+            return false;
+        }
+        let middle = self.height / 2;
+        let current = if from_bottom {
+            rows.len()
+        } else {
+            (rows.len() - 1).saturating_sub(self.scroll + middle)
+        };
+        let matches = |i: &usize| query.is_match(rows[*i].text);
+        let found = if older {
+            (0..current).rev().find(matches)
+        } else {
+            (current + 1..rows.len()).find(matches)
+        };
+        let Some(row) = found else {
+            return false;
+        };
+        let max = rows.len().saturating_sub(self.height);
+        self.scroll = (rows.len() - 1).saturating_sub(row + middle).min(max);
+        self.paused = true;
+        true
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, view: &mut LogView) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(if view.search.is_some() { 3 } else { 0 }),
+        ])
+        .split(area);
+
+    let mut title = vec![Span::raw(format!(" Logs — {} ", view.instance_name))];
+    let tabs = view.sources.iter().map(|s| s.label).chain(["Files"]);
+    for (i, label) in tabs.enumerate() {
+        let style = if i == view.current {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        title.push(Span::styled(format!("[{}] ", label), style));
+    }
+    if let Some(level) = view.level {
+        title.push(Span::styled(format!("≥{} ", level.label()), level.style()));
+    }
+
+    if view.on_files() {
+        crate::logfiles::render(
+            f,
+            chunks[0],
+            &mut view.files,
+            Line::from(title),
+            view.query.as_ref(),
+            view.collapse,
+            view.level,
+        );
+    } else {
+        title.push(if view.running {
+            Span::styled("● running ", Style::default().fg(Color::Green))
+        } else {
+            Span::styled("not running ", Style::default().fg(Color::DarkGray))
+        });
+        if view.paused {
+            title.push(Span::styled("paused ", Style::default().fg(Color::Magenta)));
+        }
+        if view.scroll > 0 {
+            title.push(Span::raw(format!("↑{} ", view.scroll)));
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(Line::from(title));
+        let inner = block.inner(chunks[0]);
+        f.render_widget(block, chunks[0]);
+        view.height = inner.height as usize;
+
+        let source = &view.sources[view.current];
+        let rows = source.log.rows(view.collapse, view.level);
+        if rows.is_empty() {
+            let message = match source.error {
+                Some(ref error) => format!("Can't read {}: {}", source.tail.path.display(), error),
+                None if !source.tail.path.exists() => format!("Waiting for {}", source.tail.path.display()),
+                None if view.level.is_some() => "Nothing at this level yet (f to change)".to_string(),
+                None => "The log is empty".to_string(),
+            };
+            f.render_widget(Paragraph::new(message).style(Style::default().fg(Color::DarkGray)), inner);
+        } else {
+            view.scroll = view.scroll.min(rows.len().saturating_sub(view.height));
+            let top = rows.len().saturating_sub(view.height + view.scroll);
+            render_rows(f, inner, &rows[top..], view.query.as_ref());
+        }
+    }
+
+    if let Some(ref search) = view.search {
+        let scope = if view.on_files() { " Search All Logs (regex) " } else { " Search (regex) " };
+        let input = Paragraph::new(format!("/{}█", search)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(scope),
+        );
+        f.render_widget(input, chunks[1]);
+    }
+}
+
+/// Draws as many of `rows` as fit, marking the first match of `query` in each.
+pub fn render_rows(f: &mut Frame, area: Rect, rows: &[Row], query: Option<&Regex>) {
+    let lines: Vec<Line> = rows
+        .iter()
+        .take(area.height as usize)
+        .map(|row| match query.and_then(|q| q.find(row.text)).filter(|m| !m.is_empty()) {
+            Some(found) => Line::from(vec![
+                Span::styled(&row.text[..found.start()], row.style),
+                Span::styled(found.as_str(), Style::default().bg(Color::Yellow).fg(Color::Black)),
+                Span::styled(&row.text[found.end()..], row.style),
+            ]),
+            None => Line::from(Span::styled(row.text, row.style)),
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+
+    fn read_all(path: &Path) -> Log {
+        let mut log = Log::default();
+        let mut tail = Tail::open(path.to_path_buf());
+        for line in tail.read().unwrap().1 {
+            log.push_line(line);
+        }
+        log
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!(Level::parse("[12:00:00] [Render thread/WARN]: Missing sound"), Some(Level::Warn));
+        assert_eq!(
+            Level::parse("[14Mar2024 12:00:00.123] [main/INFO] [cpw.mods.modlauncher.Launcher/MODLAUNCHER]: Loading"),
+            Some(Level::Info)
+        );
+        assert_eq!(Level::parse("[12:00:00] [Server thread/FATAL]: Boom"), Some(Level::Error));
+        assert_eq!(Level::parse("  12.345 W | Instance folder missing"), Some(Level::Warn));
+        assert_eq!(Level::parse("0.001 C | [launcher]: Failed"), Some(Level::Error));
+        assert_eq!(Level::parse("\tat net.minecraft.Main.main(Main.java:1)"), None);
+        assert_eq!(Level::parse("[12:00:00] [main/INFO]: chat says /WARN] here"), Some(Level::Info));
+    }
+
+    #[test]
+    fn test_repeated_traces_fold() {
+        let mut log = Log::default();
+        for _ in 0..2 {
+            log.push_line("[12:00:00] [Render thread/ERROR]: Failed to tick".into());
+            log.push_line("java.lang.NullPointerException: null".into());
+            log.push_line("\tat a.b.C.tick(C.java:10)".into());
+        }
+        log.push_line("[12:00:01] [Render thread/INFO]: Done".into());
+        assert_eq!(log.entries.len(), 3);
+        assert!(!log.entries[0].repeated);
+        assert!(log.entries[1].repeated);
+        assert_eq!(log.rows(false, None).len(), 7);
+        let rows = log.rows(true, None);
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[4].text, FOLDED);
+        assert_eq!(rows[2].style, Level::Error.style());
+    }
+
+    #[test]
+    fn test_tail_follows_rotation() {
+        let dir = std::env::temp_dir().join(format!("minecraft-tui-logs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("latest.log");
+        fs::write(&path, "[00:00:00] [main/INFO]: old\n").unwrap();
+        assert_eq!(read_all(&path).entries.len(), 1);
+
+        let mut tail = Tail::open(path.clone());
+        assert_eq!(tail.read().unwrap(), (false, vec!["[00:00:00] [main/INFO]: old".to_string()]));
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"[00:00:01] [main/INFO]: half").unwrap();
+        assert_eq!(tail.read().unwrap(), (false, Vec::new()));
+        file.write_all(b" done\r\n").unwrap();
+        assert_eq!(tail.read().unwrap(), (false, vec!["[00:00:01] [main/INFO]: half done".to_string()]));
+
+        // The game moves the old log aside and starts a new one
+        fs::rename(&path, dir.join("2024-01-01-1.log")).unwrap();
+        fs::write(&path, "[00:01:00] [main/INFO]: new\n").unwrap();
+        assert_eq!(tail.read().unwrap(), (true, vec!["[00:01:00] [main/INFO]: new".to_string()]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tail_starts_near_the_end() {
+        let dir = std::env::temp_dir().join(format!("minecraft-tui-logs-big-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("latest.log");
+        let line = format!("[00:00:00] [main/INFO]: {}\n", "x".repeat(100));
+        fs::write(&path, line.repeat(10_000)).unwrap();
+        let log = read_all(&path);
+        let expected = (TAIL_BYTES as usize) / line.len();
+        assert!((expected - 1..=expected).contains(&log.entries.len()));
+        assert!(log.entries.iter().all(|e| e.lines[0] == line.trim_end()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,31 @@
-use anyhow::Result;
+mod backup;
+mod config;
+mod crash;
+mod crashes;
+mod datapack;
+mod dedicated;
+mod filter;
+mod icon;
+mod inspector;
+mod lan;
+mod launches;
+mod logfiles;
+mod logs;
+mod map;
+mod meta;
+mod nbt;
+mod ping;
+mod process;
+mod rcon;
+mod region;
+mod servers;
+mod stats;
+mod store;
+mod trash;
+mod version;
+mod world;
+
+use anyhow::{Context, Result};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
@@ -9,24 +36,105 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
+use backup::{Backup, BackupBrowser, BackupTarget};
+use config::{BackupFormat, Config};
+use crash::CrashView;
+use crashes::{CrashHistory, CrashKind, CrashTracker};
+use datapack::DatapackView;
+use dedicated::{DedicatedView, Prompt, PropertiesEditor, Supervisor};
+use filter::Filter;
+use icon::{GraphicsProtocol, Icon, IconPainter, ICON_COLS, ICON_ROWS};
+use inspector::{Input, Inspector};
+use launches::{HistoryView, Launch};
+use logs::LogView;
+use map::MapView;
+use meta::{Catalog, VersionStatus};
+use process::{ProcessTracker, Session, SessionState};
+use rcon::Console;
+use servers::ServerList;
+use stats::StatsView;
+use version::McVersion;
+use world::{CopyPicker, WorldBrowser};
 use serde::Deserialize;
 use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     fs,
     io,
     path::{Path, PathBuf},
     process::Command,
+    sync::{mpsc, Arc},
     thread,
-    time::Duration,
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Screen {
+    Instances,
+    Worlds,
+    Backups,
+    Map,
+    Stats,
+    Inspector,
+    Datapacks,
+    Servers,
+    Console,
+    Dedicated,
+    Logs,
+    Crash,
+    CrashHistory,
+    Launches,
+}
+
+/// How often the event loop wakes up to check on background tasks.
+const TICK: Duration = Duration::from_millis(250);
+
+/// A one-line message shown over the footer until the next key press.
+struct Notification {
+    message: String,
+    error: bool,
+}
+
+/// Work running on a background thread, such as a backup or restore.
+struct Task {
+    label: String,
+    result: mpsc::Receiver<Result<String>>,
+}
+
+/// An action waiting for a y/n answer in the confirmation popup.
+struct Confirm {
+    message: String,
+    action: PendingAction,
+}
+
+enum PendingAction {
+    Restore(BackupTarget, Backup),
+    Prune(Vec<Backup>),
+    DiscardEdits,
+    /// World folder and its display name.
+    TrashWorld(PathBuf, String),
+    /// Index into the open server list.
+    RemoveServer(usize),
+    /// Dedicated server folder and name.
+    KillServer(PathBuf, String),
+    AcceptEula(PathBuf, String),
+    DiscardProperties,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum SortMode {
     Name,
     LastPlayed,
     Playtime,
+    Version,
+    ReleaseDate,
+    Loader,
+    ModCount,
+    DiskSize,
+    Created,
 }
 
 impl SortMode {
@@ -34,7 +142,13 @@ impl SortMode {
         match self {
             SortMode::Name => SortMode::LastPlayed,
             SortMode::LastPlayed => SortMode::Playtime,
-            SortMode::Playtime => SortMode::Name,
+            SortMode::Playtime => SortMode::Version,
+            SortMode::Version => SortMode::ReleaseDate,
+            SortMode::ReleaseDate => SortMode::Loader,
+            SortMode::Loader => SortMode::ModCount,
+            SortMode::ModCount => SortMode::DiskSize,
+            SortMode::DiskSize => SortMode::Created,
+            SortMode::Created => SortMode::Name,
         }
     }
 
@@ -43,11 +157,119 @@ impl SortMode {
             SortMode::Name => "Name",
             SortMode::LastPlayed => "Last Played",
             SortMode::Playtime => "Playtime",
+            SortMode::Version => "Version",
+            SortMode::ReleaseDate => "Release Date",
+            SortMode::Loader => "Loader",
+            SortMode::ModCount => "Mods",
+            SortMode::DiskSize => "Size",
+            SortMode::Created => "Created",
+        }
+    }
+
+    /// Text-like keys read best A→Z, everything else biggest/newest first.
+    fn default_descending(&self) -> bool {
+        !matches!(self, SortMode::Name | SortMode::Loader)
+    }
+
+    /// Compares two instances by this key. Instances missing the key always sort last.
+    fn compare(&self, a: &Instance, b: &Instance, descending: bool) -> Ordering {
+        match self {
+            SortMode::Name => directed(a.name.cmp(&b.name), descending),
+            SortMode::LastPlayed => compare_present(a.last_played_ts, b.last_played_ts, descending),
+            SortMode::Playtime => compare_present(a.time_played_secs, b.time_played_secs, descending),
+            SortMode::Version => compare_present(
+                a.mc_version.as_deref().map(McVersion::parse),
+                b.mc_version.as_deref().map(McVersion::parse),
+                descending,
+            ),
+            SortMode::ReleaseDate => compare_present(
+                a.version_status.release_time.as_ref(),
+                b.version_status.release_time.as_ref(),
+                descending,
+            ),
+            SortMode::Loader => compare_present(
+                a.mod_loader.map(|l| l.display()),
+                b.mod_loader.map(|l| l.display()),
+                descending,
+            ),
+            SortMode::ModCount => compare_present(a.mod_count, b.mod_count, descending),
+            SortMode::DiskSize => compare_present(a.disk_size, b.disk_size, descending),
+            SortMode::Created => compare_present(a.created_ts, b.created_ts, descending),
+        }
+    }
+}
+
+/// Sorts by `primary`, breaking ties with `secondary` in its default direction.
+fn sort_instances(instances: &mut [Instance], primary: SortMode, descending: bool, secondary: SortMode) {
+    instances.sort_by(|a, b| {
+        primary
+            .compare(a, b, descending)
+            .then_with(|| secondary.compare(a, b, secondary.default_descending()))
+    });
+}
+
+fn directed(ordering: Ordering, descending: bool) -> Ordering {
+    if descending { ordering.reverse() } else { ordering }
+}
+
+fn compare_present<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => directed(a.cmp(&b), descending),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModLoader {
+    Vanilla,
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    LiteLoader,
+}
+
+impl ModLoader {
+    /// Maps a component uid from mmc-pack.json to the loader it installs.
+    /// Only exact uids count, so e.g. `net.fabricmc.intermediary` is not a loader.
+    fn from_uid(uid: &str) -> Option<Self> {
+        match uid {
+            "net.fabricmc.fabric-loader" => Some(ModLoader::Fabric),
+            "org.quiltmc.quilt-loader" => Some(ModLoader::Quilt),
+            "net.minecraftforge" => Some(ModLoader::Forge),
+            "net.neoforged" => Some(ModLoader::NeoForge),
+            "com.mumfrey.liteloader" => Some(ModLoader::LiteLoader),
+            _ => None,
+        }
+    }
+
+    /// The mmc-pack.json / meta package uid for this loader.
+    fn uid(&self) -> Option<&'static str> {
+        match self {
+            ModLoader::Vanilla => None,
+            ModLoader::Fabric => Some("net.fabricmc.fabric-loader"),
+            ModLoader::Quilt => Some("org.quiltmc.quilt-loader"),
+            ModLoader::Forge => Some("net.minecraftforge"),
+            ModLoader::NeoForge => Some("net.neoforged"),
+            ModLoader::LiteLoader => Some("com.mumfrey.liteloader"),
+        }
+    }
+
+    fn display(&self) -> &'static str {
+        match self {
+            ModLoader::Vanilla => "Vanilla",
+            ModLoader::Fabric => "Fabric",
+            ModLoader::Quilt => "Quilt",
+            ModLoader::Forge => "Forge",
+            ModLoader::NeoForge => "NeoForge",
+            ModLoader::LiteLoader => "LiteLoader",
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 struct Instance {
     name: String,
     path: PathBuf,
@@ -56,25 +278,30 @@ struct Instance {
     time_played: Option<String>,
     time_played_secs: Option<u64>,
     mc_version: Option<String>,
-    mod_loader: Option<String>,
+    mod_loader: Option<ModLoader>,
+    loader_version: Option<String>,
+    components: Vec<Component>,
+    version_status: VersionStatus,
+    mod_count: Option<usize>,
+    disk_size: Option<u64>,
+    created_ts: Option<u64>,
+    icon: Option<Arc<Icon>>,
+    world_count: Option<usize>,
 }
 
-fn is_instance_running(instance_name: &str) -> bool {
-    // Check if there's a flatpak process running with this instance name
-    if let Ok(output) = Command::new("ps")
-        .args(&["aux"])
-        .output()
-    {
-        if let Ok(stdout) = String::from_utf8(output.stdout) {
-            // Look for flatpak processes with the instance name in the command
-            return stdout.lines().any(|line| {
-                line.contains("flatpak") &&
-                line.contains("PrismLauncher") &&
-                line.contains(instance_name)
-            });
-        }
+impl Instance {
+    /// The instance's folder name, which unlike its display name never changes.
+    fn folder(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.name.clone())
     }
-    false
+}
+
+/// Whether the game is running in the instance's folder, however it was started.
+fn game_running(instance_path: &Path) -> bool {
+    process::java_running_in(&[instance_path.to_path_buf()])[0]
 }
 
 #[derive(Debug, Deserialize)]
@@ -90,6 +317,8 @@ struct GeneralConfig {
     last_launch_time: Option<u64>,
     #[serde(rename = "totalTimePlayed")]
     total_time_played: Option<u64>,
+    #[serde(rename = "iconKey")]
+    icon_key: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,43 +326,172 @@ struct MMCPack {
     components: Vec<Component>,
 }
 
-#[derive(Debug, Deserialize)]
+impl MMCPack {
+    fn minecraft_version(&self) -> Option<String> {
+        self.components
+            .iter()
+            .find(|c| c.uid == "net.minecraft")
+            .and_then(|c| c.version().map(str::to_string))
+    }
+
+    /// Returns the first mod loader component and its version, or Vanilla if there is none.
+    fn mod_loader(&self) -> (ModLoader, Option<String>) {
+        self.components
+            .iter()
+            .find_map(|c| {
+                ModLoader::from_uid(&c.uid).map(|loader| (loader, c.version().map(str::to_string)))
+            })
+            .unwrap_or((ModLoader::Vanilla, None))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Component {
     uid: String,
     version: Option<String>,
+    cached_name: Option<String>,
+    cached_version: Option<String>,
+    #[serde(default)]
+    dependency_only: bool,
+    #[serde(default)]
+    important: bool,
+    #[serde(default)]
+    cached_requires: Vec<ComponentRequirement>,
+}
+
+impl Component {
+    fn name(&self) -> &str {
+        self.cached_name.as_deref().unwrap_or(&self.uid)
+    }
+
+    fn version(&self) -> Option<&str> {
+        self.version.as_deref().or(self.cached_version.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ComponentRequirement {
+    uid: String,
+    equals: Option<String>,
+    suggests: Option<String>,
+}
+
+fn read_mmc_pack(instance_path: &Path) -> Option<MMCPack> {
+    let content = fs::read_to_string(instance_path.join("mmc-pack.json")).ok()?;
+    serde_json::from_str(&content).ok()
 }
 
 struct App {
+    screen: Screen,
     instances: Vec<Instance>,
     filtered_instances: Vec<Instance>,
     list_state: ListState,
     should_quit: bool,
     should_launch: bool,
     sort_mode: SortMode,
+    sort_descending: bool,
+    secondary_sort: SortMode,
     search_mode: bool,
     search_query: String,
+    /// Why the search query can't be used, such as a malformed date.
+    filter_error: Option<String>,
     details_mode: bool,
+    icon_painter: IconPainter,
+    /// Where the details panel wants the selected instance's icon painted this frame.
+    icon_placement: Option<(Rect, Arc<Icon>)>,
+    world_browser: Option<WorldBrowser>,
+    backup_browser: Option<BackupBrowser>,
+    map_view: Option<MapView>,
+    stats_view: Option<StatsView>,
+    inspector: Option<Inspector>,
+    datapack_view: Option<DatapackView>,
+    server_list: Option<ServerList>,
+    console: Option<Console>,
+    /// The screen to go back to when the console is closed.
+    console_return: Screen,
+    dedicated_view: Option<DedicatedView>,
+    log_view: Option<LogView>,
+    crash_view: Option<CrashView>,
+    /// The screen to go back to when the crash analyzer is closed.
+    crash_return: Screen,
+    crash_history: Option<CrashHistory>,
+    crashes: CrashTracker,
+    history_view: Option<HistoryView>,
+    /// Launches of the games being followed, keyed by instance path, to be completed when they exit.
+    launches: HashMap<PathBuf, Launch>,
+    /// Dedicated servers started from the TUI, kept running across screens.
+    supervisor: Supervisor,
+    config: Config,
+    notification: Option<Notification>,
+    task: Option<Task>,
+    confirm: Option<Confirm>,
+    tracker: ProcessTracker,
+    /// Instance folder sizes still being measured.
+    sizes: Option<mpsc::Receiver<(PathBuf, Option<u64>)>>,
+    /// Folders of the instances with a game running, as of the last check.
+    running: HashSet<PathBuf>,
+    last_running_check: Option<Instant>,
+    /// Finished sessions waiting for their auto-backup to run, with the
+    /// exit code of those that crashed.
+    pending_auto_backups: Vec<(Session, Option<i32>)>,
 }
 
 impl App {
     fn new() -> Result<Self> {
         let instances = load_instances()?;
         let filtered_instances = instances.clone();
+        let mut crashes = CrashTracker::load();
+        crashes.scan(&instances);
+        let sizes = measure_sizes(&instances);
         let mut list_state = ListState::default();
         if !instances.is_empty() {
             list_state.select(Some(0));
         }
 
         Ok(Self {
+            screen: Screen::Instances,
             instances,
             filtered_instances,
             list_state,
             should_quit: false,
             should_launch: false,
             sort_mode: SortMode::Name,
+            sort_descending: SortMode::Name.default_descending(),
+            secondary_sort: SortMode::Name,
             search_mode: false,
             search_query: String::new(),
+            filter_error: None,
             details_mode: false,
+            icon_painter: IconPainter::new(GraphicsProtocol::detect()),
+            icon_placement: None,
+            world_browser: None,
+            backup_browser: None,
+            map_view: None,
+            stats_view: None,
+            inspector: None,
+            datapack_view: None,
+            server_list: None,
+            console: None,
+            console_return: Screen::Instances,
+            dedicated_view: None,
+            log_view: None,
+            crash_view: None,
+            crash_return: Screen::Instances,
+            crash_history: None,
+            crashes,
+            history_view: None,
+            launches: HashMap::new(),
+            supervisor: Supervisor::default(),
+            config: Config::load(),
+            notification: None,
+            task: None,
+            confirm: None,
+            tracker: ProcessTracker::default(),
+            sizes: Some(sizes),
+            running: HashSet::new(),
+            last_running_check: None,
+            pending_auto_backups: Vec::new(),
         })
     }
 
@@ -172,27 +530,220 @@ impl App {
     }
 
     fn launch_selected(&self) -> Result<()> {
-        if let Some(selected) = self.list_state.selected() {
-            if let Some(instance) = self.filtered_instances.get(selected) {
-                launch_instance(&instance.name)?;
+        if let Some(selected) = self.list_state.selected()
+            && let Some(instance) = self.filtered_instances.get(selected)
+        {
+            launch_instance(&instance.name, None)?;
+            // The game is already on its way; a history that can't be written shouldn't look like a failed launch
+            if let Err(err) = launches::record(&Launch::new(instance, None)) {
+                eprintln!("Couldn't record the launch: {:#}", err);
             }
         }
         Ok(())
     }
 
-    fn open_folder_selected(&self) -> Result<()> {
-        if let Some(selected) = self.list_state.selected() {
-            if let Some(instance) = self.filtered_instances.get(selected) {
-                Command::new("xdg-open")
-                    .arg(&instance.path)
-                    .spawn()?;
+    /// Launches the selected instance without leaving the TUI and follows the
+    /// game so it can be backed up when it exits.
+    fn launch_tracked(&mut self) -> Result<()> {
+        let Some(instance) = self.selected_instance().cloned() else {
+            return Ok(());
+        };
+        self.launch_into(&instance, None)
+    }
+
+    fn launch_into(&mut self, instance: &Instance, target: Option<LaunchTarget>) -> Result<()> {
+        if let Some(ref target) = target
+            && let Err(err) = target.check_version(instance.mc_version.as_deref())
+        {
+            self.notify(format!("{:#}", err), true);
+            return Ok(());
+        }
+        launch_instance(&instance.name, target.as_ref())?;
+        self.tracker.track(instance);
+        self.crashes.launched(instance);
+        let launch = Launch::new(instance, target.as_ref().map(LaunchTarget::describe));
+        let recorded = launches::record(&launch);
+        self.launches.insert(instance.path.clone(), launch);
+        let destination = match target {
+            Some(LaunchTarget::Server(address)) => format!(" and joining {}", address),
+            Some(LaunchTarget::World(folder)) => format!(" into {}", folder),
+            None => String::new(),
+        };
+        let message = if self.config.instance(&instance.folder()).auto_backup {
+            format!("Launched {}{}; worlds will be backed up when it exits", instance.name, destination)
+        } else {
+            format!("Launched {}{}", instance.name, destination)
+        };
+        match recorded {
+            Ok(()) => self.notify(message, false),
+            Err(err) => self.notify(format!("{}, but it couldn't be recorded: {:#}", message, err), true),
+        }
+        Ok(())
+    }
+
+    /// Launches the world browser's instance straight into the selected world.
+    fn launch_selected_world(&mut self) -> Result<()> {
+        let Some(ref browser) = self.world_browser else {
+            return Ok(());
+        };
+        let Some(world) = browser.selected() else {
+            return Ok(());
+        };
+        let (instance, target) = (browser.instance.clone(), LaunchTarget::World(world.folder.clone()));
+        self.launch_into(&instance, Some(target))
+    }
+
+    /// Launches the server list's instance and joins the selected server.
+    fn launch_selected_server(&mut self) -> Result<()> {
+        let Some(ref list) = self.server_list else {
+            return Ok(());
+        };
+        let Some(address) = list.join_address() else {
+            return Ok(());
+        };
+        let target = LaunchTarget::Server(address);
+        let Some(instance) = self.instances.iter().find(|i| i.path == list.instance.path).cloned() else {
+            let message = format!("{} is no longer in the instance list", list.instance.name);
+            self.notify(message, true);
+            return Ok(());
+        };
+        self.launch_into(&instance, Some(target))
+    }
+
+    fn toggle_auto_backup(&mut self) {
+        let Some(instance) = self.selected_instance() else {
+            return;
+        };
+        let name = instance.name.clone();
+        let settings = self.config.instance_mut(&instance.folder());
+        settings.auto_backup = !settings.auto_backup;
+        let enabled = settings.auto_backup;
+        match self.config.save() {
+            Ok(()) => {
+                let state = if enabled { "on" } else { "off" };
+                self.notify(format!("Auto-backup on exit {} for {}", state, name), false);
+            }
+            Err(err) => self.notify(format!("{:#}", err), true),
+        }
+    }
+
+    /// Reacts to tracked games that have exited. Returns true if anything changed.
+    fn poll_sessions(&mut self) -> bool {
+        let mut changed = false;
+        for (session, state) in self.tracker.poll() {
+            changed = true;
+            let exit_code = match state {
+                SessionState::Exited => self.crashes.exited(&session.instance, session.started),
+                _ => {
+                    self.crashes.forget(&session.instance);
+                    None
+                }
+            };
+            let crashed = exit_code.filter(|&code| code != 0);
+            let mut unrecorded = None;
+            if let Some(mut launch) = self.launches.remove(&session.instance.path)
+                && state == SessionState::Exited
+            {
+                launch.finish(exit_code, session.peak_memory);
+                unrecorded = launches::record(&launch)
+                    .err()
+                    .map(|err| format!("Couldn't record how {} ended: {:#}", session.instance.name, err));
+            }
+            match state {
+                SessionState::NeverStarted => {
+                    let message = format!("{} never started; stopped watching it", session.instance.name);
+                    self.notify(message, true);
+                }
+                _ if self.config.instance(&session.instance.folder()).auto_backup => {
+                    self.pending_auto_backups.push((session, crashed));
+                }
+                _ => {
+                    let played = format_duration(session.duration().as_secs());
+                    let message = match crashed {
+                        Some(code) => format!(
+                            "{} crashed with exit code {} after {}; C shows its crashes",
+                            session.instance.name, code, played
+                        ),
+                        None => format!("{} exited after {}", session.instance.name, played),
+                    };
+                    self.notify(message, crashed.is_some());
+                }
+            }
+            if let Some(message) = unrecorded {
+                self.notify(message, true);
+            }
+        }
+        if self.task.is_none() && !self.pending_auto_backups.is_empty() {
+            let (session, crashed) = self.pending_auto_backups.remove(0);
+            self.auto_backup(session, crashed);
+            changed = true;
+        }
+        changed
+    }
+
+    /// Backs up every world whose `level.dat` was written during the session,
+    /// then applies the retention policy to those worlds' backups.
+    fn auto_backup(&mut self, session: Session, crashed: Option<i32>) {
+        let backup_root = self.config.backup_dir();
+        let retention = self.config.retention.clone();
+        let format = self.config.backup_format;
+        let name = session.instance.name.clone();
+        let played = format_duration(session.duration().as_secs());
+        let ended = match crashed {
+            Some(code) => format!("crashed with exit code {} after {}", code, played),
+            None => format!("exited after {}", played),
+        };
+        self.spawn_task(format!("Auto-backing up {}", name), move || {
+            let worlds = world::modified_since(&game_dir(&session.instance.path), session.started);
+            if worlds.is_empty() {
+                return Ok(format!("{} {}; no worlds were played", name, ended));
+            }
+            let mut failed = Vec::new();
+            for folder in &worlds {
+                let target = BackupTarget::world(&backup_root, &session.instance, folder);
+                let result = backup::create(&target, format, "-auto").and_then(|_| {
+                    let expired = backup::expired_backups(&backup::list_backups(&target.dir), &retention);
+                    backup::delete_backups(&backup_root, &expired)
+                });
+                if let Err(err) = result {
+                    failed.push(format!("{}: {:#}", folder, err));
+                }
             }
+            if !failed.is_empty() {
+                anyhow::bail!("Auto-backup of {} failed: {}", name, failed.join("; "));
+            }
+            Ok(format!("{} {}; backed up {}", name, ended, worlds.join(", ")))
+        });
+    }
+
+    fn open_folder_selected(&self) -> Result<()> {
+        if let Some(selected) = self.list_state.selected()
+            && let Some(instance) = self.filtered_instances.get(selected)
+        {
+            Command::new("xdg-open")
+                .arg(&instance.path)
+                .spawn()?;
         }
         Ok(())
     }
 
     fn cycle_sort(&mut self) {
         self.sort_mode = self.sort_mode.next();
+        self.sort_descending = self.sort_mode.default_descending();
+        self.resort();
+    }
+
+    fn cycle_secondary_sort(&mut self) {
+        self.secondary_sort = self.secondary_sort.next();
+        self.resort();
+    }
+
+    fn toggle_sort_direction(&mut self) {
+        self.sort_descending = !self.sort_descending;
+        self.resort();
+    }
+
+    fn resort(&mut self) {
         self.sort_instances();
         self.update_filter();
         // Reset selection to top
@@ -202,42 +753,43 @@ impl App {
     }
 
     fn sort_instances(&mut self) {
-        match self.sort_mode {
-            SortMode::Name => {
-                self.instances.sort_by(|a, b| a.name.cmp(&b.name));
-            }
-            SortMode::LastPlayed => {
-                self.instances.sort_by(|a, b| {
-                    b.last_played_ts.unwrap_or(0).cmp(&a.last_played_ts.unwrap_or(0))
-                });
-            }
-            SortMode::Playtime => {
-                self.instances.sort_by(|a, b| {
-                    b.time_played_secs.unwrap_or(0).cmp(&a.time_played_secs.unwrap_or(0))
-                });
-            }
+        sort_instances(&mut self.instances, self.sort_mode, self.sort_descending, self.secondary_sort);
+    }
+
+    fn sort_description(&self) -> String {
+        let arrow = if self.sort_descending { "↓" } else { "↑" };
+        if self.secondary_sort == self.sort_mode {
+            format!("{} {}", self.sort_mode.display(), arrow)
+        } else {
+            format!("{} {}, then {}", self.sort_mode.display(), arrow, self.secondary_sort.display())
         }
     }
 
     fn update_filter(&mut self) {
+        self.filter_error = None;
         if self.search_query.is_empty() {
             self.filtered_instances = self.instances.clone();
         } else {
-            let query = self.search_query.to_lowercase();
-            self.filtered_instances = self.instances
-                .iter()
-                .filter(|instance| instance.name.to_lowercase().contains(&query))
-                .cloned()
-                .collect();
+            match Filter::parse(&self.search_query) {
+                Ok(filter) => {
+                    self.filtered_instances = self.instances
+                        .iter()
+                        .filter(|instance| filter.matches(instance))
+                        .cloned()
+                        .collect();
+                }
+                // Keep the last good results while a query is half typed
+                Err(err) => self.filter_error = Some(format!("{:#}", err)),
+            }
         }
 
         // Reset selection if needed
         if !self.filtered_instances.is_empty() && self.list_state.selected().is_none() {
             self.list_state.select(Some(0));
-        } else if let Some(selected) = self.list_state.selected() {
-            if selected >= self.filtered_instances.len() {
-                self.list_state.select(Some(0));
-            }
+        } else if let Some(selected) = self.list_state.selected()
+            && selected >= self.filtered_instances.len()
+        {
+            self.list_state.select(Some(0));
         }
     }
 
@@ -266,177 +818,1129 @@ impl App {
     fn toggle_details(&mut self) {
         self.details_mode = !self.details_mode;
     }
-}
 
-fn load_instances() -> Result<Vec<Instance>> {
-    let home = std::env::var("HOME")?;
-    let instances_path = Path::new(&home)
-        .join(".var/app/org.prismlauncher.PrismLauncher/data/PrismLauncher/instances");
+    fn selected_instance(&self) -> Option<&Instance> {
+        self.list_state.selected().and_then(|i| self.filtered_instances.get(i))
+    }
 
-    if !instances_path.exists() {
-        return Ok(Vec::new());
+    fn open_worlds(&mut self) {
+        if let Some(instance) = self.selected_instance() {
+            self.world_browser = Some(WorldBrowser::open(instance));
+            self.screen = Screen::Worlds;
+        }
     }
 
-    let mut instances = Vec::new();
+    fn close_worlds(&mut self) {
+        self.world_browser = None;
+        self.screen = Screen::Instances;
+    }
 
-    for entry in fs::read_dir(instances_path)? {
-        let entry = entry?;
-        let path = entry.path();
+    fn open_map(&mut self) {
+        let Some(browser) = self.world_browser.as_ref() else {
+            return;
+        };
+        if let Some(world) = browser.selected() {
+            let world_dir = game_dir(&browser.instance.path).join("saves").join(&world.folder);
+            self.map_view = Some(MapView::open(&world_dir, world));
+            self.screen = Screen::Map;
+        }
+    }
 
-        if path.is_dir() {
-            let config_path = path.join("instance.cfg");
-            if config_path.exists() {
-                if let Ok(config_str) = fs::read_to_string(&config_path) {
-                    if let Ok(config) = serde_ini::from_str::<InstanceConfig>(&config_str) {
-                        let last_played_ts = config.general.last_launch_time;
-                        let last_played = last_played_ts.map(format_timestamp);
-
-                        let time_played_secs = config.general.total_time_played;
-                        let time_played = time_played_secs.map(format_duration);
-
-                        // Try to get Minecraft version and mod loader from mmc-pack.json
-                        let mut mc_version = None;
-                        let mut mod_loader = None;
-
-                        if path.join("mmc-pack.json").exists() {
-                            if let Ok(content) = fs::read_to_string(path.join("mmc-pack.json")) {
-                                if let Ok(pack) = serde_json::from_str::<MMCPack>(&content) {
-                                    // Get Minecraft version
-                                    mc_version = pack.components
-                                        .iter()
-                                        .find(|c| c.uid == "net.minecraft")
-                                        .and_then(|c| c.version.clone());
-
-                                    // Detect mod loader
-                                    for component in &pack.components {
-                                        if component.uid.contains("fabric") {
-                                            mod_loader = Some("Fabric".to_string());
-                                            break;
-                                        } else if component.uid.contains("forge") {
-                                            mod_loader = Some("Forge".to_string());
-                                            break;
-                                        } else if component.uid.contains("quilt") {
-                                            mod_loader = Some("Quilt".to_string());
-                                            break;
-                                        } else if component.uid.contains("neoforge") {
-                                            mod_loader = Some("NeoForge".to_string());
-                                            break;
-                                        }
-                                    }
-
-                                    // If no mod loader found, it's vanilla
-                                    if mod_loader.is_none() {
-                                        mod_loader = Some("Vanilla".to_string());
-                                    }
-                                }
-                            }
-                        }
+    fn close_map(&mut self) {
+        self.map_view = None;
+        self.screen = Screen::Worlds;
+    }
 
-                        instances.push(Instance {
-                            name: config.general.name,
-                            path: path.clone(),
-                            last_played,
-                            last_played_ts,
-                            time_played,
-                            time_played_secs,
-                            mc_version,
-                            mod_loader,
-                        });
-                    }
-                }
+    /// Opens the stats screen, starting on the selected world when coming from the world browser.
+    fn open_stats(&mut self) {
+        let view = match (self.screen, self.world_browser.as_ref()) {
+            (Screen::Worlds, Some(browser)) => {
+                StatsView::open(&browser.instance, browser.selected().map(|w| w.folder.as_str()))
             }
-        }
+            _ => match self.selected_instance() {
+                Some(instance) => StatsView::open(instance, None),
+                None => return,
+            },
+        };
+        self.stats_view = Some(view);
+        self.screen = Screen::Stats;
     }
 
-    instances.sort_by(|a, b| a.name.cmp(&b.name));
-    Ok(instances)
-}
+    fn open_inspector(&mut self) {
+        let Some(browser) = self.world_browser.as_ref() else {
+            return;
+        };
+        if let Some(world) = browser.selected() {
+            let files = inspector::world_files(&game_dir(&browser.instance.path), &world.folder);
+            self.inspector = Some(Inspector::open(&browser.instance, files));
+            self.screen = Screen::Inspector;
+        }
+    }
 
-fn format_timestamp(timestamp_ms: u64) -> String {
-    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    fn open_servers(&mut self) {
+        if let Some(instance) = self.selected_instance() {
+            self.server_list = Some(ServerList::open(instance));
+            self.screen = Screen::Servers;
+        }
+    }
 
-    let duration = Duration::from_millis(timestamp_ms);
-    let datetime = UNIX_EPOCH + duration;
-    let elapsed = SystemTime::now().duration_since(datetime).ok();
+    fn close_servers(&mut self) {
+        self.server_list = None;
+        self.screen = Screen::Instances;
+    }
 
-    if let Some(elapsed) = elapsed {
-        let days = elapsed.as_secs() / 86400;
-        if days > 0 {
-            return format!("{} days ago", days);
+    /// The server list, if its instance is not running. The game rewrites
+    /// `servers.dat` from memory, so edits made meanwhile would be lost.
+    fn idle_server_list(&mut self) -> Option<&mut ServerList> {
+        let instance = &self.server_list.as_ref()?.instance;
+        if game_running(&instance.path) {
+            let message = format!("{} is running; close the game before editing servers", instance.name);
+            self.notify(message, true);
+            return None;
         }
-        let hours = elapsed.as_secs() / 3600;
-        if hours > 0 {
-            return format!("{} hours ago", hours);
+        self.server_list.as_mut()
+    }
+
+    /// Opens an RCON console to the selected server, asking for credentials
+    /// first if none are saved for its address.
+    fn open_server_console(&mut self) {
+        let Some(ref mut list) = self.server_list else {
+            return;
+        };
+        let Some(server) = list.selected() else {
+            return;
+        };
+        // Credentials are saved under the server's address rather than its
+        // name, which is often just the default "Minecraft Server"
+        let key = match ping::normalize_address(&server.ip) {
+            Ok(key) => key,
+            Err(err) => {
+                self.notify(format!("{:#}", err), true);
+                return;
+            }
+        };
+        match self.config.rcon.get(&key) {
+            Some(saved) => {
+                let console = Console::open(&key, saved);
+                self.open_console(console);
+            }
+            None => {
+                let host = ping::parse_address(&key, ping::DEFAULT_PORT).map(|(host, _)| host).unwrap_or_default();
+                list.rcon_form = Some(rcon::CredentialsForm::new(&key, &host));
+            }
         }
-        let minutes = elapsed.as_secs() / 60;
-        if minutes > 0 {
-            return format!("{} minutes ago", minutes);
+    }
+
+    fn save_rcon_credentials(&mut self) {
+        let Some(form) = self.server_list.as_mut().and_then(|list| list.rcon_form.take()) else {
+            return;
+        };
+        let server = form.server();
+        self.config.rcon.insert(form.name.clone(), server.clone());
+        if let Err(err) = self.config.save() {
+            self.notify(format!("Failed to save RCON credentials: {:#}", err), true);
         }
-        "Just now".to_string()
-    } else {
-        "Recently".to_string()
+        self.open_console(Console::open(&form.name, &server));
     }
-}
 
-fn format_duration(seconds: u64) -> String {
-    let hours = seconds / 3600;
-    let minutes = (seconds % 3600) / 60;
+    fn open_console(&mut self, console: Console) {
+        self.console = Some(console);
+        self.console_return = self.screen;
+        self.screen = Screen::Console;
+    }
 
-    if hours > 0 {
-        format!("{}h {}m", hours, minutes)
-    } else if minutes > 0 {
-        format!("{}m", minutes)
-    } else {
-        format!("{}s", seconds)
+    fn close_console(&mut self) {
+        self.console = None;
+        self.screen = self.console_return;
     }
-}
 
-fn launch_instance(instance_name: &str) -> Result<()> {
-    // Call the dedicated launch script
-    let home = std::env::var("HOME")?;
-    let script_path = format!("{}/scripts/launch-minecraft.sh", home);
+    fn request_remove_server(&mut self) {
+        let Some(list) = self.idle_server_list() else {
+            return;
+        };
+        let (Some(index), Some(server)) = (list.state.selected(), list.selected()) else {
+            return;
+        };
+        self.confirm = Some(Confirm {
+            message: format!("Remove {} ({}) from the server list?", server.name, server.ip),
+            action: PendingAction::RemoveServer(index),
+        });
+    }
 
-    Command::new(&script_path)
-        .arg(instance_name)
-        .spawn()?;
+    fn open_datapacks(&mut self) {
+        let Some(browser) = self.world_browser.as_ref() else {
+            return;
+        };
+        if let Some(world) = browser.selected() {
+            let world_dir = browser.saves_dir().join(&world.folder);
+            self.datapack_view = Some(DatapackView::open(&browser.instance, &world_dir, world));
+            self.screen = Screen::Datapacks;
+        }
+    }
 
-    Ok(())
-}
+    fn open_logs(&mut self) {
+        if let Some(instance) = self.selected_instance() {
+            self.log_view = Some(LogView::open(instance));
+            self.screen = Screen::Logs;
+        }
+    }
 
-fn main() -> Result<()> {
-    let mut terminal = setup_terminal()?;
-    let mut app = App::new()?;
-    let res = run_app(&mut terminal, &mut app);
-    restore_terminal(&mut terminal)?;
+    fn close_logs(&mut self) {
+        self.log_view = None;
+        self.screen = Screen::Instances;
+    }
 
-    if let Err(err) = res {
-        eprintln!("Error: {:?}", err);
-        return Ok(());
+    /// Analyzes the log screen's current file, if it's a crash report.
+    fn analyze_log_file(&mut self) {
+        let Some(ref view) = self.log_view else {
+            return;
+        };
+        let Some(file) = view.files.current() else {
+            return;
+        };
+        let file_name = file.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        if !crash::is_crash_file(&file_name) {
+            self.notify("Only crash reports and hs_err_pid logs can be analyzed", true);
+            return;
+        }
+        let (instance_name, name, path) = (view.instance_name.clone(), file.name.clone(), file.path.clone());
+        self.open_crash(&instance_name, &name, &path);
     }
 
-    // Launch instance AFTER terminal is restored
-    if app.should_launch {
-        app.launch_selected()?;
-        // Give the script time to spawn the flatpak process
-        thread::sleep(Duration::from_secs(1));
+    fn open_crash(&mut self, instance_name: &str, name: &str, path: &Path) {
+        match CrashView::open(instance_name, name, path) {
+            Ok(view) => {
+                self.crash_view = Some(view);
+                self.crash_return = self.screen;
+                self.screen = Screen::Crash;
+            }
+            Err(err) => self.notify(format!("{:#}", err), true),
+        }
     }
 
-    Ok(())
-}
+    fn open_crash_history(&mut self) {
+        let Some(instance) = self.selected_instance().cloned() else {
+            return;
+        };
+        self.crashes.mark_seen(&instance);
+        let crashes = self.crashes.history(&instance);
+        self.crash_history = Some(CrashHistory::new(instance, crashes));
+        self.screen = Screen::CrashHistory;
+    }
 
-fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend)?;
-    Ok(terminal)
-}
+    fn close_crash_history(&mut self) {
+        self.crash_history = None;
+        self.screen = Screen::Instances;
+    }
 
-fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
+    /// Opens the selected crash of the history in the analyzer.
+    fn analyze_selected_crash(&mut self) {
+        let Some(ref history) = self.crash_history else {
+            return;
+        };
+        let Some(crash) = history.selected() else {
+            return;
+        };
+        match crash.kind {
+            CrashKind::Report(ref file) => {
+                let (instance_name, name, path) = (history.instance.name.clone(), file.name.clone(), file.path.clone());
+                self.open_crash(&instance_name, &name, &path);
+            }
+            CrashKind::Exit(_) => self.notify("This crash left no report to analyze", true),
+        }
+    }
+
+    fn open_launch_history(&mut self) {
+        let selected = self.selected_instance();
+        self.history_view = Some(HistoryView::new(launches::load(), selected));
+        self.screen = Screen::Launches;
+    }
+
+    fn close_launch_history(&mut self) {
+        self.history_view = None;
+        self.screen = Screen::Instances;
+    }
+
+    fn close_crash(&mut self) {
+        self.crash_view = None;
+        self.screen = self.crash_return;
+    }
+
+    fn open_dedicated(&mut self) {
+        self.dedicated_view = Some(DedicatedView::open(&self.config.dedicated_servers));
+        self.screen = Screen::Dedicated;
+    }
+
+    fn close_dedicated(&mut self) {
+        self.dedicated_view = None;
+        self.screen = Screen::Instances;
+    }
+
+    fn reload_dedicated(&mut self) {
+        if let Some(view) = self.dedicated_view.as_mut() {
+            view.reload(&self.config.dedicated_servers);
+        }
+    }
+
+    /// Adds a server folder typed into the prompt, named after the folder.
+    fn add_dedicated_server(&mut self, text: &str) -> Result<String> {
+        let text = text.trim();
+        let path = match text.strip_prefix("~/") {
+            Some(rest) => PathBuf::from(std::env::var("HOME")?).join(rest),
+            None => PathBuf::from(text),
+        };
+        let path = fs::canonicalize(&path).with_context(|| format!("finding {}", path.display()))?;
+        if !path.is_dir() {
+            anyhow::bail!("{} is not a folder", path.display());
+        }
+        if self.config.dedicated_servers.values().any(|s| s.path == path) {
+            anyhow::bail!("{} is already in the list", path.display());
+        }
+        let base = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "server".into());
+        let mut name = base.clone();
+        for n in 2.. {
+            if !self.config.dedicated_servers.contains_key(&name) {
+                break;
+            }
+            name = format!("{} ({})", base, n);
+        }
+        let settings = config::DedicatedServerSettings {
+            path,
+            ..Default::default()
+        };
+        self.config.dedicated_servers.insert(name.clone(), settings);
+        self.config.save()?;
+        self.reload_dedicated();
+        if let Some(view) = self.dedicated_view.as_mut()
+            && let Some(index) = view.servers.iter().position(|s| s.name == name)
+        {
+            view.state.select(Some(index));
+        }
+        Ok(format!("Added {}", name))
+    }
+
+    fn set_dedicated_java(&mut self, text: &str) -> Result<String> {
+        let Some(name) = self.selected_dedicated().map(|s| s.name.clone()) else {
+            return Ok(String::new());
+        };
+        let java = text.trim();
+        let settings = self.config.dedicated_servers.get_mut(&name).context("Server no longer exists")?;
+        settings.java = if java.is_empty() || java == "java" { None } else { Some(java.to_string()) };
+        self.config.save()?;
+        self.reload_dedicated();
+        Ok(format!("Java command for {} saved; it applies from the next start", name))
+    }
+
+    fn remove_dedicated_server(&mut self) {
+        let Some(server) = self.selected_dedicated() else {
+            return;
+        };
+        let (name, path) = (server.name.clone(), server.settings.path.clone());
+        if self.supervisor.status(&path) != dedicated::Status::Stopped {
+            self.notify(format!("{} is running; stop it first", name), true);
+            return;
+        }
+        self.config.dedicated_servers.remove(&name);
+        let result = self
+            .config
+            .save()
+            .map(|_| format!("Removed {} from the list; its folder was left alone", name));
+        self.notify_result(result);
+        self.reload_dedicated();
+    }
+
+    fn selected_dedicated(&self) -> Option<&dedicated::ServerEntry> {
+        self.dedicated_view.as_ref().and_then(DedicatedView::selected)
+    }
+
+    /// Starts the selected server, or asks it to stop if it is running.
+    fn start_stop_dedicated(&mut self) {
+        let Some(server) = self.selected_dedicated() else {
+            return;
+        };
+        let (name, settings) = (server.name.clone(), server.settings.clone());
+        let result = match self.supervisor.status(&settings.path) {
+            dedicated::Status::Stopped => self.supervisor.start(&name, &settings).map(|_| format!("Starting {}", name)),
+            dedicated::Status::Running(_) => self.supervisor.stop(&settings.path).map(|_| format!("Stopping {}", name)),
+            dedicated::Status::External => Err(anyhow::anyhow!(
+                "{} was started outside minecraft-tui; stop it from its own console or over RCON",
+                name
+            )),
+        };
+        self.notify_result(result);
+    }
+
+    fn request_kill_dedicated(&mut self) {
+        let Some(server) = self.selected_dedicated() else {
+            return;
+        };
+        let (name, path) = (server.name.clone(), server.settings.path.clone());
+        if !matches!(self.supervisor.status(&path), dedicated::Status::Running(_)) {
+            self.notify(format!("{} is not running from minecraft-tui", name), true);
+            return;
+        }
+        self.confirm = Some(Confirm {
+            message: format!("Kill {} without saving?", name),
+            action: PendingAction::KillServer(path, name),
+        });
+    }
+
+    fn request_accept_eula(&mut self) {
+        let Some(server) = self.selected_dedicated() else {
+            return;
+        };
+        if server.eula == Some(true) {
+            return;
+        }
+        self.confirm = Some(Confirm {
+            message: format!(
+                "Accept the Minecraft EULA (https://aka.ms/MinecraftEULA) for {}?",
+                server.name
+            ),
+            action: PendingAction::AcceptEula(server.settings.path.clone(), server.name.clone()),
+        });
+    }
+
+    fn open_properties_editor(&mut self) {
+        let Some(view) = self.dedicated_view.as_mut() else {
+            return;
+        };
+        let Some(server) = view.selected() else {
+            return;
+        };
+        match PropertiesEditor::open(&server.settings.path) {
+            Ok(editor) => view.editor = Some(editor),
+            Err(err) => self.notify(format!("{:#}", err), true),
+        }
+    }
+
+    fn close_properties_editor(&mut self) {
+        if let Some(view) = self.dedicated_view.as_mut() {
+            view.editor = None;
+        }
+        self.reload_dedicated();
+    }
+
+    fn run_dedicated_installer(&mut self) {
+        let Some(server) = self.selected_dedicated() else {
+            return;
+        };
+        let (name, settings) = (server.name.clone(), server.settings.clone());
+        self.spawn_task(format!("Installing {}", name), move || dedicated::run_installer(&settings));
+    }
+
+    /// Opens an RCON console using the credentials in the server's `server.properties`.
+    fn open_dedicated_rcon(&mut self) {
+        let Some(server) = self.selected_dedicated() else {
+            return;
+        };
+        let Some((address, password)) = server.rcon() else {
+            let message = format!("RCON is off for {}; set enable-rcon and rcon.password (e)", server.name);
+            self.notify(message, true);
+            return;
+        };
+        let console = Console::open(&server.name, &config::RconServer { address, password });
+        self.open_console(console);
+    }
+
+    fn close_datapacks(&mut self) {
+        self.datapack_view = None;
+        self.screen = Screen::Worlds;
+    }
+
+    fn toggle_datapack(&mut self) {
+        let Some(view) = self.datapack_view.as_mut() else {
+            return;
+        };
+        if game_running(&view.instance_path) {
+            let message = format!("{} is running; close the game before changing data packs", view.instance_name);
+            self.notify(message, true);
+            return;
+        }
+        let result = view.toggle();
+        self.notify_result(result);
+    }
+
+    /// Leaves the inspector, asking first if there are unsaved edits.
+    fn request_close_inspector(&mut self) {
+        match self.inspector.as_ref() {
+            Some(inspector) if inspector.dirty => {
+                self.confirm = Some(Confirm {
+                    message: format!("Discard unsaved changes to {}?", inspector.current_file().label),
+                    action: PendingAction::DiscardEdits,
+                });
+            }
+            _ => self.close_inspector(),
+        }
+    }
+
+    fn close_inspector(&mut self) {
+        self.inspector = None;
+        self.screen = Screen::Worlds;
+        if let Some(browser) = self.world_browser.as_mut() {
+            browser.reload();
+        }
+    }
+
+    fn save_inspector(&mut self) {
+        let Some(inspector) = self.inspector.as_mut() else {
+            return;
+        };
+        if inspector.dirty && game_running(&inspector.instance_path) {
+            let message = format!("{} is running; close the game before saving", inspector.instance_name);
+            self.notify(message, true);
+            return;
+        }
+        let result = inspector.save();
+        self.notify_result(result);
+    }
+
+    fn close_stats(&mut self) {
+        self.stats_view = None;
+        self.screen = if self.world_browser.is_some() { Screen::Worlds } else { Screen::Instances };
+    }
+
+    fn notify(&mut self, message: impl Into<String>, error: bool) {
+        self.notification = Some(Notification {
+            message: message.into(),
+            error,
+        });
+    }
+
+    /// Shows a success message, or the error chain in red.
+    fn notify_result(&mut self, result: Result<String>) {
+        match result {
+            Ok(message) => self.notify(message, false),
+            Err(err) => self.notify(format!("{:#}", err), true),
+        }
+    }
+
+    /// Runs `job` on a background thread; its result becomes a notification.
+    /// Only one task runs at a time.
+    fn spawn_task(&mut self, label: impl Into<String>, job: impl FnOnce() -> Result<String> + Send + 'static) {
+        if let Some(ref task) = self.task {
+            let message = format!("Still busy: {}", task.label);
+            self.notify(message, true);
+            return;
+        }
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(job());
+        });
+        self.task = Some(Task {
+            label: label.into(),
+            result,
+        });
+    }
+
+    /// Fills in instance sizes as they're measured. Returns true if any arrived.
+    fn poll_sizes(&mut self) -> bool {
+        let Some(ref sizes) = self.sizes else {
+            return false;
+        };
+        let mut changed = false;
+        loop {
+            match sizes.try_recv() {
+                Ok((path, size)) => {
+                    for instance in self.instances.iter_mut().chain(self.filtered_instances.iter_mut()) {
+                        if instance.path == path {
+                            instance.disk_size = size;
+                        }
+                    }
+                    changed = true;
+                }
+                Err(mpsc::TryRecvError::Empty) => return changed,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+        self.sizes = None;
+        // Sorting by size waits for every size, so the list doesn't shuffle while it fills in
+        if self.sort_mode == SortMode::DiskSize || self.secondary_sort == SortMode::DiskSize {
+            let selected = self.selected_instance().map(|i| i.path.clone());
+            self.sort_instances();
+            self.update_filter();
+            if let Some(index) = self.filtered_instances.iter().position(|i| Some(&i.path) == selected.as_ref()) {
+                self.list_state.select(Some(index));
+            }
+        }
+        true
+    }
+
+    /// Re-checks which instances have a game running while the instance list
+    /// is shown, at most every [`logs::RUNNING_INTERVAL`]. Returns true if that changed.
+    fn poll_running(&mut self) -> bool {
+        let now = Instant::now();
+        if self.screen != Screen::Instances
+            || self.last_running_check.is_some_and(|last| now.duration_since(last) < logs::RUNNING_INTERVAL)
+        {
+            return false;
+        }
+        self.last_running_check = Some(now);
+        let paths: Vec<PathBuf> = self.instances.iter().map(|i| i.path.clone()).collect();
+        let flags = process::java_running_in(&paths);
+        let running: HashSet<PathBuf> = paths
+            .into_iter()
+            .zip(flags)
+            .filter_map(|(path, running)| running.then_some(path))
+            .collect();
+        let changed = running != self.running;
+        self.running = running;
+        changed
+    }
+
+    /// Picks up the result of a finished background task. Returns true if one finished.
+    fn poll_task(&mut self) -> bool {
+        let Some(ref task) = self.task else {
+            return false;
+        };
+        let result = match task.result.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => Err(anyhow::anyhow!("{} stopped unexpectedly", task.label)),
+        };
+        self.task = None;
+        self.notify_result(result);
+        if let Some(ref mut browser) = self.backup_browser {
+            browser.reload();
+        }
+        if let Some(ref mut browser) = self.world_browser {
+            browser.reload();
+        }
+        true
+    }
+
+    fn backup(&mut self, target: BackupTarget, format: BackupFormat) {
+        let warning = if game_running(&target.instance_path) {
+            " (instance was running, backup may be inconsistent)"
+        } else {
+            ""
+        };
+        let label = format!("Backing up {}", target.label);
+        self.spawn_task(label, move || {
+            let (_, written) = backup::create(&target, format, "")?;
+            let kind = match format {
+                BackupFormat::Archive => "Backed up",
+                BackupFormat::Snapshot => "Snapshotted",
+            };
+            Ok(format!("{} {} ({} written){}", kind, target.label, format_size(written), warning))
+        });
+    }
+
+    fn backup_selected_instance(&mut self) {
+        if let Some(instance) = self.selected_instance() {
+            let target = BackupTarget::instance(&self.config.backup_dir(), instance);
+            self.backup(target, self.config.backup_format);
+        }
+    }
+
+    fn selected_world_target(&self) -> Option<BackupTarget> {
+        let browser = self.world_browser.as_ref()?;
+        let world = browser.selected()?;
+        Some(BackupTarget::world(&self.config.backup_dir(), &browser.instance, &world.folder))
+    }
+
+    /// The world browser, if its instance is not running. Changing a world under
+    /// a running game would be undone or corrupted when it next saves.
+    fn idle_world_browser(&mut self) -> Option<&mut WorldBrowser> {
+        let instance = &self.world_browser.as_ref()?.instance;
+        if game_running(&instance.path) {
+            let message = format!("{} is running; close the game first", instance.name);
+            self.notify(message, true);
+            return None;
+        }
+        self.world_browser.as_mut()
+    }
+
+    fn start_rename(&mut self) {
+        if let Some(browser) = self.idle_world_browser()
+            && let Some(world) = browser.selected()
+        {
+            browser.rename_input = Some(world.display_name().to_string());
+        }
+    }
+
+    fn finish_rename(&mut self, name: &str) {
+        let backup_root = self.config.backup_dir();
+        let Some(browser) = self.world_browser.as_mut() else {
+            return;
+        };
+        let Some(folder) = browser.selected().map(|w| w.folder.clone()) else {
+            return;
+        };
+        let result = world::rename_world(&browser.saves_dir(), &folder, name).map(|new_folder| {
+            // Keep the world's backups with it
+            let old_backups = BackupTarget::world(&backup_root, &browser.instance, &folder).dir;
+            let new_backups = BackupTarget::world(&backup_root, &browser.instance, &new_folder).dir;
+            if new_folder != folder && old_backups.is_dir() && !new_backups.exists() {
+                let _ = fs::rename(&old_backups, &new_backups);
+            }
+            browser.reload();
+            browser.select_folder(&new_folder);
+            format!("Renamed to {}", name.trim())
+        });
+        self.notify_result(result);
+    }
+
+    fn start_duplicate(&mut self) {
+        let instances = self.instances.clone();
+        if let Some(browser) = self.idle_world_browser()
+            && browser.selected().is_some()
+        {
+            let mut state = ListState::default();
+            state.select(instances.iter().position(|i| i.path == browser.instance.path).or(Some(0)));
+            browser.copy_picker = Some(CopyPicker { instances, state });
+        }
+    }
+
+    fn finish_duplicate(&mut self, target: Instance) {
+        let Some(browser) = self.world_browser.as_ref() else {
+            return;
+        };
+        let Some(world) = browser.selected() else {
+            return;
+        };
+        if game_running(&target.path) {
+            self.notify(format!("{} is running; close the game first", target.name), true);
+            return;
+        }
+        let source = browser.saves_dir().join(&world.folder);
+        let dest_saves = game_dir(&target.path).join("saves");
+        let name = if target.path == browser.instance.path {
+            format!("{} (Copy)", world.display_name())
+        } else {
+            world.display_name().to_string()
+        };
+        let label = format!("Copying {} to {}", world.display_name(), target.name);
+        self.spawn_task(label, move || {
+            let folder = world::duplicate_world(&source, &dest_saves, &name)?;
+            Ok(format!("Copied to {} as {}", target.name, folder))
+        });
+    }
+
+    fn request_trash_world(&mut self) {
+        let Some(browser) = self.idle_world_browser() else {
+            return;
+        };
+        let Some(world) = browser.selected() else {
+            return;
+        };
+        let name = world.display_name().to_string();
+        let world_dir = browser.saves_dir().join(&world.folder);
+        self.confirm = Some(Confirm {
+            message: format!("Move {} to the trash?\nIt can be restored from your file manager.", name),
+            action: PendingAction::TrashWorld(world_dir, name),
+        });
+    }
+
+    fn open_backups(&mut self, target: BackupTarget) {
+        self.backup_browser = Some(BackupBrowser::open(target));
+        self.screen = Screen::Backups;
+    }
+
+    fn close_backups(&mut self) {
+        self.backup_browser = None;
+        self.screen = if self.world_browser.is_some() { Screen::Worlds } else { Screen::Instances };
+    }
+
+    fn request_restore(&mut self) {
+        let Some(browser) = self.backup_browser.as_ref() else {
+            return;
+        };
+        let Some(selected) = browser.selected() else {
+            return;
+        };
+        let target = browser.target.clone();
+        if game_running(&target.instance_path) {
+            self.notify(format!("{} is running; close the game before restoring", target.instance_name), true);
+            return;
+        }
+        self.confirm = Some(Confirm {
+            message: format!(
+                "Restore {} to {}?\nThe current state is backed up first.",
+                target.label, selected.name
+            ),
+            action: PendingAction::Restore(target, selected.clone()),
+        });
+    }
+
+    fn request_prune(&mut self) {
+        let Some(browser) = self.backup_browser.as_ref() else {
+            return;
+        };
+        let expired = backup::expired_backups(&browser.backups, &self.config.retention);
+        if expired.is_empty() {
+            let message = format!("Nothing to prune ({})", backup::describe_policy(&self.config.retention));
+            self.notify(message, false);
+            return;
+        }
+        self.confirm = Some(Confirm {
+            message: format!(
+                "Delete {} backup(s) outside the retention policy?\n({})",
+                expired.len(),
+                backup::describe_policy(&self.config.retention)
+            ),
+            action: PendingAction::Prune(expired),
+        });
+    }
+
+    fn collect_garbage(&mut self) {
+        let backup_root = self.config.backup_dir();
+        self.spawn_task("Cleaning up the snapshot store", move || {
+            let (objects, bytes) = backup::collect_garbage(&backup_root)?;
+            Ok(format!("Removed {} unused object(s), freed {}", objects, format_size(bytes)))
+        });
+    }
+
+    fn answer_confirm(&mut self, yes: bool) {
+        let Some(confirm) = self.confirm.take() else {
+            return;
+        };
+        if !yes {
+            return;
+        }
+        match confirm.action {
+            PendingAction::Restore(target, chosen) => {
+                let label = format!("Restoring {}", target.label);
+                self.spawn_task(label, move || {
+                    backup::restore(&target, &chosen)?;
+                    Ok(format!("Restored {} from {}", target.label, chosen.name))
+                });
+            }
+            PendingAction::Prune(expired) => {
+                let backup_root = self.config.backup_dir();
+                self.spawn_task("Pruning backups", move || {
+                    backup::delete_backups(&backup_root, &expired)?;
+                    Ok(format!("Deleted {} old backup(s)", expired.len()))
+                });
+            }
+            PendingAction::DiscardEdits => self.close_inspector(),
+            PendingAction::DiscardProperties => self.close_properties_editor(),
+            PendingAction::KillServer(path, name) => {
+                let result = self.supervisor.kill(&path).map(|_| format!("Killed {}", name));
+                self.notify_result(result);
+            }
+            PendingAction::AcceptEula(path, name) => {
+                let result = dedicated::accept_eula(&path).map(|_| format!("Accepted the EULA for {}", name));
+                self.notify_result(result);
+                self.reload_dedicated();
+            }
+            PendingAction::RemoveServer(index) => {
+                if let Some(list) = self.server_list.as_mut() {
+                    let result = list.remove(index);
+                    self.notify_result(result);
+                }
+            }
+            PendingAction::TrashWorld(world_dir, name) => {
+                let result = trash::trash(&world_dir).map(|_| format!("Moved {} to the trash", name));
+                self.notify_result(result);
+                if let Some(browser) = self.world_browser.as_mut() {
+                    browser.reload();
+                }
+            }
+        }
+    }
+}
+
+fn prism_data_dir() -> Result<PathBuf> {
+    let home = std::env::var("HOME")?;
+    Ok(Path::new(&home).join(".var/app/org.prismlauncher.PrismLauncher/data/PrismLauncher"))
+}
+
+fn load_instances() -> Result<Vec<Instance>> {
+    let data_dir = prism_data_dir()?;
+    let instances_path = data_dir.join("instances");
+
+    if !instances_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let catalog = Catalog::load(&data_dir.join("meta"));
+    let icons_dir = data_dir.join("icons");
+    let mut instances = Vec::new();
+
+    for entry in fs::read_dir(instances_path)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.is_dir()
+            && let Some(mut instance) = load_instance(&path, &icons_dir)
+        {
+            let loader = instance
+                .mod_loader
+                .and_then(|loader| loader.uid())
+                .zip(instance.loader_version.as_deref());
+            instance.version_status = catalog.status(instance.mc_version.as_deref(), loader);
+            instances.push(instance);
+        }
+    }
+
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(instances)
+}
+
+/// Walks each instance folder on a background thread, since large modpacks
+/// can take seconds to measure.
+fn measure_sizes(instances: &[Instance]) -> mpsc::Receiver<(PathBuf, Option<u64>)> {
+    let paths: Vec<PathBuf> = instances.iter().map(|i| i.path.clone()).collect();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for path in paths {
+            let size = dir_size(&path).ok();
+            if sender.send((path, size)).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+fn load_instance(path: &Path, icons_dir: &Path) -> Option<Instance> {
+    let config_str = fs::read_to_string(path.join("instance.cfg")).ok()?;
+    let config = serde_ini::from_str::<InstanceConfig>(&config_str).ok()?;
+
+    let last_played_ts = config.general.last_launch_time;
+    let last_played = last_played_ts.map(format_timestamp);
+
+    let time_played_secs = config.general.total_time_played;
+    let time_played = time_played_secs.map(format_duration);
+
+    // Minecraft version, mod loader and the full component list come from mmc-pack.json
+    let pack = read_mmc_pack(path);
+    let mc_version = pack.as_ref().and_then(MMCPack::minecraft_version);
+    let (mod_loader, loader_version) = match pack.as_ref().map(MMCPack::mod_loader) {
+        Some((loader, version)) => (Some(loader), version),
+        None => (None, None),
+    };
+    let components = pack.map(|p| p.components).unwrap_or_default();
+
+    let mod_count = count_mods(&game_dir(path));
+    let world_count = world::count_worlds(&game_dir(path));
+    let created_ts = fs::metadata(path)
+        .and_then(|m| m.created())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64);
+
+    let icon = config
+        .general
+        .icon_key
+        .as_deref()
+        .and_then(|key| Icon::load(icons_dir, key))
+        .map(Arc::new);
+
+    Some(Instance {
+        name: config.general.name,
+        path: path.to_path_buf(),
+        last_played,
+        last_played_ts,
+        time_played,
+        time_played_secs,
+        mc_version,
+        mod_loader,
+        loader_version,
+        components,
+        version_status: VersionStatus::default(),
+        mod_count,
+        // Measured in the background; see `measure_sizes`
+        disk_size: None,
+        created_ts,
+        icon,
+        world_count,
+    })
+}
+
+/// The `.minecraft` folder Prism keeps inside each instance (`minecraft` on older setups).
+fn game_dir(instance_path: &Path) -> PathBuf {
+    let dot_minecraft = instance_path.join(".minecraft");
+    if dot_minecraft.is_dir() {
+        dot_minecraft
+    } else {
+        instance_path.join("minecraft")
+    }
+}
+
+fn count_mods(game_dir: &Path) -> Option<usize> {
+    let entries = fs::read_dir(game_dir.join("mods")).ok()?;
+    let count = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.path().extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext == "jar")
+                .unwrap_or(false)
+        })
+        .count();
+    Some(count)
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            total += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+fn format_timestamp(timestamp_ms: u64) -> String {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    let duration = Duration::from_millis(timestamp_ms);
+    let datetime = UNIX_EPOCH + duration;
+    let elapsed = SystemTime::now().duration_since(datetime).ok();
+
+    if let Some(elapsed) = elapsed {
+        let days = elapsed.as_secs() / 86400;
+        if days > 0 {
+            return format!("{} days ago", days);
+        }
+        let hours = elapsed.as_secs() / 3600;
+        if hours > 0 {
+            return format!("{} hours ago", hours);
+        }
+        let minutes = elapsed.as_secs() / 60;
+        if minutes > 0 {
+            return format!("{} minutes ago", minutes);
+        }
+        "Just now".to_string()
+    } else {
+        "Recently".to_string()
+    }
+}
+
+fn format_duration(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Where to go once the game has started, passed on to Prism's quick play options.
+#[derive(Debug, Clone, PartialEq)]
+enum LaunchTarget {
+    /// A server address, joined with `--server`.
+    Server(String),
+    /// A save folder name, opened with `--world`.
+    World(String),
+}
+
+impl LaunchTarget {
+    fn args(&self) -> [&str; 2] {
+        match self {
+            LaunchTarget::Server(address) => ["--server", address],
+            LaunchTarget::World(folder) => ["--world", folder],
+        }
+    }
+
+    /// How the launch history shows it.
+    fn describe(&self) -> String {
+        match self {
+            LaunchTarget::Server(address) => format!("server {}", address),
+            LaunchTarget::World(folder) => format!("world {}", folder),
+        }
+    }
+
+    /// Prism can join a server on any version, falling back to the old
+    /// `--server`/`--port` game arguments, but opening a world needs the
+    /// quick play support added in 1.20 (snapshot 23w14a).
+    fn check_version(&self, mc_version: Option<&str>) -> Result<()> {
+        let LaunchTarget::World(_) = self else {
+            return Ok(());
+        };
+        let Some(version) = mc_version else {
+            anyhow::bail!("Unknown Minecraft version; launching into a world needs 1.20 or newer");
+        };
+        if McVersion::parse(version) < McVersion::parse("23w14a") {
+            anyhow::bail!("Launching into a world needs Minecraft 1.20 or newer (this instance is {})", version);
+        }
+        Ok(())
+    }
+}
+
+fn launch_instance(instance_name: &str, target: Option<&LaunchTarget>) -> Result<()> {
+    // Call the dedicated launch script
+    let home = std::env::var("HOME")?;
+    let script_path = format!("{}/scripts/launch-minecraft.sh", home);
+
+    let mut command = Command::new(&script_path);
+    command.arg(instance_name);
+    if let Some(target) = target {
+        command.args(target.args());
+    }
+    command.spawn()?;
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("rcon") {
+        if let Err(err) = rcon::run_cli(&args[1..]) {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut terminal = setup_terminal()?;
+    let mut app = App::new()?;
+    let res = run_app(&mut terminal, &mut app);
+    restore_terminal(&mut terminal)?;
+    app.supervisor.shutdown();
+
+    if let Err(err) = res {
+        eprintln!("Error: {:?}", err);
+        return Ok(());
+    }
+
+    // Launch instance AFTER terminal is restored
+    if app.should_launch {
+        app.launch_selected()?;
+        // Give the script time to spawn the flatpak process
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    Ok(())
+}
+
+fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let terminal = Terminal::new(backend)?;
+    Ok(terminal)
+}
+
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
         LeaveAlternateScreen,
         DisableMouseCapture
     )?;
@@ -444,81 +1948,1285 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Re
     Ok(())
 }
 
-fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
-    loop {
-        terminal.draw(|f| ui(f, app))?;
-
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                if app.search_mode {
-                    // In search mode
-                    match key.code {
-                        KeyCode::Esc => {
-                            app.exit_search_mode();
-                        }
-                        KeyCode::Char(c) => {
-                            app.update_search_query(c);
-                        }
-                        KeyCode::Backspace => {
-                            app.backspace_search();
-                        }
-                        KeyCode::Enter => {
-                            // Exit search and launch
-                            app.exit_search_mode();
-                            app.should_quit = true;
-                            app.should_launch = true;
-                        }
-                        KeyCode::Down => {
-                            app.next();
-                        }
-                        KeyCode::Up => {
-                            app.previous();
-                        }
-                        _ => {}
-                    }
-                } else {
-                    // Normal mode
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => {
-                            app.should_quit = true;
-                        }
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            app.next();
-                        }
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            app.previous();
-                        }
-                        KeyCode::Enter => {
-                            app.should_quit = true;
-                            app.should_launch = true;
-                        }
-                        KeyCode::Char('o') => {
-                            app.open_folder_selected()?;
-                        }
-                        KeyCode::Char('s') => {
-                            app.cycle_sort();
-                        }
-                        KeyCode::Char('/') => {
-                            app.enter_search_mode();
-                        }
-                        KeyCode::Char('i') => {
-                            app.toggle_details();
-                        }
-                        _ => {}
+fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    // Only redraw when something changed; drawing checks every instance's running state
+    let mut dirty = true;
+    loop {
+        if dirty {
+            terminal.draw(|f| ui(f, app))?;
+
+            // Graphics-protocol icons are painted on top of the finished frame
+            if app.icon_painter.needs_clear(app.icon_placement.as_ref()) {
+                terminal.clear()?;
+                app.icon_painter.reset();
+                terminal.draw(|f| ui(f, app))?;
+            }
+            app.icon_painter.paint(&mut io::stdout(), app.icon_placement.as_ref())?;
+            dirty = false;
+        }
+
+        if app.poll_task() {
+            dirty = true;
+        }
+        if app.poll_sizes() {
+            dirty = true;
+        }
+        if app.poll_running() {
+            dirty = true;
+        }
+        if app.poll_sessions() {
+            dirty = true;
+        }
+        for message in app.crashes.scan(&app.instances) {
+            app.notify(message, true);
+            dirty = true;
+        }
+        if let Some(ref mut browser) = app.world_browser
+            && browser.poll()
+        {
+            dirty = true;
+        }
+        if let Some(ref mut map) = app.map_view
+            && map.poll()
+        {
+            dirty = true;
+        }
+        if let Some(ref mut list) = app.server_list
+            && list.poll()
+        {
+            dirty = true;
+        }
+        if let Some(ref mut console) = app.console
+            && console.poll()
+        {
+            dirty = true;
+        }
+        if let Some(ref mut view) = app.log_view
+            && view.poll()
+        {
+            dirty = true;
+        }
+        let watched = app.dedicated_view.as_ref().map(DedicatedView::watched).unwrap_or_default();
+        let (changed, stopped) = app.supervisor.poll(&watched);
+        if changed {
+            dirty = true;
+        }
+        for message in stopped {
+            app.notify(message, false);
+        }
+
+        if !event::poll(TICK)? {
+            continue;
+        }
+        dirty = true;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            app.notification = None;
+            if app.confirm.is_some() {
+                app.answer_confirm(matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter));
+                continue;
+            }
+            match app.screen {
+                Screen::Instances => handle_instances_key(app, key.code)?,
+                Screen::Worlds => handle_worlds_key(app, key.code),
+                Screen::Backups => handle_backups_key(app, key.code),
+                Screen::Map => handle_map_key(app, key.code),
+                Screen::Stats => handle_stats_key(app, key.code),
+                Screen::Inspector => handle_inspector_key(app, key.code),
+                Screen::Datapacks => handle_datapacks_key(app, key.code),
+                Screen::Servers => handle_servers_key(app, key.code),
+                Screen::Console => handle_console_key(app, key.code),
+                Screen::Dedicated => handle_dedicated_key(app, key.code),
+                Screen::Logs => handle_logs_key(app, key.code),
+                Screen::Crash => handle_crash_key(app, key.code),
+                Screen::CrashHistory => handle_crash_history_key(app, key.code),
+                Screen::Launches => handle_launches_key(app, key.code),
+            }
+        }
+
+        if app.should_quit {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_instances_key(app: &mut App, code: KeyCode) -> Result<()> {
+    if app.search_mode {
+        // In search mode
+        match code {
+            KeyCode::Esc => {
+                app.exit_search_mode();
+            }
+            KeyCode::Char(c) => {
+                app.update_search_query(c);
+            }
+            KeyCode::Backspace => {
+                app.backspace_search();
+            }
+            KeyCode::Enter => {
+                // Exit search and launch
+                app.exit_search_mode();
+                app.should_quit = true;
+                app.should_launch = true;
+            }
+            KeyCode::Down => {
+                app.next();
+            }
+            KeyCode::Up => {
+                app.previous();
+            }
+            _ => {}
+        }
+    } else {
+        // Normal mode
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                app.should_quit = true;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.next();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.previous();
+            }
+            KeyCode::Enter => {
+                app.should_quit = true;
+                app.should_launch = true;
+            }
+            KeyCode::Char('o') => {
+                app.open_folder_selected()?;
+            }
+            KeyCode::Char('s') => {
+                app.cycle_sort();
+            }
+            KeyCode::Char('S') => {
+                app.cycle_secondary_sort();
+            }
+            KeyCode::Char('d') => {
+                app.toggle_sort_direction();
+            }
+            KeyCode::Char('/') => {
+                app.enter_search_mode();
+            }
+            KeyCode::Char('i') => {
+                app.toggle_details();
+            }
+            KeyCode::Char('w') => {
+                app.open_worlds();
+            }
+            KeyCode::Char('t') => {
+                app.open_stats();
+            }
+            KeyCode::Char('m') => {
+                app.open_servers();
+            }
+            KeyCode::Char('D') => {
+                app.open_dedicated();
+            }
+            KeyCode::Char('L') => {
+                app.open_logs();
+            }
+            KeyCode::Char('C') => {
+                app.open_crash_history();
+            }
+            KeyCode::Char('H') => {
+                app.open_launch_history();
+            }
+            KeyCode::Char('l') => {
+                app.launch_tracked()?;
+            }
+            KeyCode::Char('a') => {
+                app.toggle_auto_backup();
+            }
+            KeyCode::Char('b') => {
+                app.backup_selected_instance();
+            }
+            KeyCode::Char('B') => {
+                if let Some(instance) = app.selected_instance() {
+                    let target = BackupTarget::instance(&app.config.backup_dir(), instance);
+                    app.open_backups(target);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn handle_worlds_key(app: &mut App, code: KeyCode) {
+    let Some(browser) = app.world_browser.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    if let Some(name) = browser.rename_input.as_mut() {
+        match code {
+            KeyCode::Esc => {
+                browser.rename_input = None;
+            }
+            KeyCode::Char(c) => {
+                name.push(c);
+            }
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Enter => {
+                if let Some(name) = browser.rename_input.take() {
+                    app.finish_rename(&name);
+                }
+            }
+            _ => {}
+        }
+        return;
+    }
+    if let Some(picker) = browser.copy_picker.as_mut() {
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                browser.copy_picker = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                step_selection(&mut picker.state, picker.instances.len(), true);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                step_selection(&mut picker.state, picker.instances.len(), false);
+            }
+            KeyCode::Enter => {
+                let target = picker.state.selected().and_then(|i| picker.instances.get(i)).cloned();
+                browser.copy_picker = None;
+                if let Some(target) = target {
+                    app.finish_duplicate(target);
+                }
+            }
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_worlds();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            browser.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            browser.previous();
+        }
+        KeyCode::Char('s') => {
+            browser.cycle_sort();
+        }
+        KeyCode::Char('b') => {
+            if let Some(target) = app.selected_world_target() {
+                app.backup(target, app.config.backup_format);
+            }
+        }
+        KeyCode::Char('B') => {
+            if let Some(target) = app.selected_world_target() {
+                app.open_backups(target);
+            }
+        }
+        KeyCode::Char('m') => {
+            app.open_map();
+        }
+        KeyCode::Char('t') => {
+            app.open_stats();
+        }
+        KeyCode::Char('n') => {
+            app.open_inspector();
+        }
+        KeyCode::Char('p') => {
+            app.open_datapacks();
+        }
+        KeyCode::Char('r') => {
+            app.start_rename();
+        }
+        KeyCode::Char('c') => {
+            app.start_duplicate();
+        }
+        KeyCode::Char('x') | KeyCode::Delete => {
+            app.request_trash_world();
+        }
+        KeyCode::Char('l') => {
+            if let Err(err) = app.launch_selected_world() {
+                app.notify(format!("Failed to launch: {:#}", err), true);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_dedicated_key(app: &mut App, code: KeyCode) {
+    let Some(view) = app.dedicated_view.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    if let Some((prompt, ref mut text)) = view.prompt {
+        match code {
+            KeyCode::Esc => {
+                view.prompt = None;
+            }
+            KeyCode::Char(c) => {
+                text.push(c);
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Enter => {
+                let text = std::mem::take(text);
+                view.prompt = None;
+                let result = match prompt {
+                    Prompt::AddFolder => app.add_dedicated_server(&text),
+                    Prompt::JavaCommand => app.set_dedicated_java(&text),
+                };
+                app.notify_result(result);
+            }
+            _ => {}
+        }
+        return;
+    }
+    if view.console {
+        match code {
+            KeyCode::Esc => {
+                view.console = false;
+            }
+            KeyCode::Enter => {
+                let path = view.selected().map(|s| s.settings.path.clone());
+                if let (Some(command), Some(path)) = (view.take_input(), path)
+                    && let Err(err) = app.supervisor.send(&path, &command)
+                {
+                    app.notify(format!("{:#}", err), true);
+                }
+            }
+            KeyCode::Up => {
+                view.history_previous();
+            }
+            KeyCode::Down => {
+                view.history_next();
+            }
+            KeyCode::PageUp => {
+                view.scroll += 10;
+            }
+            KeyCode::PageDown => {
+                view.scroll = view.scroll.saturating_sub(10);
+            }
+            KeyCode::Char(c) => {
+                view.input.push(c);
+            }
+            KeyCode::Backspace => {
+                view.input.pop();
+            }
+            _ => {}
+        }
+        return;
+    }
+    if let Some(editor) = view.editor.as_mut() {
+        if let Some(ref mut text) = editor.editing {
+            match code {
+                KeyCode::Esc => {
+                    editor.editing = None;
+                }
+                KeyCode::Enter => {
+                    editor.apply_edit();
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                }
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                _ => {}
+            }
+            return;
+        }
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                if editor.dirty {
+                    app.confirm = Some(Confirm {
+                        message: "Discard changes to server.properties?".to_string(),
+                        action: PendingAction::DiscardProperties,
+                    });
+                } else {
+                    app.close_properties_editor();
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                editor.next();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                editor.previous();
+            }
+            KeyCode::Enter => {
+                editor.begin_edit();
+            }
+            KeyCode::Char('w') => {
+                let result = editor.save();
+                app.notify_result(result);
+            }
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_dedicated();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            view.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            view.previous();
+        }
+        KeyCode::Enter if view.selected().is_some() => {
+            view.console = true;
+            view.scroll = 0;
+        }
+        KeyCode::Char('a') => {
+            view.prompt = Some((Prompt::AddFolder, String::new()));
+        }
+        KeyCode::Char('J') => {
+            if let Some(server) = view.selected() {
+                let java = server.settings.java.clone().unwrap_or_else(|| "java".to_string());
+                view.prompt = Some((Prompt::JavaCommand, java));
+            }
+        }
+        KeyCode::Char('d') => {
+            app.remove_dedicated_server();
+        }
+        KeyCode::Char('s') => {
+            app.start_stop_dedicated();
+        }
+        KeyCode::Char('K') => {
+            app.request_kill_dedicated();
+        }
+        KeyCode::Char('e') => {
+            app.open_properties_editor();
+        }
+        KeyCode::Char('E') => {
+            app.request_accept_eula();
+        }
+        KeyCode::Char('I') => {
+            app.run_dedicated_installer();
+        }
+        KeyCode::Char('r') => {
+            app.open_dedicated_rcon();
+        }
+        _ => {}
+    }
+}
+
+fn handle_console_key(app: &mut App, code: KeyCode) {
+    let Some(console) = app.console.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    match code {
+        KeyCode::Esc => {
+            app.close_console();
+        }
+        KeyCode::Enter => {
+            console.submit();
+        }
+        KeyCode::Up => {
+            console.history_previous();
+        }
+        KeyCode::Down => {
+            console.history_next();
+        }
+        KeyCode::PageUp => {
+            console.scroll += 10;
+        }
+        KeyCode::PageDown => {
+            console.scroll = console.scroll.saturating_sub(10);
+        }
+        KeyCode::Char(c) => {
+            console.input.push(c);
+        }
+        KeyCode::Backspace => {
+            console.input.pop();
+        }
+        _ => {}
+    }
+}
+
+fn handle_servers_key(app: &mut App, code: KeyCode) {
+    let Some(list) = app.server_list.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    if let Some(form) = list.rcon_form.as_mut() {
+        match code {
+            KeyCode::Esc => {
+                list.rcon_form = None;
+            }
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Down | KeyCode::Up => {
+                form.toggle_field();
+            }
+            KeyCode::Char(c) => {
+                form.input(c);
+            }
+            KeyCode::Backspace => {
+                form.backspace();
+            }
+            KeyCode::Enter => {
+                app.save_rcon_credentials();
+            }
+            _ => {}
+        }
+        return;
+    }
+    if let Some(form) = list.form.as_mut() {
+        match code {
+            KeyCode::Esc => {
+                list.form = None;
+            }
+            KeyCode::Tab | KeyCode::Down => {
+                form.next_field();
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                form.previous_field();
+            }
+            KeyCode::Left | KeyCode::Right if form.field == servers::FormField::ResourcePacks => {
+                form.cycle_resource_packs();
+            }
+            KeyCode::Char(c) => {
+                form.input(c);
+            }
+            KeyCode::Backspace => {
+                form.backspace();
+            }
+            KeyCode::Enter => {
+                if let Some(list) = app.idle_server_list() {
+                    let result = list.submit_form();
+                    app.notify_result(result);
+                }
+            }
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_servers();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            list.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            list.previous();
+        }
+        KeyCode::Tab => {
+            list.toggle_focus();
+        }
+        KeyCode::Char('l') | KeyCode::Enter if list.lan_focus => {
+            if let Err(err) = app.launch_selected_server() {
+                app.notify(format!("Failed to launch: {:#}", err), true);
+            }
+        }
+        _ if list.lan_focus => {}
+        KeyCode::Char('a') => {
+            if let Some(list) = app.idle_server_list() {
+                list.begin_add();
+            }
+        }
+        KeyCode::Char('e') | KeyCode::Enter => {
+            if let Some(list) = app.idle_server_list() {
+                list.begin_edit();
+            }
+        }
+        KeyCode::Char('J') | KeyCode::Char('K') => {
+            let delta = if code == KeyCode::Char('J') { 1 } else { -1 };
+            if let Some(list) = app.idle_server_list()
+                && let Err(err) = list.move_selected(delta)
+            {
+                app.notify(format!("{:#}", err), true);
+            }
+        }
+        KeyCode::Char('x') | KeyCode::Delete => {
+            app.request_remove_server();
+        }
+        KeyCode::Char('r') => {
+            list.refresh();
+        }
+        KeyCode::Char('l') => {
+            if let Err(err) = app.launch_selected_server() {
+                app.notify(format!("Failed to launch: {:#}", err), true);
+            }
+        }
+        KeyCode::Char('c') => {
+            app.open_server_console();
+        }
+        _ => {}
+    }
+}
+
+fn handle_logs_key(app: &mut App, code: KeyCode) {
+    let Some(view) = app.log_view.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    if let Some(search) = view.search.as_mut() {
+        match code {
+            KeyCode::Esc => {
+                view.search = None;
+            }
+            KeyCode::Char(c) => {
+                search.push(c);
+            }
+            KeyCode::Backspace => {
+                search.pop();
+            }
+            KeyCode::Enter => match view.finish_search() {
+                Ok(found) => {
+                    if !found && view.query.is_some() {
+                        app.notify("No matches", true);
+                    }
+                }
+                Err(err) => app.notify(format!("{:#}", err), true),
+            },
+            _ => {}
+        }
+        return;
+    }
+    // Keys shared by the live logs and the Files tab
+    match code {
+        KeyCode::Tab => {
+            view.next_source();
+            return;
+        }
+        KeyCode::Char('/') => {
+            view.search = Some(String::new());
+            return;
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') => {
+            let found = view.find(code == KeyCode::Char('n'));
+            if !found {
+                app.notify("No more matches", false);
+            }
+            return;
+        }
+        KeyCode::Char('c') => {
+            view.toggle_collapse();
+            return;
+        }
+        KeyCode::Char('f') => {
+            view.cycle_level();
+            return;
+        }
+        KeyCode::Char('e') => {
+            let found = view.first_error();
+            if !found {
+                app.notify("No errors", false);
+            }
+            return;
+        }
+        _ => {}
+    }
+    if view.on_files() {
+        let browser = &mut view.files;
+        match (code, browser.opened.as_mut()) {
+            (KeyCode::Char('q') | KeyCode::Esc, _) => {
+                let closed = browser.close();
+                if !closed {
+                    app.close_logs();
+                }
+            }
+            (KeyCode::Enter, _) => {
+                browser.open_selected();
+            }
+            (KeyCode::Char('a'), _) => {
+                app.analyze_log_file();
+            }
+            (KeyCode::Up | KeyCode::Char('k'), Some(opened)) => {
+                opened.scroll_by(1);
+            }
+            (KeyCode::Down | KeyCode::Char('j'), Some(opened)) => {
+                opened.scroll_by(-1);
+            }
+            (KeyCode::PageUp, Some(opened)) => {
+                opened.scroll_by(opened.page());
+            }
+            (KeyCode::PageDown, Some(opened)) => {
+                opened.scroll_by(-opened.page());
+            }
+            (KeyCode::Home | KeyCode::Char('g'), Some(opened)) => {
+                opened.top = 0;
+            }
+            (KeyCode::End | KeyCode::Char('G'), Some(opened)) => {
+                opened.top = usize::MAX;
+            }
+            (KeyCode::Up | KeyCode::Char('k'), None) => {
+                browser.previous();
+            }
+            (KeyCode::Down | KeyCode::Char('j'), None) => {
+                browser.next();
+            }
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_logs();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            view.scroll_by(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            view.scroll_by(-1);
+        }
+        KeyCode::PageUp => {
+            view.scroll_by(view.page());
+        }
+        KeyCode::PageDown => {
+            view.scroll_by(-view.page());
+        }
+        KeyCode::Home | KeyCode::Char('g') => {
+            view.scroll_to_top();
+        }
+        KeyCode::End | KeyCode::Char('G') => {
+            view.follow();
+        }
+        KeyCode::Char('p') | KeyCode::Char(' ') => {
+            view.toggle_pause();
+        }
+        _ => {}
+    }
+}
+
+fn handle_launches_key(app: &mut App, code: KeyCode) {
+    let Some(view) = app.history_view.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    if let Some(input) = view.date_input.as_mut() {
+        match code {
+            KeyCode::Esc => {
+                view.date_input = None;
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => {
+                input.push(c);
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                view.finish_date();
+            }
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_launch_history();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            view.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            view.previous();
+        }
+        KeyCode::Char('i') => {
+            view.cycle_instance();
+        }
+        KeyCode::Char('d') => {
+            view.cycle_period();
+        }
+        KeyCode::Char('/') => {
+            view.date_input = Some(view.date.clone());
+        }
+        _ => {}
+    }
+}
+
+fn handle_crash_history_key(app: &mut App, code: KeyCode) {
+    let Some(history) = app.crash_history.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_crash_history();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            history.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            history.previous();
+        }
+        KeyCode::Enter => {
+            app.analyze_selected_crash();
+        }
+        _ => {}
+    }
+}
+
+fn handle_crash_key(app: &mut App, code: KeyCode) {
+    let Some(view) = app.crash_view.as_mut() else {
+        app.screen = Screen::Instances;
+        return;
+    };
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_crash();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            view.scroll_by(1);
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            view.scroll_by(-1);
+        }
+        KeyCode::PageDown => {
+            view.scroll_by(10);
+        }
+        KeyCode::PageUp => {
+            view.scroll_by(-10);
+        }
+        _ => {}
+    }
+}
+
+fn handle_datapacks_key(app: &mut App, code: KeyCode) {
+    let Some(view) = app.datapack_view.as_mut() else {
+        app.screen = Screen::Worlds;
+        return;
+    };
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_datapacks();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            view.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            view.previous();
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.toggle_datapack();
+        }
+        _ => {}
+    }
+}
+
+fn handle_inspector_key(app: &mut App, code: KeyCode) {
+    let Some(inspector) = app.inspector.as_mut() else {
+        app.screen = Screen::Worlds;
+        return;
+    };
+    if let Some(input) = inspector.input.as_mut() {
+        let text = match input {
+            Input::Search(text) | Input::Edit(text) => text,
+        };
+        match code {
+            KeyCode::Esc => {
+                inspector.input = None;
+            }
+            KeyCode::Char(c) => {
+                text.push(c);
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Enter => match inspector.input.take() {
+                Some(Input::Search(query)) => {
+                    inspector.query = query;
+                    if let Err(err) = inspector.find_next() {
+                        app.notify(err.to_string(), true);
                     }
                 }
+                Some(Input::Edit(value)) => {
+                    let result = inspector.apply_edit(&value);
+                    app.notify_result(result);
+                }
+                None => {}
+            },
+            _ => {}
+        }
+        return;
+    }
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.request_close_inspector();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            inspector.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            inspector.previous();
+        }
+        KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Right | KeyCode::Char('l') => {
+            inspector.toggle();
+        }
+        KeyCode::Left | KeyCode::Char('h') => {
+            inspector.collapse();
+        }
+        KeyCode::Char('/') => {
+            inspector.input = Some(Input::Search(inspector.query.clone()));
+        }
+        KeyCode::Char('n') => {
+            if let Err(err) = inspector.find_next() {
+                app.notify(err.to_string(), true);
+            }
+        }
+        KeyCode::Char('e') => {
+            if let Err(err) = inspector.begin_edit() {
+                app.notify(err.to_string(), true);
+            }
+        }
+        KeyCode::Char('u') if inspector.dirty => {
+            inspector.load();
+            app.notify("Reverted unsaved changes", false);
+        }
+        KeyCode::Tab => {
+            if let Err(err) = inspector.next_file() {
+                app.notify(err.to_string(), true);
             }
         }
+        KeyCode::Char('w') => {
+            app.save_inspector();
+        }
+        _ => {}
+    }
+}
 
-        if app.should_quit {
-            break;
+fn handle_stats_key(app: &mut App, code: KeyCode) {
+    let Some(view) = app.stats_view.as_mut() else {
+        app.close_stats();
+        return;
+    };
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_stats();
         }
+        KeyCode::Down | KeyCode::Char('j') => {
+            view.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            view.previous();
+        }
+        _ => {}
     }
+}
 
-    Ok(())
+fn handle_map_key(app: &mut App, code: KeyCode) {
+    let Some(map) = app.map_view.as_mut() else {
+        app.screen = Screen::Worlds;
+        return;
+    };
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_map();
+        }
+        KeyCode::Left | KeyCode::Char('h') => {
+            map.pan(-1, 0);
+        }
+        KeyCode::Right | KeyCode::Char('l') => {
+            map.pan(1, 0);
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            map.pan(0, -1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            map.pan(0, 1);
+        }
+        KeyCode::Char('+') | KeyCode::Char('=') => {
+            map.zoom_in();
+        }
+        KeyCode::Char('-') => {
+            map.zoom_out();
+        }
+        KeyCode::Char('s') => {
+            map.center_on_spawn();
+        }
+        KeyCode::Char('p') => {
+            map.center_on_player();
+        }
+        _ => {}
+    }
+}
+
+fn handle_backups_key(app: &mut App, code: KeyCode) {
+    let Some(browser) = app.backup_browser.as_mut() else {
+        app.close_backups();
+        return;
+    };
+    match code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.close_backups();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            browser.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            browser.previous();
+        }
+        KeyCode::Char('b') => {
+            let target = browser.target.clone();
+            app.backup(target, app.config.backup_format);
+        }
+        KeyCode::Char('i') => {
+            let target = browser.target.clone();
+            app.backup(target, BackupFormat::Snapshot);
+        }
+        KeyCode::Char('g') => {
+            app.collect_garbage();
+        }
+        KeyCode::Enter | KeyCode::Char('r') => {
+            app.request_restore();
+        }
+        KeyCode::Char('p') => {
+            app.request_prune();
+        }
+        _ => {}
+    }
 }
 
 fn ui(f: &mut Frame, app: &mut App) {
+    app.icon_placement = None;
+
+    if app.screen != Screen::Instances {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(3),
+            ])
+            .split(f.area());
+
+        render_header(f, chunks[0]);
+        match app.screen {
+            Screen::Instances => {}
+            Screen::Worlds => {
+                if let Some(browser) = app.world_browser.as_mut() {
+                    world::render(f, chunks[1], browser);
+                    let sort = format!("Sort: {}", browser.sort_display());
+                    render_help_bar(
+                        f,
+                        chunks[2],
+                        &[
+                            ("↑↓", "Navigate"),
+                            ("s", &sort),
+                            ("m", "Map"),
+                            ("t", "Stats"),
+                            ("n", "NBT"),
+                            ("p", "Data Packs"),
+                            ("r", "Rename"),
+                            ("c", "Copy"),
+                            ("x", "Trash"),
+                            ("l", "Play"),
+                            ("b", "Backup"),
+                            ("B", "Backups"),
+                            ("Esc", "Back"),
+                        ],
+                    );
+                }
+            }
+            Screen::Map => {
+                if let Some(map) = app.map_view.as_mut() {
+                    map::render(f, chunks[1], map);
+                    render_help_bar(
+                        f,
+                        chunks[2],
+                        &[
+                            ("←↑↓→/hjkl", "Pan"),
+                            ("+/-", "Zoom"),
+                            ("s", "Spawn ◆"),
+                            ("p", "Player ●"),
+                            ("Esc", "Back"),
+                        ],
+                    );
+                }
+            }
+            Screen::Stats => {
+                if let Some(view) = app.stats_view.as_mut() {
+                    stats::render(f, chunks[1], view);
+                    render_help_bar(f, chunks[2], &[("↑↓", "Navigate"), ("Esc", "Back")]);
+                }
+            }
+            Screen::Servers => {
+                if let Some(list) = app.server_list.as_mut() {
+                    app.icon_placement = servers::render(f, chunks[1], list, app.icon_painter.protocol);
+                    let keys: &[(&str, &str)] = if list.lan_focus {
+                        &[("↑↓", "Navigate"), ("Enter/l", "Join"), ("Tab", "Saved Servers"), ("Esc", "Back")]
+                    } else {
+                        &[
+                            ("↑↓", "Navigate"),
+                            ("a", "Add"),
+                            ("Enter/e", "Edit"),
+                            ("J/K", "Move"),
+                            ("x", "Remove"),
+                            ("r", "Refresh"),
+                            ("l", "Join"),
+                            ("c", "Console"),
+                            ("Tab", "LAN"),
+                            ("Esc", "Back"),
+                        ]
+                    };
+                    render_help_bar(f, chunks[2], keys);
+                }
+            }
+            Screen::Dedicated => {
+                if let Some(view) = app.dedicated_view.as_mut() {
+                    dedicated::render(f, chunks[1], view, &app.supervisor);
+                    let keys: &[(&str, &str)] = if view.prompt.is_some() {
+                        &[("Enter", "Save"), ("Esc", "Cancel")]
+                    } else if view.console {
+                        &[("Enter", "Send"), ("↑↓", "History"), ("PgUp/PgDn", "Scroll"), ("Esc", "Back")]
+                    } else if view.editor.as_ref().is_some_and(|e| e.editing.is_some()) {
+                        &[("Enter", "Apply"), ("Esc", "Cancel")]
+                    } else if view.editor.is_some() {
+                        &[("↑↓", "Navigate"), ("Enter", "Edit/Toggle"), ("w", "Save"), ("Esc", "Back")]
+                    } else {
+                        &[
+                            ("↑↓", "Navigate"),
+                            ("s", "Start/Stop"),
+                            ("Enter", "Console"),
+                            ("e", "Properties"),
+                            ("r", "RCON"),
+                            ("a", "Add"),
+                            ("d", "Remove"),
+                            ("J", "Java"),
+                            ("I", "Install"),
+                            ("E", "EULA"),
+                            ("K", "Kill"),
+                            ("Esc", "Back"),
+                        ]
+                    };
+                    render_help_bar(f, chunks[2], keys);
+                }
+            }
+            Screen::Logs => {
+                if let Some(view) = app.log_view.as_mut() {
+                    logs::render(f, chunks[1], view);
+                    let keys: &[(&str, &str)] = if view.search.is_some() {
+                        &[("Enter", "Search"), ("Esc", "Cancel")]
+                    } else if view.on_files() && view.files.opened.is_some() {
+                        &[
+                            ("↑↓/PgUp/PgDn", "Scroll"),
+                            ("g/G", "Top/Bottom"),
+                            ("e", "First Error"),
+                            ("f", "Level"),
+                            ("/", "Search All"),
+                            ("n/N", "Next/Prev Match"),
+                            ("c", "Fold"),
+                            ("a", "Analyze"),
+                            ("Esc", "Close"),
+                        ]
+                    } else if view.on_files() {
+                        &[
+                            ("↑↓", "Navigate"),
+                            ("Enter", "Open"),
+                            ("a", "Analyze Crash"),
+                            ("/", "Search All"),
+                            ("n/N", "Next/Prev Match"),
+                            ("f", "Level"),
+                            ("Tab", "Switch Log"),
+                            ("Esc", "Back"),
+                        ]
+                    } else {
+                        &[
+                            ("↑↓/PgUp/PgDn", "Scroll"),
+                            ("g/G", "Top/Follow"),
+                            ("p", "Pause"),
+                            ("/", "Search"),
+                            ("n/N", "Older/Newer"),
+                            ("e", "First Error"),
+                            ("f", "Level"),
+                            ("c", "Fold"),
+                            ("Tab", "Switch Log"),
+                            ("Esc", "Back"),
+                        ]
+                    };
+                    render_help_bar(f, chunks[2], keys);
+                }
+            }
+            Screen::Launches => {
+                if let Some(view) = app.history_view.as_mut() {
+                    launches::render(f, chunks[1], view);
+                    let keys: &[(&str, &str)] = if view.date_input.is_some() {
+                        &[("Enter", "Filter"), ("Esc", "Cancel")]
+                    } else {
+                        &[("↑↓", "Navigate"), ("i", "Instance"), ("d", "Period"), ("/", "Date"), ("Esc", "Back")]
+                    };
+                    render_help_bar(f, chunks[2], keys);
+                }
+            }
+            Screen::CrashHistory => {
+                if let Some(history) = app.crash_history.as_mut() {
+                    crashes::render(f, chunks[1], history);
+                    render_help_bar(f, chunks[2], &[("↑↓", "Navigate"), ("Enter", "Analyze"), ("Esc", "Back")]);
+                }
+            }
+            Screen::Crash => {
+                if let Some(view) = app.crash_view.as_mut() {
+                    crash::render(f, chunks[1], view);
+                    render_help_bar(f, chunks[2], &[("↑↓/PgUp/PgDn", "Scroll"), ("Esc", "Back")]);
+                }
+            }
+            Screen::Console => {
+                if let Some(console) = app.console.as_mut() {
+                    rcon::render(f, chunks[1], console);
+                    render_help_bar(
+                        f,
+                        chunks[2],
+                        &[("Enter", "Run"), ("↑↓", "History"), ("PgUp/PgDn", "Scroll"), ("Esc", "Back")],
+                    );
+                }
+            }
+            Screen::Datapacks => {
+                if let Some(view) = app.datapack_view.as_mut() {
+                    datapack::render(f, chunks[1], view);
+                    render_help_bar(
+                        f,
+                        chunks[2],
+                        &[("↑↓", "Navigate"), ("Enter/Space", "Enable/Disable"), ("Esc", "Back")],
+                    );
+                }
+            }
+            Screen::Inspector => {
+                if let Some(inspector) = app.inspector.as_mut() {
+                    inspector::render(f, chunks[1], inspector);
+                    render_help_bar(
+                        f,
+                        chunks[2],
+                        &[
+                            ("↑↓", "Navigate"),
+                            ("Enter/←→", "Expand/Collapse"),
+                            ("/ n", "Search/Next"),
+                            ("e", "Edit"),
+                            ("w", "Save"),
+                            ("u", "Revert"),
+                            ("Tab", "Next File"),
+                            ("Esc", "Back"),
+                        ],
+                    );
+                }
+            }
+            Screen::Backups => {
+                if let Some(browser) = app.backup_browser.as_mut() {
+                    backup::render(f, chunks[1], browser, &app.config.retention);
+                    render_help_bar(
+                        f,
+                        chunks[2],
+                        &[
+                            ("↑↓", "Navigate"),
+                            ("Enter/r", "Restore"),
+                            ("b", "New Backup"),
+                            ("i", "Incremental Snapshot"),
+                            ("p", "Prune"),
+                            ("g", "Clean Store"),
+                            ("Esc", "Back"),
+                        ],
+                    );
+                }
+            }
+        }
+        render_overlays(f, app);
+        return;
+    }
+
     if app.details_mode {
         // Details view: split horizontally
         let main_chunks = Layout::default()
@@ -571,6 +3279,61 @@ fn ui(f: &mut Frame, app: &mut App) {
         render_instances(f, chunks[1], app);
         render_footer(f, chunks[2], app);
     }
+    render_overlays(f, app);
+}
+
+/// A `width` × `height` rectangle in the middle of `area`, shrunk to fit.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Task progress and notifications over the footer's top border, then any
+/// confirmation popup on top of everything.
+fn render_overlays(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let (text, color) = if let Some(ref task) = app.task {
+        (format!(" ⏳ {}… ", task.label), Color::Cyan)
+    } else if let Some(ref notification) = app.notification {
+        let color = if notification.error { Color::Red } else { Color::Green };
+        (format!(" {} ", notification.message), color)
+    } else {
+        (String::new(), Color::Reset)
+    };
+    if !text.is_empty() && area.height >= 3 && area.width > 4 {
+        let line = Rect::new(area.x + 2, area.bottom() - 3, area.width - 4, 1);
+        let status = Paragraph::new(Span::styled(text, Style::default().fg(color).add_modifier(Modifier::BOLD)));
+        f.render_widget(status, line);
+    }
+
+    if let Some(ref confirm) = app.confirm {
+        let popup = centered_rect(60, 7, area);
+        let mut lines: Vec<Line> = confirm.message.lines().map(|l| Line::from(l.to_string())).collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("y", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::raw(" Yes  "),
+            Span::styled("any other key", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+            Span::raw(" No"),
+        ]));
+        let dialog = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(" Confirm "),
+            );
+        f.render_widget(Clear, popup);
+        f.render_widget(dialog, popup);
+    }
 }
 
 fn render_header(f: &mut Frame, area: Rect) {
@@ -591,13 +3354,17 @@ fn render_header(f: &mut Frame, area: Rect) {
 
 fn render_search_bar(f: &mut Frame, area: Rect, app: &App) {
     let search_text = format!("Search: {}", app.search_query);
+    let (title, color) = match app.filter_error {
+        Some(ref error) => (format!(" {} ", error), Color::Red),
+        None => (" Filter (ESC to exit) ".to_string(), Color::Yellow),
+    };
     let search_bar = Paragraph::new(search_text)
         .style(Style::default().fg(Color::Yellow))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Yellow))
-                .title(" Filter (ESC to exit) ")
+                .border_style(Style::default().fg(color))
+                .title(title)
         );
     f.render_widget(search_bar, area);
 }
@@ -625,10 +3392,16 @@ fn render_instances(f: &mut Frame, area: Rect, app: &mut App) {
         .filtered_instances
         .iter()
         .map(|instance| {
-            let is_running = is_instance_running(&instance.name);
+            let is_running = app.running.contains(&instance.path);
+
+            // Instances with an icon get a glyph in the icon's dominant color
+            let glyph = match instance.icon.as_ref().and_then(|icon| icon.dominant_color()) {
+                Some(color) => Span::styled("■ ", Style::default().fg(color)),
+                None => Span::styled("▶ ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            };
 
             let mut title_spans = vec![
-                Span::styled("▶ ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+                glyph,
                 Span::styled(&instance.name, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
             ];
 
@@ -640,6 +3413,13 @@ fn render_instances(f: &mut Frame, area: Rect, app: &mut App) {
                 ));
             }
 
+            // Flag instances the local meta cache knows a newer game or loader for
+            if instance.version_status.newer_minecraft.is_some()
+                || instance.version_status.newer_loader.is_some()
+            {
+                title_spans.push(Span::styled(" ⬆", Style::default().fg(Color::Yellow)));
+            }
+
             // Add running indicator
             if is_running {
                 title_spans.push(Span::styled(
@@ -648,6 +3428,14 @@ fn render_instances(f: &mut Frame, area: Rect, app: &mut App) {
                 ));
             }
 
+            // Flag a crash nobody has looked at yet
+            if app.crashes.is_unseen(instance) {
+                title_spans.push(Span::styled(
+                    " ✖ CRASHED",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                ));
+            }
+
             let mut lines = vec![Line::from(title_spans)];
 
             let mut info_parts = Vec::new();
@@ -658,13 +3446,23 @@ fn render_instances(f: &mut Frame, area: Rect, app: &mut App) {
                 info_parts.push(format!("Last played: {}", last_played));
             }
 
-            if !info_parts.is_empty() {
-                lines.push(Line::from(
-                    Span::styled(
-                        format!("  {}", info_parts.join(" • ")),
-                        Style::default().fg(Color::DarkGray)
-                    )
-                ));
+            let last_crash = app.crashes.last_crash(instance).map(|time| Span::styled(
+                format!("Last crash {}", format_timestamp(time).to_lowercase()),
+                Style::default().fg(Color::Red)
+            ));
+
+            if !info_parts.is_empty() || last_crash.is_some() {
+                let mut info_spans = vec![Span::styled(
+                    format!("  {}", info_parts.join(" • ")),
+                    Style::default().fg(Color::DarkGray)
+                )];
+                if let Some(last_crash) = last_crash {
+                    if !info_parts.is_empty() {
+                        info_spans.push(Span::styled(" • ", Style::default().fg(Color::DarkGray)));
+                    }
+                    info_spans.push(last_crash);
+                }
+                lines.push(Line::from(info_spans));
             }
 
             ListItem::new(lines).style(Style::default())
@@ -690,7 +3488,7 @@ fn render_instances(f: &mut Frame, area: Rect, app: &mut App) {
 }
 
 fn render_footer(f: &mut Frame, area: Rect, app: &App) {
-    let sort_text = format!(" Sort: {}  ", app.sort_mode.display());
+    let sort_text = format!(" Sort: {}  ", app.sort_description());
     let help_text = vec![
         Span::styled("↑↓", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
         Span::raw(" Navigate  "),
@@ -698,7 +3496,7 @@ fn render_footer(f: &mut Frame, area: Rect, app: &App) {
         Span::raw(" Launch  "),
         Span::styled("o", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         Span::raw(" Open  "),
-        Span::styled("s", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+        Span::styled("s/S/d", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
         Span::raw(sort_text),
         Span::styled("/", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
         Span::raw(" Search  "),
@@ -719,89 +3517,210 @@ fn render_footer(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(footer, area);
 }
 
-fn render_details(f: &mut Frame, area: Rect, app: &App) {
-    if let Some(selected) = app.list_state.selected() {
-        if let Some(instance) = app.filtered_instances.get(selected) {
-            let mut details_lines = vec![];
+/// Footer for secondary screens: each hint is a key and what it does.
+fn render_help_bar(f: &mut Frame, area: Rect, hints: &[(&str, &str)]) {
+    let spans: Vec<Span> = hints
+        .iter()
+        .flat_map(|(key, action)| {
+            [
+                Span::styled(key.to_string(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                Span::raw(format!(" {}  ", action)),
+            ]
+        })
+        .collect();
+
+    let footer = Paragraph::new(Line::from(spans))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::White))
+        );
+
+    f.render_widget(footer, area);
+}
+
+/// A bold cyan label followed by its value, as used in the details panels.
+fn detail_line(label: &str, value: impl Into<String>) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(label.to_string(), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::raw(value.into()),
+    ])
+}
+
+/// Moves a list selection one step, wrapping around at either end.
+fn step_selection(state: &mut ListState, len: usize, forward: bool) {
+    if len == 0 {
+        return;
+    }
+    let i = match state.selected() {
+        Some(i) if forward => if i >= len - 1 { 0 } else { i + 1 },
+        Some(i) => if i == 0 { len - 1 } else { i - 1 },
+        None => 0,
+    };
+    state.select(Some(i));
+}
+
+fn render_details(f: &mut Frame, area: Rect, app: &mut App) {
+    if let Some(selected) = app.list_state.selected()
+        && let Some(instance) = app.filtered_instances.get(selected)
+    {
+        let mut details_lines = vec![];
+
+        details_lines.push(Line::from(vec![
+            Span::styled("Name: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::raw(&instance.name),
+        ]));
+
+        if let Some(ref version) = instance.mc_version {
+            details_lines.push(Line::from(vec![
+                Span::styled("Minecraft Version: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(version),
+            ]));
+        }
 
+        let status = &instance.version_status;
+        if let Some(date) = status.release_date() {
+            let kind = status.kind.as_deref().unwrap_or("unknown");
             details_lines.push(Line::from(vec![
-                Span::styled("Name: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                Span::raw(&instance.name),
+                Span::styled("Released: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(format!("{} ({})", date, kind)),
             ]));
+        }
 
-            if let Some(ref version) = instance.mc_version {
-                details_lines.push(Line::from(vec![
-                    Span::styled("Minecraft Version: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                    Span::raw(version),
-                ]));
-            }
+        if let Some(ref newer) = status.newer_minecraft {
+            details_lines.push(Line::from(vec![
+                Span::styled("Newer Minecraft: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                Span::raw(newer),
+            ]));
+        }
 
-            if let Some(ref loader) = instance.mod_loader {
-                details_lines.push(Line::from(vec![
-                    Span::styled("Mod Loader: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                    Span::raw(loader),
-                ]));
-            }
+        if let Some(loader) = instance.mod_loader {
+            details_lines.push(Line::from(vec![
+                Span::styled("Mod Loader: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(loader.display()),
+            ]));
+        }
 
-            details_lines.push(Line::from("")); // Blank line
+        if let Some(ref loader_version) = instance.loader_version {
+            details_lines.push(Line::from(vec![
+                Span::styled("Loader Version: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(loader_version),
+            ]));
+        }
 
+        if let Some(ref newer) = status.newer_loader {
             details_lines.push(Line::from(vec![
-                Span::styled("Path: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::styled("Newer Loader: ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                Span::raw(newer),
             ]));
-            details_lines.push(Line::from(
-                Span::styled(instance.path.display().to_string(), Style::default().fg(Color::DarkGray))
-            ));
+        }
+
+        details_lines.push(Line::from("")); // Blank line
+
+        details_lines.push(Line::from(vec![
+            Span::styled("Path: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        ]));
+        details_lines.push(Line::from(
+            Span::styled(instance.path.display().to_string(), Style::default().fg(Color::DarkGray))
+        ));
+
+        details_lines.push(Line::from("")); // Blank line
+
+        if let Some(ref time_played) = instance.time_played {
+            details_lines.push(Line::from(vec![
+                Span::styled("Total Playtime: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(time_played),
+            ]));
+        }
+
+        if let Some(ref last_played) = instance.last_played {
+            details_lines.push(Line::from(vec![
+                Span::styled("Last Played: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(last_played),
+            ]));
+        }
+
+        let disk_size = match instance.disk_size {
+            Some(disk_size) => Some(format_size(disk_size)),
+            None if app.sizes.is_some() => Some("…".to_string()),
+            None => None,
+        };
+        if let Some(disk_size) = disk_size {
+            details_lines.push(Line::from(vec![
+                Span::styled("Disk Size: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(disk_size),
+            ]));
+        }
+
+        if let Some(created_ts) = instance.created_ts {
+            details_lines.push(Line::from(vec![
+                Span::styled("Created: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(format_timestamp(created_ts)),
+            ]));
+        }
 
+        if let Some(mod_count) = instance.mod_count {
             details_lines.push(Line::from("")); // Blank line
+            details_lines.push(Line::from(vec![
+                Span::styled("Mods: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(format!("{} installed", mod_count)),
+            ]));
+        }
 
-            if let Some(ref time_played) = instance.time_played {
-                details_lines.push(Line::from(vec![
-                    Span::styled("Total Playtime: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                    Span::raw(time_played),
-                ]));
-            }
+        if let Some(world_count) = instance.world_count {
+            details_lines.push(Line::from(vec![
+                Span::styled("Worlds: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(format!("{} (w to browse, t for stats)", world_count)),
+            ]));
+        }
+        let auto_backup = app.config.instance(&instance.folder()).auto_backup;
+        details_lines.push(detail_line(
+            "Auto Backup: ",
+            format!("{} (a to toggle, l to launch tracked)", if auto_backup { "On" } else { "Off" }),
+        ));
 
-            if let Some(ref last_played) = instance.last_played {
-                details_lines.push(Line::from(vec![
-                    Span::styled("Last Played: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                    Span::raw(last_played),
-                ]));
+        if !instance.components.is_empty() {
+            details_lines.push(Line::from("")); // Blank line
+            details_lines.push(Line::from(vec![
+                Span::styled("Components:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            ]));
+            for component in &instance.components {
+                details_lines.push(component_line(component));
             }
+        }
 
-            // Count mods
-            let mods_path = instance.path.join("mods");
-            if mods_path.exists() {
-                if let Ok(entries) = fs::read_dir(&mods_path) {
-                    let mod_count = entries
-                        .filter_map(|e| e.ok())
-                        .filter(|e| {
-                            e.path().extension()
-                                .and_then(|ext| ext.to_str())
-                                .map(|ext| ext == "jar")
-                                .unwrap_or(false)
-                        })
-                        .count();
-
-                    details_lines.push(Line::from("")); // Blank line
-                    details_lines.push(Line::from(vec![
-                        Span::styled("Mods: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                        Span::raw(format!("{} installed", mod_count)),
-                    ]));
-                }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Blue))
+            .title(" Instance Details (i to close) ");
+        let mut text_area = block.inner(area);
+        f.render_widget(block, area);
+
+        if let Some(icon) = instance.icon.clone() {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(ICON_ROWS + 1), Constraint::Min(0)])
+                .split(text_area);
+            let icon_area = Rect {
+                width: ICON_COLS.min(chunks[0].width),
+                height: ICON_ROWS.min(chunks[0].height),
+                ..chunks[0]
+            };
+            text_area = chunks[1];
+
+            if app.icon_painter.protocol == GraphicsProtocol::HalfBlocks {
+                f.render_widget(Paragraph::new(icon.half_blocks(icon_area.width, icon_area.height)), icon_area);
+            } else {
+                app.icon_placement = Some((icon_area, icon));
             }
+        }
 
-            let details = Paragraph::new(details_lines)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .border_style(Style::default().fg(Color::Blue))
-                        .title(" Instance Details (i to close) ")
-                )
-                .wrap(ratatui::widgets::Wrap { trim: false });
+        let details = Paragraph::new(details_lines)
+            .wrap(ratatui::widgets::Wrap { trim: false });
 
-            f.render_widget(details, area);
-            return;
-        }
+        f.render_widget(details, text_area);
+        return;
     }
 
     // No instance selected
@@ -817,10 +3736,63 @@ fn render_details(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(message, area);
 }
 
+fn component_line(component: &Component) -> Line<'_> {
+    // Dependency-only components were pulled in automatically, so dim them
+    let name_style = if component.dependency_only {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let mut spans = vec![
+        Span::raw(if component.important { "  ★ " } else { "  • " }),
+        Span::styled(component.name(), name_style),
+    ];
+
+    if let Some(version) = component.version() {
+        spans.push(Span::styled(format!(" {}", version), Style::default().fg(Color::Green)));
+    }
+
+    if component.dependency_only {
+        spans.push(Span::styled(" (dependency)", Style::default().fg(Color::DarkGray)));
+    }
+
+    let requires: Vec<String> = component
+        .cached_requires
+        .iter()
+        .map(|req| match req.equals.as_ref().or(req.suggests.as_ref()) {
+            Some(version) => format!("{} {}", req.uid, version),
+            None => req.uid.clone(),
+        })
+        .collect();
+    if !requires.is_empty() {
+        spans.push(Span::styled(
+            format!(" → {}", requires.join(", ")),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    Line::from(spans)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_launch_target_version_check() {
+        let world = LaunchTarget::World("New World".into());
+        assert_eq!(world.args(), ["--world", "New World"]);
+        assert!(world.check_version(Some("1.20.1")).is_ok());
+        assert!(world.check_version(Some("23w14a")).is_ok());
+        assert!(world.check_version(Some("1.19.4")).is_err());
+        assert!(world.check_version(None).is_err());
+
+        let server = LaunchTarget::Server("mc.example.net:25566".into());
+        assert_eq!(server.args(), ["--server", "mc.example.net:25566"]);
+        assert!(server.check_version(Some("1.8.9")).is_ok());
+    }
+
     #[test]
     fn test_format_duration_hours() {
         assert_eq!(format_duration(3661), "1h 1m");
@@ -841,7 +3813,90 @@ mod tests {
         let mode = SortMode::Name;
         assert_eq!(mode.next(), SortMode::LastPlayed);
         assert_eq!(mode.next().next(), SortMode::Playtime);
-        assert_eq!(mode.next().next().next(), SortMode::Name);
+        assert_eq!(mode.next().next().next(), SortMode::Version);
+        assert_eq!(mode.next().next().next().next(), SortMode::ReleaseDate);
+        assert_eq!(mode.next().next().next().next().next(), SortMode::Loader);
+        assert_eq!(SortMode::Loader.next(), SortMode::ModCount);
+        assert_eq!(SortMode::ModCount.next(), SortMode::DiskSize);
+        assert_eq!(SortMode::DiskSize.next(), SortMode::Created);
+        assert_eq!(SortMode::Created.next(), SortMode::Name);
+    }
+
+    #[test]
+    fn test_sort_missing_values_last() {
+        let with_mods = Instance { mod_count: Some(3), ..Default::default() };
+        let without = Instance::default();
+        for descending in [true, false] {
+            assert_eq!(SortMode::ModCount.compare(&with_mods, &without, descending), Ordering::Less);
+        }
+    }
+
+    #[test]
+    fn test_sort_loader_then_name() {
+        let instance = |name: &str, loader| Instance {
+            name: name.to_string(),
+            mod_loader: Some(loader),
+            ..Default::default()
+        };
+        let mut instances = [
+            instance("b", ModLoader::Fabric),
+            instance("c", ModLoader::Forge),
+            instance("a", ModLoader::Forge),
+            instance("d", ModLoader::Fabric),
+        ];
+        sort_instances(&mut instances, SortMode::Loader, false, SortMode::Name);
+        let names: Vec<_> = instances.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["b", "d", "a", "c"]);
+
+        // Reversing the primary key leaves the tie-break ascending
+        sort_instances(&mut instances, SortMode::Loader, true, SortMode::Name);
+        let names: Vec<_> = instances.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["a", "c", "b", "d"]);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[test]
+    fn test_mod_loader_exact_uid() {
+        assert_eq!(ModLoader::from_uid("net.neoforged"), Some(ModLoader::NeoForge));
+        assert_eq!(ModLoader::from_uid("net.minecraftforge"), Some(ModLoader::Forge));
+        assert_eq!(ModLoader::from_uid("net.fabricmc.intermediary"), None);
+        assert_eq!(ModLoader::from_uid("org.lwjgl3"), None);
+    }
+
+    #[test]
+    fn test_mmc_pack_neoforge() {
+        let pack: MMCPack = serde_json::from_str(r#"{
+            "components": [
+                {"cachedName": "LWJGL 3", "cachedVersion": "3.3.3", "cachedVolatile": true,
+                 "dependencyOnly": true, "uid": "org.lwjgl3", "version": "3.3.3"},
+                {"cachedName": "Minecraft", "cachedRequires": [{"suggests": "3.3.3", "uid": "org.lwjgl3"}],
+                 "cachedVersion": "1.21.1", "important": true, "uid": "net.minecraft", "version": "1.21.1"},
+                {"cachedName": "NeoForge", "cachedRequires": [{"equals": "1.21.1", "uid": "net.minecraft"}],
+                 "cachedVersion": "21.1.77", "uid": "net.neoforged", "version": "21.1.77"}
+            ],
+            "formatVersion": 1
+        }"#).unwrap();
+
+        assert_eq!(pack.minecraft_version().as_deref(), Some("1.21.1"));
+        assert_eq!(pack.mod_loader(), (ModLoader::NeoForge, Some("21.1.77".to_string())));
+        assert!(pack.components[0].dependency_only);
+        assert!(pack.components[1].important);
+        assert_eq!(pack.components[2].cached_requires[0].equals.as_deref(), Some("1.21.1"));
+    }
+
+    #[test]
+    fn test_mmc_pack_vanilla() {
+        let pack: MMCPack = serde_json::from_str(
+            r#"{"components": [{"uid": "net.minecraft", "cachedVersion": "1.20.1"}]}"#
+        ).unwrap();
+        assert_eq!(pack.minecraft_version().as_deref(), Some("1.20.1"));
+        assert_eq!(pack.mod_loader(), (ModLoader::Vanilla, None));
     }
 
     #[test]
@@ -849,5 +3904,11 @@ mod tests {
         assert_eq!(SortMode::Name.display(), "Name");
         assert_eq!(SortMode::LastPlayed.display(), "Last Played");
         assert_eq!(SortMode::Playtime.display(), "Playtime");
+        assert_eq!(SortMode::Version.display(), "Version");
+        assert_eq!(SortMode::ReleaseDate.display(), "Release Date");
+        assert_eq!(SortMode::Loader.display(), "Loader");
+        assert_eq!(SortMode::ModCount.display(), "Mods");
+        assert_eq!(SortMode::DiskSize.display(), "Size");
+        assert_eq!(SortMode::Created.display(), "Created");
     }
 }
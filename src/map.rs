@@ -0,0 +1,389 @@
+//! Overhead map of a world's overworld, drawn with half-block characters.
+//!
+//! Each terminal cell shows two map pixels (`▀` with the top pixel as foreground
+//! and the bottom as background), which keeps pixels roughly square. Regions are
+//! decoded on a background thread as they scroll into view, and blocks are
+//! colored from a built-in table, shaded by height against their northern
+//! neighbor and, for water, by depth.
+
+use crate::region::{self, RegionSurface};
+use crate::world::World;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders},
+    Frame,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::mpsc,
+    thread,
+};
+
+const REGION_BLOCKS: i32 = 512;
+
+/// Blocks per map pixel at each zoom level.
+const ZOOM_LEVELS: [i32; 6] = [1, 2, 4, 8, 16, 32];
+
+type RegionPos = (i32, i32);
+
+/// One color per block column of a region, `None` where no chunk was generated.
+struct RegionImage {
+    pixels: Vec<Option<[u8; 3]>>,
+}
+
+enum Tile {
+    Loading,
+    Ready(RegionImage),
+    Failed,
+}
+
+enum Pixel {
+    Color([u8; 3]),
+    Empty,
+    Loading,
+}
+
+pub struct MapView {
+    pub world_name: String,
+    available: HashSet<RegionPos>,
+    tiles: HashMap<RegionPos, Tile>,
+    requests: mpsc::Sender<RegionPos>,
+    results: mpsc::Receiver<(RegionPos, Option<RegionImage>)>,
+    /// Block x and z at the middle of the view.
+    center: (i32, i32),
+    zoom: usize,
+    spawn: Option<(i32, i32)>,
+    player: Option<(i32, i32)>,
+    /// Size of the last drawn map in pixels, used to size pan steps.
+    view: (i32, i32),
+}
+
+impl MapView {
+    pub fn open(world_dir: &Path, world: &World) -> Self {
+        let region_dir = world_dir.join("region");
+        let available = region::list_regions(&region_dir).into_iter().collect();
+
+        let (requests, inbox) = mpsc::channel::<RegionPos>();
+        let (outbox, results) = mpsc::channel();
+        thread::spawn(move || {
+            // Ends when the view is closed and its sender dropped
+            for (rx, rz) in inbox {
+                let image = region::load_surface(&region_dir, rx, rz).ok().map(|s| render_region(&s));
+                if outbox.send(((rx, rz), image)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            world_name: world.display_name().to_string(),
+            available,
+            tiles: HashMap::new(),
+            requests,
+            results,
+            center: world.player.or(world.spawn).unwrap_or((0, 0)),
+            zoom: 1,
+            spawn: world.spawn,
+            player: world.player,
+            view: (80, 40),
+        }
+    }
+
+    /// Collects regions the loader has finished. Returns true if any arrived.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((pos, image)) = self.results.try_recv() {
+            let tile = image.map_or(Tile::Failed, Tile::Ready);
+            self.tiles.insert(pos, tile);
+            changed = true;
+        }
+        changed
+    }
+
+    fn scale(&self) -> i32 {
+        ZOOM_LEVELS[self.zoom]
+    }
+
+    /// Moves the view by a quarter of its size in the given direction.
+    pub fn pan(&mut self, dx: i32, dz: i32) {
+        let scale = self.scale();
+        self.center.0 += dx * (self.view.0 * scale / 4).max(1);
+        self.center.1 += dz * (self.view.1 * scale / 4).max(1);
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom + 1).min(ZOOM_LEVELS.len() - 1);
+    }
+
+    pub fn center_on_spawn(&mut self) {
+        if let Some(spawn) = self.spawn {
+            self.center = spawn;
+        }
+    }
+
+    pub fn center_on_player(&mut self) {
+        if let Some(player) = self.player {
+            self.center = player;
+        }
+    }
+
+    fn pixel(&mut self, x: i32, z: i32) -> Pixel {
+        let pos = (x.div_euclid(REGION_BLOCKS), z.div_euclid(REGION_BLOCKS));
+        if !self.available.contains(&pos) {
+            return Pixel::Empty;
+        }
+        match self.tiles.get(&pos) {
+            Some(Tile::Ready(image)) => {
+                let index = z.rem_euclid(REGION_BLOCKS) * REGION_BLOCKS + x.rem_euclid(REGION_BLOCKS);
+                image.pixels[index as usize].map_or(Pixel::Empty, Pixel::Color)
+            }
+            Some(Tile::Failed) => Pixel::Empty,
+            Some(Tile::Loading) => Pixel::Loading,
+            None => {
+                let _ = self.requests.send(pos);
+                self.tiles.insert(pos, Tile::Loading);
+                Pixel::Loading
+            }
+        }
+    }
+
+    fn loading(&self) -> usize {
+        self.tiles.values().filter(|t| matches!(t, Tile::Loading)).count()
+    }
+
+    /// Where a block position lands in the view, as (column, pixel row).
+    fn project(&self, (x, z): (i32, i32)) -> Option<(i32, i32)> {
+        let scale = self.scale();
+        let px = (x - self.center.0).div_euclid(scale) + self.view.0 / 2;
+        let py = (z - self.center.1).div_euclid(scale) + self.view.1 / 2;
+        (0..self.view.0).contains(&px).then_some(())?;
+        (0..self.view.1).contains(&py).then_some((px, py))
+    }
+
+    pub fn status(&self) -> String {
+        let mut status = format!("x {}, z {} • 1:{}", self.center.0, self.center.1, self.scale());
+        let loading = self.loading();
+        if loading > 0 {
+            status.push_str(&format!(" • loading {} region(s)", loading));
+        }
+        if self.available.is_empty() {
+            status.push_str(" • no region files");
+        }
+        status
+    }
+}
+
+fn render_region(surface: &RegionSurface) -> RegionImage {
+    let size = REGION_BLOCKS as usize;
+    let mut heights = vec![None; size * size];
+    let mut bases = vec![None; size * size];
+
+    for (chunk_index, chunk) in surface.chunks.iter().enumerate() {
+        let Some(chunk) = chunk else { continue };
+        let (chunk_x, chunk_z) = (chunk_index % 32, chunk_index / 32);
+        let colors: Vec<[u8; 3]> = chunk.palette.iter().map(|name| block_color(name)).collect();
+        for (column_index, column) in chunk.columns.iter().enumerate() {
+            let Some(column) = column else { continue };
+            let x = chunk_x * 16 + column_index % 16;
+            let z = chunk_z * 16 + column_index / 16;
+            let i = z * size + x;
+            heights[i] = Some(column.y);
+            bases[i] = Some((colors[column.block as usize], column.water_depth));
+        }
+    }
+
+    let pixels = (0..size * size)
+        .map(|i| {
+            let (color, water_depth) = bases[i]?;
+            let factor = if water_depth > 0 {
+                1.0 - water_depth.min(24) as f32 * 0.025
+            } else {
+                // Light slopes facing north, dark slopes facing south
+                match (heights[i], i.checked_sub(size).and_then(|north| heights[north])) {
+                    (Some(y), Some(north)) if y > north => 1.15,
+                    (Some(y), Some(north)) if y < north => 0.82,
+                    _ => 1.0,
+                }
+            };
+            Some(shade(color, factor))
+        })
+        .collect();
+    RegionImage { pixels }
+}
+
+fn shade([r, g, b]: [u8; 3], factor: f32) -> [u8; 3] {
+    let scale = |c: u8| (c as f32 * factor).round().clamp(0.0, 255.0) as u8;
+    [scale(r), scale(g), scale(b)]
+}
+
+/// Approximate top-down color of a block, by exact name first and then by
+/// family (leaves, logs, dyed blocks, …).
+pub fn block_color(name: &str) -> [u8; 3] {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let exact = match name {
+        "grass_block" => Some([95, 159, 53]),
+        "short_grass" | "grass" | "tall_grass" | "fern" | "large_fern" => Some([88, 150, 48]),
+        "dirt" | "coarse_dirt" | "rooted_dirt" | "dirt_path" | "farmland" => Some([134, 96, 67]),
+        "podzol" => Some([106, 75, 40]),
+        "mycelium" => Some([111, 99, 107]),
+        "mud" => Some([60, 57, 61]),
+        "moss_block" | "moss_carpet" => Some([89, 109, 45]),
+        "water" | "bubble_column" | "seagrass" | "tall_seagrass" | "kelp" | "kelp_plant" => Some([52, 86, 186]),
+        "lava" | "magma_block" => Some([207, 92, 20]),
+        "sand" | "sandstone" | "smooth_sandstone" | "cut_sandstone" => Some([219, 207, 163]),
+        "red_sand" | "red_sandstone" => Some([190, 102, 33]),
+        "gravel" => Some([131, 127, 126]),
+        "clay" => Some([160, 166, 179]),
+        "snow" | "snow_block" | "powder_snow" => Some([243, 248, 248]),
+        "ice" | "frosted_ice" => Some([145, 183, 253]),
+        "packed_ice" | "blue_ice" => Some([116, 167, 253]),
+        "stone" | "cobblestone" | "andesite" | "stone_bricks" => Some([125, 125, 125]),
+        "granite" => Some([149, 103, 85]),
+        "diorite" | "calcite" => Some([200, 200, 200]),
+        "deepslate" | "cobbled_deepslate" | "tuff" => Some([80, 80, 82]),
+        "bedrock" => Some([85, 85, 85]),
+        "obsidian" | "crying_obsidian" => Some([20, 18, 30]),
+        "netherrack" => Some([97, 38, 38]),
+        "soul_sand" | "soul_soil" => Some([81, 62, 50]),
+        "basalt" | "blackstone" => Some([60, 58, 64]),
+        "end_stone" => Some([219, 222, 158]),
+        "cactus" => Some([85, 127, 43]),
+        "sugar_cane" | "bamboo" => Some([148, 192, 101]),
+        "pumpkin" => Some([198, 118, 24]),
+        "melon" => Some([111, 145, 30]),
+        "lily_pad" => Some([32, 128, 48]),
+        "terracotta" => Some([152, 94, 67]),
+        "glass" => Some([200, 220, 230]),
+        _ => None,
+    };
+    if let Some(color) = exact {
+        return color;
+    }
+
+    if name.ends_with("_leaves") {
+        return match name {
+            "birch_leaves" => [128, 167, 85],
+            "spruce_leaves" => [97, 153, 97],
+            "cherry_leaves" => [229, 172, 194],
+            "azalea_leaves" | "flowering_azalea_leaves" => [101, 124, 47],
+            _ => [60, 120, 30],
+        };
+    }
+    if name.ends_with("_log") || name.ends_with("_wood") || name.ends_with("_stem") || name.ends_with("_hyphae") {
+        return [102, 81, 51];
+    }
+    if name.ends_with("_planks") || name.ends_with("_slab") || name.ends_with("_stairs") || name.ends_with("_fence") {
+        return [162, 130, 78];
+    }
+    if name.contains("copper") {
+        return [192, 107, 79];
+    }
+    if name.ends_with("_nylium") {
+        return if name.starts_with("warped") { [43, 114, 101] } else { [189, 48, 49] };
+    }
+    // Dyed blocks: wool, carpet, concrete, terracotta, glass, beds, …
+    let dyes: [(&str, [u8; 3]); 16] = [
+        ("light_blue_", [58, 175, 217]),
+        ("light_gray_", [142, 142, 134]),
+        ("white_", [233, 236, 236]),
+        ("orange_", [240, 118, 19]),
+        ("magenta_", [189, 68, 179]),
+        ("yellow_", [248, 197, 39]),
+        ("lime_", [112, 185, 25]),
+        ("pink_", [237, 141, 172]),
+        ("gray_", [62, 68, 71]),
+        ("cyan_", [21, 137, 145]),
+        ("purple_", [121, 42, 172]),
+        ("blue_", [53, 57, 157]),
+        ("brown_", [114, 71, 40]),
+        ("green_", [84, 109, 27]),
+        ("red_", [160, 39, 34]),
+        ("black_", [20, 21, 25]),
+    ];
+    if let Some((_, color)) = dyes.iter().find(|(prefix, _)| name.starts_with(prefix)) {
+        return *color;
+    }
+    if name.contains("flower") || name.ends_with("_tulip") || matches!(name, "poppy" | "dandelion" | "cornflower") {
+        return [88, 150, 48];
+    }
+    if name.contains("stone") || name.contains("brick") || name.contains("ore") {
+        return [125, 125, 125];
+    }
+    [128, 128, 128]
+}
+
+fn to_color(pixel: Pixel) -> Color {
+    match pixel {
+        Pixel::Color([r, g, b]) => Color::Rgb(r, g, b),
+        Pixel::Empty => Color::Rgb(12, 12, 16),
+        Pixel::Loading => Color::Rgb(40, 40, 48),
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, map: &mut MapView) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" Map — {} │ {} ", map.world_name, map.status()));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if inner.width == 0 || inner.height == 0 {
+        return;
+    }
+
+    map.view = (inner.width as i32, inner.height as i32 * 2);
+    let scale = map.scale();
+    let (half_w, half_h) = (map.view.0 / 2, map.view.1 / 2);
+    for row in 0..inner.height {
+        for col in 0..inner.width {
+            let x = map.center.0 + (col as i32 - half_w) * scale;
+            let top_z = map.center.1 + (row as i32 * 2 - half_h) * scale;
+            let top = to_color(map.pixel(x, top_z));
+            let bottom = to_color(map.pixel(x, top_z + scale));
+            f.buffer_mut()[(inner.x + col, inner.y + row)]
+                .set_char('▀')
+                .set_fg(top)
+                .set_bg(bottom);
+        }
+    }
+
+    let markers = [(map.spawn, '◆', Color::Yellow), (map.player, '●', Color::Red)];
+    for (position, symbol, color) in markers {
+        if let Some((col, py)) = position.and_then(|p| map.project(p)) {
+            let cell = &mut f.buffer_mut()[(inner.x + col as u16, inner.y + (py / 2) as u16)];
+            cell.set_char(symbol)
+                .set_style(Style::default().fg(color).bg(Color::Black).add_modifier(Modifier::BOLD));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_colors() {
+        assert_eq!(block_color("minecraft:grass_block"), [95, 159, 53]);
+        assert_eq!(block_color("minecraft:dark_oak_leaves"), [60, 120, 30]);
+        assert_eq!(block_color("minecraft:light_blue_wool"), [58, 175, 217]);
+        assert_eq!(block_color("minecraft:blue_concrete"), [53, 57, 157]);
+        assert_eq!(block_color("somemod:mystery_block"), [128, 128, 128]);
+    }
+
+    #[test]
+    fn test_project_markers() {
+        let mut map = MapView::open(Path::new("/nonexistent"), &World::default());
+        map.view = (80, 40);
+        map.center = (100, 100);
+        assert_eq!(map.project((100, 100)), Some((40, 20)));
+        map.zoom = 2;
+        assert_eq!(map.project((108, 92)), Some((42, 18)));
+        assert_eq!(map.project((10_000, 100)), None);
+    }
+}
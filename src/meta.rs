@@ -0,0 +1,188 @@
+//! Offline version catalog built from the metadata Prism caches under `meta/`.
+//!
+//! Each package (`net.minecraft`, `net.fabricmc.fabric-loader`, ...) has an
+//! `index.json` listing every version Prism knows about, with release times and
+//! requirements. Nothing here touches the network: if Prism never downloaded an
+//! index, the catalog simply knows nothing about that package.
+
+use crate::version::McVersion;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+pub const MINECRAFT_UID: &str = "net.minecraft";
+
+/// Packages whose indexes are loaded: the game itself plus every mod loader.
+const PACKAGES: &[&str] = &[
+    MINECRAFT_UID,
+    "net.fabricmc.fabric-loader",
+    "org.quiltmc.quilt-loader",
+    "net.minecraftforge",
+    "net.neoforged",
+    "com.mumfrey.liteloader",
+];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetaVersion {
+    pub version: String,
+    pub release_time: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub requires: Vec<MetaRequirement>,
+}
+
+impl MetaVersion {
+    /// The Minecraft version this entry is pinned to, if it declares one.
+    fn required_minecraft(&self) -> Option<&str> {
+        self.requires
+            .iter()
+            .find(|r| r.uid == MINECRAFT_UID)
+            .and_then(|r| r.equals.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetaRequirement {
+    pub uid: String,
+    pub equals: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetaIndex {
+    versions: Vec<MetaVersion>,
+}
+
+/// What the local metadata says about an instance's versions.
+#[derive(Debug, Clone, Default)]
+pub struct VersionStatus {
+    pub release_time: Option<String>,
+    pub kind: Option<String>,
+    pub newer_minecraft: Option<String>,
+    pub newer_loader: Option<String>,
+}
+
+impl VersionStatus {
+    pub fn release_date(&self) -> Option<&str> {
+        self.release_time.as_deref().map(|t| t.get(..10).unwrap_or(t))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Catalog {
+    packages: HashMap<String, Vec<MetaVersion>>,
+}
+
+impl Catalog {
+    pub fn load(meta_dir: &Path) -> Self {
+        let mut catalog = Catalog::default();
+        for uid in PACKAGES {
+            let index_path = meta_dir.join(uid).join("index.json");
+            if let Ok(content) = fs::read_to_string(&index_path)
+                && let Ok(index) = serde_json::from_str::<MetaIndex>(&content)
+            {
+                catalog.insert(uid, index.versions);
+            }
+        }
+        catalog
+    }
+
+    pub fn insert(&mut self, uid: &str, versions: Vec<MetaVersion>) {
+        self.packages.insert(uid.to_string(), versions);
+    }
+
+    pub fn version(&self, uid: &str, version: &str) -> Option<&MetaVersion> {
+        self.packages.get(uid)?.iter().find(|v| v.version == version)
+    }
+
+    /// Newest release of Minecraft that is a later version than `current`.
+    pub fn newer_minecraft(&self, current: &str) -> Option<&MetaVersion> {
+        let current = McVersion::parse(current);
+        self.packages
+            .get(MINECRAFT_UID)?
+            .iter()
+            .filter(|v| v.kind.as_deref() == Some("release"))
+            .map(|v| (McVersion::parse(&v.version), v))
+            .filter(|(version, _)| version.is_release() && *version > current)
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, v)| v)
+    }
+
+    /// Newest build of a loader that is newer than `current` and still targets `minecraft`.
+    pub fn newer_loader(&self, uid: &str, current: &str, minecraft: Option<&str>) -> Option<&MetaVersion> {
+        let current_time = self.version(uid, current)?.release_time.as_deref()?;
+        self.packages
+            .get(uid)?
+            .iter()
+            .filter(|v| match (v.required_minecraft(), minecraft) {
+                (Some(required), Some(minecraft)) => required == minecraft,
+                _ => true,
+            })
+            .filter(|v| v.release_time.as_deref().is_some_and(|t| t > current_time))
+            .max_by(|a, b| a.release_time.cmp(&b.release_time))
+    }
+
+    pub fn status(&self, minecraft: Option<&str>, loader: Option<(&str, &str)>) -> VersionStatus {
+        let current = minecraft.and_then(|v| self.version(MINECRAFT_UID, v));
+        VersionStatus {
+            release_time: current.and_then(|v| v.release_time.clone()),
+            kind: current.and_then(|v| v.kind.clone()),
+            newer_minecraft: minecraft
+                .and_then(|v| self.newer_minecraft(v))
+                .map(|v| v.version.clone()),
+            newer_loader: loader
+                .and_then(|(uid, version)| self.newer_loader(uid, version, minecraft))
+                .map(|v| v.version.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        let minecraft: MetaIndex = serde_json::from_str(r#"{"versions": [
+            {"version": "24w14a", "type": "snapshot", "releaseTime": "2024-04-03T12:00:00+00:00"},
+            {"version": "1.9", "type": "release", "releaseTime": "2016-02-29T13:49:54+00:00"},
+            {"version": "1.20.4", "type": "release", "releaseTime": "2023-12-07T12:56:20+00:00"},
+            {"version": "1.20.1", "type": "release", "releaseTime": "2023-06-12T13:25:51+00:00"}
+        ]}"#).unwrap();
+        let forge: MetaIndex = serde_json::from_str(r#"{"versions": [
+            {"version": "49.0.30", "releaseTime": "2024-02-01T00:00:00+00:00",
+             "requires": [{"uid": "net.minecraft", "equals": "1.20.4"}]},
+            {"version": "47.2.20", "releaseTime": "2024-01-10T00:00:00+00:00",
+             "requires": [{"uid": "net.minecraft", "equals": "1.20.1"}]},
+            {"version": "47.1.0", "releaseTime": "2023-07-01T00:00:00+00:00",
+             "requires": [{"uid": "net.minecraft", "equals": "1.20.1"}]}
+        ]}"#).unwrap();
+
+        let mut catalog = Catalog::default();
+        catalog.insert(MINECRAFT_UID, minecraft.versions);
+        catalog.insert("net.minecraftforge", forge.versions);
+        catalog
+    }
+
+    #[test]
+    fn test_newer_minecraft_skips_snapshots() {
+        let catalog = catalog();
+        assert_eq!(catalog.newer_minecraft("1.20.1").unwrap().version, "1.20.4");
+        assert!(catalog.newer_minecraft("1.20.4").is_none());
+    }
+
+    #[test]
+    fn test_newer_loader_matches_minecraft() {
+        let catalog = catalog();
+        let newer = catalog.newer_loader("net.minecraftforge", "47.1.0", Some("1.20.1"));
+        assert_eq!(newer.unwrap().version, "47.2.20");
+    }
+
+    #[test]
+    fn test_status() {
+        let status = catalog().status(Some("1.20.1"), None);
+        assert_eq!(status.release_date(), Some("2023-06-12"));
+        assert_eq!(status.kind.as_deref(), Some("release"));
+        assert_eq!(status.newer_minecraft.as_deref(), Some("1.20.4"));
+        assert!(status.newer_loader.is_none());
+    }
+}
@@ -0,0 +1,588 @@
+//! Reader and writer for Minecraft's NBT (Named Binary Tag) format.
+//!
+//! Compounds keep their entries in file order and lists remember their element
+//! type, so a parsed tree carries everything needed to write the same bytes back.
+//! Strings are Java "modified UTF-8" on disk.
+
+use crate::backup::{now_secs, timestamp_name};
+use anyhow::{bail, Context, Result};
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression as Level,
+};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+/// Deeper nesting than this is treated as a corrupt file rather than recursed into.
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// Element type id and elements. Empty lists keep whatever type id the file had.
+    List(u8, Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    /// Looks up a direct child of a compound.
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Follows a chain of compound keys, e.g. `["Data", "Version", "Name"]`.
+    pub fn path(&self, keys: &[&str]) -> Option<&Tag> {
+        keys.iter().try_fold(self, |tag, key| tag.get(key))
+    }
+
+    /// Any integer tag widened to i64.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Tag::Float(v) => Some(v as f64),
+            Tag::Double(v) => Some(v),
+            _ => self.as_i64().map(|v| v as f64),
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(_, items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(..) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Tag::Byte(_) => "byte",
+            Tag::Short(_) => "short",
+            Tag::Int(_) => "int",
+            Tag::Long(_) => "long",
+            Tag::Float(_) => "float",
+            Tag::Double(_) => "double",
+            Tag::ByteArray(_) => "byte[]",
+            Tag::String(_) => "string",
+            Tag::List(..) => "list",
+            Tag::Compound(_) => "compound",
+            Tag::IntArray(_) => "int[]",
+            Tag::LongArray(_) => "long[]",
+        }
+    }
+
+    /// Numbers and strings, the tags that can be edited in place.
+    pub fn is_scalar(&self) -> bool {
+        !matches!(
+            self,
+            Tag::ByteArray(_) | Tag::List(..) | Tag::Compound(_) | Tag::IntArray(_) | Tag::LongArray(_)
+        )
+    }
+
+    /// Parses `text` as a new value of the same type as this tag.
+    pub fn parse_like(&self, text: &str) -> Result<Tag> {
+        let text = text.trim();
+        let invalid = || format!("{:?} is not a valid {}", text, self.type_name());
+        Ok(match self {
+            Tag::Byte(_) => Tag::Byte(match text {
+                "true" => 1,
+                "false" => 0,
+                _ => text.parse().with_context(invalid)?,
+            }),
+            Tag::Short(_) => Tag::Short(text.parse().with_context(invalid)?),
+            Tag::Int(_) => Tag::Int(text.parse().with_context(invalid)?),
+            Tag::Long(_) => Tag::Long(text.parse().with_context(invalid)?),
+            Tag::Float(_) => Tag::Float(text.parse().with_context(invalid)?),
+            Tag::Double(_) => Tag::Double(text.parse().with_context(invalid)?),
+            Tag::String(_) => {
+                if encode_mutf8(text).len() > u16::MAX as usize {
+                    bail!("string is longer than NBT allows");
+                }
+                Tag::String(text.to_string())
+            }
+            _ => bail!("{} tags cannot be edited as text", self.type_name()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zlib,
+}
+
+/// A whole NBT file: the root tag plus what is needed to write it back the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub name: String,
+    pub root: Tag,
+    pub compression: Compression,
+}
+
+impl Document {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let compression = detect_compression(&bytes);
+        let raw = decompress(&bytes).with_context(|| format!("decompressing {}", path.display()))?;
+        let (name, root) = read(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        Ok(Self { name, root, compression })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let raw = write(&self.name, &self.root)?;
+        Ok(match self.compression {
+            Compression::None => raw,
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+                encoder.write_all(&raw)?;
+                encoder.finish()?
+            }
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Level::default());
+                encoder.write_all(&raw)?;
+                encoder.finish()?
+            }
+        })
+    }
+
+    /// Writes to a temporary file next to `path` and renames it over the original,
+    /// so a failed write never leaves a half-written file behind.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let bytes = self.to_bytes()?;
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".part");
+        fs::write(&temp, bytes).with_context(|| format!("writing {}", path.display()))?;
+        fs::rename(&temp, path).with_context(|| format!("replacing {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Copies the file currently at `path` to `<name>.<timestamp>.bak`, then saves
+    /// over it. Returns the backup's path, or `None` if there was no file yet.
+    pub fn save_with_backup(&self, path: &Path) -> Result<Option<PathBuf>> {
+        let backup = if path.exists() {
+            let mut name = path.as_os_str().to_owned();
+            name.push(format!(".{}.bak", timestamp_name(now_secs())));
+            let backup = PathBuf::from(name);
+            fs::copy(path, &backup).with_context(|| format!("backing up {}", path.display()))?;
+            Some(backup)
+        } else {
+            None
+        };
+        self.save(path)?;
+        Ok(backup)
+    }
+}
+
+/// Reads an NBT file and returns its root tag.
+pub fn read_file(path: &Path) -> Result<Tag> {
+    let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    from_bytes(&bytes).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Detects gzip/zlib from the magic bytes, falling back to uncompressed NBT.
+pub fn from_bytes(bytes: &[u8]) -> Result<Tag> {
+    let raw = decompress(bytes)?;
+    let (_, root) = read(&raw)?;
+    Ok(root)
+}
+
+fn detect_compression(bytes: &[u8]) -> Compression {
+    match bytes {
+        [0x1f, 0x8b, ..] => Compression::Gzip,
+        [0x78, second, ..] if (0x78u16 << 8 | *second as u16).is_multiple_of(31) => Compression::Zlib,
+        _ => Compression::None,
+    }
+}
+
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut raw = Vec::new();
+    match detect_compression(bytes) {
+        Compression::Gzip => {
+            GzDecoder::new(bytes).read_to_end(&mut raw)?;
+        }
+        Compression::Zlib => {
+            ZlibDecoder::new(bytes).read_to_end(&mut raw)?;
+        }
+        Compression::None => raw.extend_from_slice(bytes),
+    }
+    Ok(raw)
+}
+
+/// Parses uncompressed NBT: a single named root tag.
+pub fn read(bytes: &[u8]) -> Result<(String, Tag)> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let id = reader.u8()?;
+    if id == TAG_END {
+        bail!("empty NBT document");
+    }
+    let name = reader.string()?;
+    let root = reader.payload(id, 0)?;
+    Ok((name, root))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len());
+        let Some(end) = end else {
+            bail!("unexpected end of NBT data at byte {}", self.pos);
+        };
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("slice has requested length"))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let len = i32::from_be_bytes(self.array()?);
+        if len < 0 {
+            bail!("negative length {} at byte {}", len, self.pos);
+        }
+        Ok(len as usize)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = u16::from_be_bytes(self.array()?) as usize;
+        Ok(decode_mutf8(self.take(len)?))
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> Result<Tag> {
+        if depth > MAX_DEPTH {
+            bail!("NBT nested deeper than {} levels", MAX_DEPTH);
+        }
+        Ok(match id {
+            TAG_BYTE => Tag::Byte(self.u8()? as i8),
+            TAG_SHORT => Tag::Short(i16::from_be_bytes(self.array()?)),
+            TAG_INT => Tag::Int(i32::from_be_bytes(self.array()?)),
+            TAG_LONG => Tag::Long(i64::from_be_bytes(self.array()?)),
+            TAG_FLOAT => Tag::Float(f32::from_be_bytes(self.array()?)),
+            TAG_DOUBLE => Tag::Double(f64::from_be_bytes(self.array()?)),
+            TAG_BYTE_ARRAY => {
+                let len = self.len()?;
+                Tag::ByteArray(self.take(len)?.iter().map(|&b| b as i8).collect())
+            }
+            TAG_STRING => Tag::String(self.string()?),
+            TAG_LIST => {
+                let element = self.u8()?;
+                let len = self.len()?;
+                if element == TAG_END && len > 0 {
+                    bail!("list of TAG_End with {} elements", len);
+                }
+                let mut items = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    items.push(self.payload(element, depth + 1)?);
+                }
+                Tag::List(element, items)
+            }
+            TAG_COMPOUND => {
+                let mut entries = Vec::new();
+                loop {
+                    let id = self.u8()?;
+                    if id == TAG_END {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(id, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            TAG_INT_ARRAY => {
+                let len = self.len()?;
+                let bytes = self.take(len.checked_mul(4).context("int array too long")?)?;
+                Tag::IntArray(bytes.chunks_exact(4).map(|c| i32::from_be_bytes(c.try_into().unwrap())).collect())
+            }
+            TAG_LONG_ARRAY => {
+                let len = self.len()?;
+                let bytes = self.take(len.checked_mul(8).context("long array too long")?)?;
+                Tag::LongArray(bytes.chunks_exact(8).map(|c| i64::from_be_bytes(c.try_into().unwrap())).collect())
+            }
+            other => bail!("unknown tag type {} at byte {}", other, self.pos),
+        })
+    }
+}
+
+/// Serializes a named root tag to uncompressed NBT.
+pub fn write(name: &str, root: &Tag) -> Result<Vec<u8>> {
+    let mut out = vec![root.id()];
+    write_string(&mut out, name)?;
+    write_payload(&mut out, root)?;
+    Ok(out)
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) -> Result<()> {
+    let bytes = encode_mutf8(s);
+    let len = u16::try_from(bytes.len()).context("string too long for NBT")?;
+    out.extend(len.to_be_bytes());
+    out.extend(bytes);
+    Ok(())
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) -> Result<()> {
+    let len = i32::try_from(len).context("array too long for NBT")?;
+    out.extend(len.to_be_bytes());
+    Ok(())
+}
+
+fn write_payload(out: &mut Vec<u8>, tag: &Tag) -> Result<()> {
+    match tag {
+        Tag::Byte(v) => out.push(*v as u8),
+        Tag::Short(v) => out.extend(v.to_be_bytes()),
+        Tag::Int(v) => out.extend(v.to_be_bytes()),
+        Tag::Long(v) => out.extend(v.to_be_bytes()),
+        Tag::Float(v) => out.extend(v.to_be_bytes()),
+        Tag::Double(v) => out.extend(v.to_be_bytes()),
+        Tag::ByteArray(values) => {
+            write_len(out, values.len())?;
+            out.extend(values.iter().map(|&b| b as u8));
+        }
+        Tag::String(s) => write_string(out, s)?,
+        Tag::List(element, items) => {
+            if let Some(item) = items.iter().find(|item| item.id() != *element) {
+                bail!("list of {} contains a {}", element, item.type_name());
+            }
+            out.push(*element);
+            write_len(out, items.len())?;
+            for item in items {
+                write_payload(out, item)?;
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, value) in entries {
+                out.push(value.id());
+                write_string(out, name)?;
+                write_payload(out, value)?;
+            }
+            out.push(TAG_END);
+        }
+        Tag::IntArray(values) => {
+            write_len(out, values.len())?;
+            values.iter().for_each(|v| out.extend(v.to_be_bytes()));
+        }
+        Tag::LongArray(values) => {
+            write_len(out, values.len())?;
+            values.iter().for_each(|v| out.extend(v.to_be_bytes()));
+        }
+    }
+    Ok(())
+}
+
+/// Encodes Java's modified UTF-8, the inverse of [`decode_mutf8`].
+fn encode_mutf8(s: &str) -> Vec<u8> {
+    if !s.contains('\0') && s.chars().all(|c| (c as u32) < 0x10000) {
+        return s.as_bytes().to_vec();
+    }
+
+    let mut out = Vec::with_capacity(s.len() + 4);
+    for unit in s.encode_utf16() {
+        match unit {
+            0x01..=0x7f => out.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                out.push(0xc0 | (unit >> 6) as u8);
+                out.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                out.push(0xe0 | (unit >> 12) as u8);
+                out.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                out.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    out
+}
+
+/// Decodes Java's modified UTF-8: NUL is `C0 80` and characters outside the BMP
+/// are written as two 3-byte surrogates.
+fn decode_mutf8(bytes: &[u8]) -> String {
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return s.to_string();
+    }
+
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let continuation = |offset: usize| bytes.get(i + offset).map_or(0, |&c| (c & 0x3f) as u16);
+        if b < 0x80 {
+            units.push(b as u16);
+            i += 1;
+        } else if b & 0xe0 == 0xc0 {
+            units.push(((b & 0x1f) as u16) << 6 | continuation(1));
+            i += 2;
+        } else {
+            units.push(((b & 0x0f) as u16) << 12 | continuation(1) << 6 | continuation(2));
+            i += 3;
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `{"": {Data: {LevelName: "Test", GameType: 1, Seed: 42L, Pos: [1.5d, 2.5d]}}}`
+    fn sample() -> Vec<u8> {
+        let mut b = vec![TAG_COMPOUND, 0, 0];
+        b.extend([TAG_COMPOUND, 0, 4]);
+        b.extend(b"Data");
+        b.extend([TAG_STRING, 0, 9]);
+        b.extend(b"LevelName");
+        b.extend([0, 4]);
+        b.extend(b"Test");
+        b.extend([TAG_INT, 0, 8]);
+        b.extend(b"GameType");
+        b.extend(1i32.to_be_bytes());
+        b.extend([TAG_LONG, 0, 4]);
+        b.extend(b"Seed");
+        b.extend(42i64.to_be_bytes());
+        b.extend([TAG_LIST, 0, 3]);
+        b.extend(b"Pos");
+        b.push(TAG_DOUBLE);
+        b.extend(2i32.to_be_bytes());
+        b.extend(1.5f64.to_be_bytes());
+        b.extend(2.5f64.to_be_bytes());
+        b.push(TAG_END);
+        b.push(TAG_END);
+        b
+    }
+
+    #[test]
+    fn test_read_uncompressed() {
+        let (name, root) = read(&sample()).unwrap();
+        assert_eq!(name, "");
+        assert_eq!(root.path(&["Data", "LevelName"]).and_then(Tag::as_str), Some("Test"));
+        assert_eq!(root.path(&["Data", "GameType"]).and_then(Tag::as_i64), Some(1));
+        assert_eq!(root.path(&["Data", "Seed"]).and_then(Tag::as_i64), Some(42));
+        assert_eq!(
+            root.path(&["Data", "Pos"]),
+            Some(&Tag::List(TAG_DOUBLE, vec![Tag::Double(1.5), Tag::Double(2.5)]))
+        );
+    }
+
+    #[test]
+    fn test_read_gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(&sample()).unwrap();
+        let root = from_bytes(&encoder.finish().unwrap()).unwrap();
+        assert!(root.get("Data").is_some());
+    }
+
+    #[test]
+    fn test_truncated_data_is_an_error() {
+        let data = sample();
+        assert!(read(&data[..data.len() - 5]).is_err());
+    }
+
+    #[test]
+    fn test_modified_utf8() {
+        assert_eq!(decode_mutf8(&[0xc0, 0x80]), "\0");
+        // U+1F600 as a CESU-8 surrogate pair
+        assert_eq!(decode_mutf8(&[0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]), "😀");
+        assert_eq!(encode_mutf8("a\0😀"), [b'a', 0xc0, 0x80, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+        assert_eq!(encode_mutf8("Überwelt"), "Überwelt".as_bytes());
+    }
+
+    #[test]
+    fn test_write_round_trips() {
+        let bytes = sample();
+        let (name, root) = read(&bytes).unwrap();
+        assert_eq!(write(&name, &root).unwrap(), bytes);
+
+        let dir = std::env::temp_dir().join(format!("mctui-nbt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("level.dat");
+        let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(&bytes).unwrap();
+        fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let mut doc = Document::load(&path).unwrap();
+        assert_eq!(doc.compression, Compression::Gzip);
+        let Tag::Compound(entries) = &mut doc.root else { panic!() };
+        entries[0].1 = Tag::Compound(vec![("GameType".into(), Tag::Int(3))]);
+        doc.save(&path).unwrap();
+        let reloaded = Document::load(&path).unwrap();
+        assert_eq!(reloaded, doc);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_like() {
+        assert_eq!(Tag::Byte(0).parse_like("true").unwrap(), Tag::Byte(1));
+        assert_eq!(Tag::Long(0).parse_like(" -5 ").unwrap(), Tag::Long(-5));
+        assert!(Tag::Byte(0).parse_like("300").is_err());
+        assert!(Tag::List(TAG_INT, vec![]).parse_like("1").is_err());
+    }
+}
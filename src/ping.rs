@@ -0,0 +1,531 @@
+//! The Server List Ping protocol, as used by the multiplayer screen.
+//!
+//! Modern servers (1.7+) answer a handshake and status request with a JSON
+//! document, then echo a ping packet so the round trip can be timed. Older
+//! servers only understand the legacy `0xFE 0x01` query, which gets a
+//! UTF-16 string back; that is tried when the modern exchange fails.
+//!
+//! SRV records are not looked up, so servers that rely on them need their
+//! port in the address.
+
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use serde_json::Value;
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_PORT: u16 = 25565;
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+    /// The MOTD as a chat component; legacy servers give a plain `§` string.
+    pub description: Value,
+    pub version: String,
+    pub protocol: Option<i64>,
+    pub online: i64,
+    pub max: i64,
+    pub sample: Vec<String>,
+    /// The `data:image/png;base64,…` favicon, if the server sends one.
+    pub favicon: Option<String>,
+    pub latency: Duration,
+    pub legacy: bool,
+}
+
+/// Splits `host[:port]`, accepting `[v6]:port` and bare IPv6 addresses.
+pub fn parse_address(address: &str, default_port: u16) -> Result<(String, u16)> {
+    let address = address.trim();
+    if address.is_empty() {
+        bail!("no address");
+    }
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').context("unclosed [ in address")?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (address, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().with_context(|| format!("invalid port {}", port))?,
+        None => default_port,
+    };
+    Ok((host.to_string(), port))
+}
+
+/// `host:port` with the host lowercased and the default port filled in, so
+/// differently written addresses of one server compare equal.
+pub fn normalize_address(address: &str) -> Result<String> {
+    let (host, port) = parse_address(address, DEFAULT_PORT)?;
+    let host = host.to_ascii_lowercase();
+    Ok(if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    })
+}
+
+pub fn connect(host: &str, port: u16, timeout: Duration) -> Result<TcpStream> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .with_context(|| format!("resolving {}", host))?;
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) => Err(e).with_context(|| format!("connecting to {}:{}", host, port)),
+        None => bail!("{} has no addresses", host),
+    }
+}
+
+/// Queries `address`, falling back to the legacy protocol if the modern one fails.
+pub fn ping(address: &str, timeout: Duration) -> Result<ServerStatus> {
+    let (host, port) = parse_address(address, DEFAULT_PORT)?;
+    match ping_modern(&host, port, timeout) {
+        Ok(status) => Ok(status),
+        Err(modern) => ping_legacy(&host, port, timeout).map_err(|_| modern),
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7F) as u32) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    bail!("VarInt is too long")
+}
+
+fn write_packet(stream: &mut impl Write, id: i32, body: &[u8]) -> Result<()> {
+    let mut payload = Vec::with_capacity(body.len() + 1);
+    write_varint(&mut payload, id);
+    payload.extend_from_slice(body);
+    let mut packet = Vec::with_capacity(payload.len() + 5);
+    write_varint(&mut packet, payload.len() as i32);
+    packet.extend(payload);
+    stream.write_all(&packet)?;
+    Ok(())
+}
+
+/// Reads one packet, returning its id and body.
+fn read_packet(stream: &mut impl Read) -> Result<(i32, Vec<u8>)> {
+    let length = read_varint(stream)?;
+    if !(1..=1 << 21).contains(&length) {
+        bail!("bad packet length {}", length);
+    }
+    let mut payload = vec![0; length as usize];
+    stream.read_exact(&mut payload)?;
+    let mut cursor = payload.as_slice();
+    let id = read_varint(&mut cursor)?;
+    Ok((id, cursor.to_vec()))
+}
+
+fn ping_modern(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus> {
+    let mut stream = connect(host, port, timeout)?;
+
+    let mut handshake = Vec::new();
+    // -1 asks the server to report its own protocol version
+    write_varint(&mut handshake, -1);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend_from_slice(host.as_bytes());
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    write_packet(&mut stream, 0x00, &handshake)?;
+
+    let started = Instant::now();
+    write_packet(&mut stream, 0x00, &[])?;
+    let (id, body) = read_packet(&mut stream).context("reading status")?;
+    let mut latency = started.elapsed();
+    if id != 0x00 {
+        bail!("unexpected packet {:#04x} instead of status", id);
+    }
+    let mut cursor = body.as_slice();
+    let json_len = read_varint(&mut cursor)? as usize;
+    let json = cursor.get(..json_len).context("status response is truncated")?;
+    let json: Value = serde_json::from_slice(json).context("status response is not JSON")?;
+
+    // A server that drops the connection instead of answering the ping
+    // still gave a status; the status round trip stands in for latency.
+    let payload = 0x6D63_7475_6900_0000u64.to_be_bytes();
+    let started = Instant::now();
+    if write_packet(&mut stream, 0x01, &payload).is_ok()
+        && let Ok((0x01, echo)) = read_packet(&mut stream)
+        && echo == payload
+    {
+        latency = started.elapsed();
+    }
+
+    let players = json.get("players");
+    let count = |key: &str| players.and_then(|p| p.get(key)).and_then(Value::as_i64).unwrap_or(0);
+    let sample = players
+        .and_then(|p| p.get("sample"))
+        .and_then(Value::as_array)
+        .map(|sample| {
+            sample
+                .iter()
+                .filter_map(|player| player.get("name").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let version = json.get("version");
+    Ok(ServerStatus {
+        description: json.get("description").cloned().unwrap_or(Value::Null),
+        version: version
+            .and_then(|v| v.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        protocol: version.and_then(|v| v.get("protocol")).and_then(Value::as_i64),
+        online: count("online"),
+        max: count("max"),
+        sample,
+        favicon: json.get("favicon").and_then(Value::as_str).map(str::to_string),
+        latency,
+        legacy: false,
+    })
+}
+
+fn ping_legacy(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus> {
+    let mut stream = connect(host, port, timeout)?;
+    let started = Instant::now();
+    stream.write_all(&[0xFE, 0x01])?;
+    let mut header = [0; 3];
+    stream.read_exact(&mut header).context("reading legacy status")?;
+    let latency = started.elapsed();
+    if header[0] != 0xFF {
+        bail!("unexpected legacy response {:#04x}", header[0]);
+    }
+    let length = u16::from_be_bytes([header[1], header[2]]) as usize;
+    let mut raw = vec![0; length * 2];
+    stream.read_exact(&mut raw)?;
+    let units: Vec<u16> = raw.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    parse_legacy(&String::from_utf16_lossy(&units), latency)
+}
+
+/// 1.4+ answers `§1\0protocol\0version\0motd\0online\0max`; older servers
+/// send `motd§online§max`.
+fn parse_legacy(response: &str, latency: Duration) -> Result<ServerStatus> {
+    let number = |s: &str| s.trim().parse::<i64>().unwrap_or(0);
+    let (motd, version, protocol, online, max) = if let Some(rest) = response.strip_prefix("§1\0") {
+        let fields: Vec<&str> = rest.split('\0').collect();
+        let [protocol, version, motd, online, max] = fields[..] else {
+            bail!("legacy status has {} fields", fields.len());
+        };
+        (motd, version, protocol.parse().ok(), number(online), number(max))
+    } else {
+        let mut fields = response.rsplitn(3, '§');
+        let (Some(max), Some(online), Some(motd)) = (fields.next(), fields.next(), fields.next()) else {
+            bail!("unrecognised legacy status");
+        };
+        (motd, "", None, number(online), number(max))
+    };
+    Ok(ServerStatus {
+        description: Value::String(motd.to_string()),
+        version: version.to_string(),
+        protocol,
+        online,
+        max,
+        sample: Vec::new(),
+        favicon: None,
+        latency,
+        legacy: true,
+    })
+}
+
+/// The color a `§` code selects, or `None` for formatting codes.
+fn legacy_color(code: char) -> Option<Color> {
+    Some(match code {
+        '0' => Color::Rgb(0x00, 0x00, 0x00),
+        '1' => Color::Rgb(0x00, 0x00, 0xAA),
+        '2' => Color::Rgb(0x00, 0xAA, 0x00),
+        '3' => Color::Rgb(0x00, 0xAA, 0xAA),
+        '4' => Color::Rgb(0xAA, 0x00, 0x00),
+        '5' => Color::Rgb(0xAA, 0x00, 0xAA),
+        '6' => Color::Rgb(0xFF, 0xAA, 0x00),
+        '7' => Color::Rgb(0xAA, 0xAA, 0xAA),
+        '8' => Color::Rgb(0x55, 0x55, 0x55),
+        '9' => Color::Rgb(0x55, 0x55, 0xFF),
+        'a' => Color::Rgb(0x55, 0xFF, 0x55),
+        'b' => Color::Rgb(0x55, 0xFF, 0xFF),
+        'c' => Color::Rgb(0xFF, 0x55, 0x55),
+        'd' => Color::Rgb(0xFF, 0x55, 0xFF),
+        'e' => Color::Rgb(0xFF, 0xFF, 0x55),
+        'f' => Color::Rgb(0xFF, 0xFF, 0xFF),
+        _ => return None,
+    })
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let rgb = u32::from_str_radix(hex, 16).ok()?;
+        return Some(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+    let code = match name {
+        "black" => '0',
+        "dark_blue" => '1',
+        "dark_green" => '2',
+        "dark_aqua" => '3',
+        "dark_red" => '4',
+        "dark_purple" => '5',
+        "gold" => '6',
+        "gray" => '7',
+        "dark_gray" => '8',
+        "blue" => '9',
+        "green" => 'a',
+        "aqua" => 'b',
+        "red" => 'c',
+        "light_purple" => 'd',
+        "yellow" => 'e',
+        "white" => 'f',
+        _ => return None,
+    };
+    legacy_color(code)
+}
+
+/// Appends `text` to `lines`, applying `§` codes on top of `base`.
+/// `§r` goes back to `base`, as the game does inside a component.
+fn push_legacy(lines: &mut Vec<Line<'static>>, text: &str, base: Style) {
+    let mut style = base;
+    let mut current = String::new();
+    let mut chars = text.chars();
+    let flush = |lines: &mut Vec<Line<'static>>, current: &mut String, style: Style| {
+        if !current.is_empty() {
+            lines.last_mut().unwrap().push_span(Span::styled(std::mem::take(current), style));
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '§' => {
+                flush(lines, &mut current, style);
+                let Some(code) = chars.next().map(|c| c.to_ascii_lowercase()) else {
+                    break;
+                };
+                style = match code {
+                    'l' => style.add_modifier(Modifier::BOLD),
+                    'o' => style.add_modifier(Modifier::ITALIC),
+                    'n' => style.add_modifier(Modifier::UNDERLINED),
+                    'm' => style.add_modifier(Modifier::CROSSED_OUT),
+                    'k' => style.add_modifier(Modifier::HIDDEN),
+                    'r' => base,
+                    // A color code also clears formatting
+                    code => match legacy_color(code) {
+                        Some(color) => base.fg(color),
+                        None => style,
+                    },
+                };
+            }
+            '\n' => {
+                flush(lines, &mut current, style);
+                lines.push(Line::default());
+            }
+            c => current.push(c),
+        }
+    }
+    flush(lines, &mut current, style);
+}
+
+fn push_component(lines: &mut Vec<Line<'static>>, value: &Value, inherited: Style) {
+    match value {
+        Value::String(text) => push_legacy(lines, text, inherited),
+        Value::Array(parts) => {
+            // The first element is the parent of the rest
+            let Some((first, rest)) = parts.split_first() else {
+                return;
+            };
+            push_component(lines, first, inherited);
+            let style = component_style(first, inherited);
+            for part in rest {
+                push_component(lines, part, style);
+            }
+        }
+        Value::Object(obj) => {
+            let style = component_style(value, inherited);
+            let text = obj.get("text").or(obj.get("translate")).and_then(Value::as_str);
+            push_legacy(lines, text.unwrap_or_default(), style);
+            if let Some(Value::Array(extra)) = obj.get("extra") {
+                for part in extra {
+                    push_component(lines, part, style);
+                }
+            }
+        }
+        Value::Number(n) => push_legacy(lines, &n.to_string(), inherited),
+        Value::Bool(b) => push_legacy(lines, &b.to_string(), inherited),
+        Value::Null => {}
+    }
+}
+
+fn component_style(value: &Value, inherited: Style) -> Style {
+    let Value::Object(obj) = value else {
+        return inherited;
+    };
+    let mut style = inherited;
+    if let Some(color) = obj.get("color").and_then(Value::as_str).and_then(named_color) {
+        style = style.fg(color);
+    }
+    for (key, modifier) in [
+        ("bold", Modifier::BOLD),
+        ("italic", Modifier::ITALIC),
+        ("underlined", Modifier::UNDERLINED),
+        ("strikethrough", Modifier::CROSSED_OUT),
+        ("obfuscated", Modifier::HIDDEN),
+    ] {
+        match obj.get(key).and_then(Value::as_bool) {
+            Some(true) => style = style.add_modifier(modifier),
+            Some(false) => style = style.remove_modifier(modifier),
+            None => {}
+        }
+    }
+    style
+}
+
+/// Renders a chat component (or a plain `§` string) as styled lines.
+pub fn styled_lines(value: &Value, base: Style) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::default()];
+    push_component(&mut lines, value, base);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse_address("play.example.net", 25565).unwrap(), ("play.example.net".into(), 25565));
+        assert_eq!(parse_address("localhost:25566", 25565).unwrap(), ("localhost".into(), 25566));
+        assert_eq!(parse_address("[::1]:25570", 25565).unwrap(), ("::1".into(), 25570));
+        assert_eq!(parse_address("::1", 25575).unwrap(), ("::1".into(), 25575));
+        assert!(parse_address("host:port", 25565).is_err());
+
+        assert_eq!(normalize_address("LocalHost").unwrap(), "localhost:25565");
+        assert_eq!(normalize_address("localhost:25566").unwrap(), "localhost:25566");
+        assert_eq!(normalize_address("[::1]").unwrap(), "[::1]:25565");
+    }
+
+    #[test]
+    fn test_styled_lines() {
+        let lines = styled_lines(&Value::String("§aGreen §lbold\n§rplain".into()), Style::default());
+        assert_eq!(plain(&lines), ["Green bold", "plain"]);
+        assert_eq!(lines[0].spans[0].style.fg, legacy_color('a'));
+        assert!(lines[0].spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(lines[1].spans[0].style, Style::default());
+
+        let component = serde_json::json!({
+            "text": "",
+            "extra": [{"text": "Hub ", "color": "gold", "bold": true}, {"text": "1.21", "color": "#123456"}]
+        });
+        let lines = styled_lines(&component, Style::default());
+        assert_eq!(plain(&lines), ["Hub 1.21"]);
+        assert_eq!(lines[0].spans[0].style.fg, legacy_color('6'));
+        assert_eq!(lines[0].spans[1].style.fg, Some(Color::Rgb(0x12, 0x34, 0x56)));
+    }
+
+    /// Serves one modern status exchange on a local port.
+    fn modern_stub(status: Value) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let (id, handshake) = read_packet(&mut stream).unwrap();
+            assert_eq!(id, 0x00);
+            assert_eq!(*handshake.last().unwrap(), 1);
+            assert_eq!(read_packet(&mut stream).unwrap(), (0x00, Vec::new()));
+            let json = status.to_string();
+            let mut body = Vec::new();
+            write_varint(&mut body, json.len() as i32);
+            body.extend_from_slice(json.as_bytes());
+            write_packet(&mut stream, 0x00, &body).unwrap();
+            let (id, payload) = read_packet(&mut stream).unwrap();
+            assert_eq!(id, 0x01);
+            write_packet(&mut stream, 0x01, &payload).unwrap();
+        });
+        port
+    }
+
+    #[test]
+    fn test_ping_modern() {
+        let port = modern_stub(serde_json::json!({
+            "version": {"name": "1.21.4", "protocol": 769},
+            "players": {"max": 20, "online": 2, "sample": [{"name": "Alex", "id": "0"}, {"name": "Steve", "id": "1"}]},
+            "description": {"text": "A §bstub§r server"},
+            "favicon": "data:image/png;base64,AAAA"
+        }));
+        let status = ping(&format!("127.0.0.1:{}", port), TIMEOUT).unwrap();
+        assert!(!status.legacy);
+        assert_eq!(status.version, "1.21.4");
+        assert_eq!(status.protocol, Some(769));
+        assert_eq!((status.online, status.max), (2, 20));
+        assert_eq!(status.sample, ["Alex", "Steve"]);
+        assert_eq!(status.favicon.as_deref(), Some("data:image/png;base64,AAAA"));
+        assert_eq!(plain(&styled_lines(&status.description, Style::default())), ["A stub server"]);
+    }
+
+    #[test]
+    fn test_ping_legacy_fallback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            // A pre-1.7 server closes the connection on the modern handshake
+            drop(listener.accept().unwrap());
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 2];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(request, [0xFE, 0x01]);
+            let text: Vec<u16> = ["§1", "74", "1.6.4", "§eOld times", "3", "10"].join("\0").encode_utf16().collect();
+            let mut response = vec![0xFF];
+            response.extend_from_slice(&(text.len() as u16).to_be_bytes());
+            response.extend(text.iter().flat_map(|u| u.to_be_bytes()));
+            stream.write_all(&response).unwrap();
+        });
+        let status = ping(&format!("127.0.0.1:{}", port), TIMEOUT).unwrap();
+        assert!(status.legacy);
+        assert_eq!(status.version, "1.6.4");
+        assert_eq!(status.protocol, Some(74));
+        assert_eq!((status.online, status.max), (3, 10));
+        assert_eq!(status.description, Value::String("§eOld times".into()));
+
+        let beta = parse_legacy("A Minecraft Server§1§20", Duration::ZERO).unwrap();
+        assert_eq!((beta.online, beta.max), (1, 20));
+        assert_eq!(beta.description, Value::String("A Minecraft Server".into()));
+    }
+}
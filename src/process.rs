@@ -0,0 +1,223 @@
+//! Tracks games launched from the TUI so it can react when they exit.
+//!
+//! PrismLauncher runs the game as a separate Java process, so a session is
+//! followed by scanning `/proc` for a Java process whose command line or working
+//! directory points into the instance folder. The launcher itself may stay open
+//! after the game closes, which is why its own process is not used. Each scan
+//! also notes the game's resident memory, so a session knows its peak.
+
+use crate::Instance;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How long a launch may take (downloads, mod loading) before the game process appears.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// `/proc` is scanned at most this often.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub instance: Instance,
+    /// Wall-clock launch time, compared against file modification times.
+    pub started: SystemTime,
+    launched_at: Instant,
+    seen_game: bool,
+    /// The most resident memory the game was seen using, in bytes.
+    pub peak_memory: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionState {
+    Starting,
+    Running,
+    /// The game was seen and has now exited.
+    Exited,
+    /// No game process showed up within the startup timeout.
+    NeverStarted,
+}
+
+impl Session {
+    fn update(&mut self, game_running: bool, now: Instant) -> SessionState {
+        if game_running {
+            self.seen_game = true;
+            SessionState::Running
+        } else if self.seen_game {
+            SessionState::Exited
+        } else if now.duration_since(self.launched_at) > STARTUP_TIMEOUT {
+            SessionState::NeverStarted
+        } else {
+            SessionState::Starting
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.launched_at.elapsed()
+    }
+}
+
+#[derive(Default)]
+pub struct ProcessTracker {
+    sessions: Vec<Session>,
+    last_poll: Option<Instant>,
+}
+
+impl ProcessTracker {
+    pub fn track(&mut self, instance: &Instance) {
+        self.sessions.retain(|s| s.instance.path != instance.path);
+        self.sessions.push(Session {
+            instance: instance.clone(),
+            started: SystemTime::now(),
+            launched_at: Instant::now(),
+            seen_game: false,
+            peak_memory: None,
+        });
+    }
+
+    /// Checks on tracked games and returns the sessions that ended, with how they ended.
+    pub fn poll(&mut self) -> Vec<(Session, SessionState)> {
+        let now = Instant::now();
+        if self.sessions.is_empty() || self.last_poll.is_some_and(|last| now.duration_since(last) < POLL_INTERVAL) {
+            return Vec::new();
+        }
+        self.last_poll = Some(now);
+
+        let processes = java_processes();
+        let mut ended = Vec::new();
+        self.sessions.retain_mut(|session| {
+            let memory = processes
+                .iter()
+                .filter(|p| p.belongs_to(&session.instance.path))
+                .map(|p| p.memory)
+                .max();
+            let running = memory.is_some();
+            if let Some(Some(memory)) = memory {
+                session.peak_memory = session.peak_memory.max(Some(memory));
+            }
+            match session.update(running, now) {
+                SessionState::Starting | SessionState::Running => true,
+                state => {
+                    ended.push((session.clone(), state));
+                    false
+                }
+            }
+        });
+        ended
+    }
+}
+
+/// For each folder, whether a Java process is running in it or refers to it.
+/// Used for dedicated servers, which may have been started outside the TUI.
+pub fn java_running_in(dirs: &[PathBuf]) -> Vec<bool> {
+    let processes = java_processes();
+    dirs.iter()
+        .map(|dir| processes.iter().any(|p| p.belongs_to(dir)))
+        .collect()
+}
+
+struct JavaProcess {
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+    /// Peak resident memory in bytes.
+    memory: Option<u64>,
+}
+
+impl JavaProcess {
+    fn belongs_to(&self, instance_path: &Path) -> bool {
+        // The trailing slash keeps "instances/Create" from matching "instances/Create 2"
+        let needle = format!("{}/", instance_path.display());
+        self.cwd.as_deref().is_some_and(|cwd| cwd.starts_with(instance_path))
+            || self.args.iter().any(|arg| arg.contains(&needle))
+    }
+}
+
+fn java_processes() -> Vec<JavaProcess> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_str().is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit())))
+        .filter_map(|e| {
+            let cmdline = fs::read(e.path().join("cmdline")).ok()?;
+            let args: Vec<String> = cmdline
+                .split(|&b| b == 0)
+                .filter(|a| !a.is_empty())
+                .map(|a| String::from_utf8_lossy(a).into_owned())
+                .collect();
+            let program = Path::new(args.first()?).file_name()?.to_str()?;
+            if !program.starts_with("java") {
+                return None;
+            }
+            let cwd = fs::read_link(e.path().join("cwd")).ok();
+            let memory = fs::read_to_string(e.path().join("status")).ok().and_then(|s| peak_resident_memory(&s));
+            Some(JavaProcess { args, cwd, memory })
+        })
+        .collect()
+}
+
+/// The `VmHWM` line of `/proc/<pid>/status` (the most memory the process has
+/// had resident), in bytes, so spikes between polls still count.
+fn peak_resident_memory(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(launched_at: Instant) -> Session {
+        Session {
+            instance: Instance::default(),
+            started: SystemTime::now(),
+            launched_at,
+            seen_game: false,
+            peak_memory: None,
+        }
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let start = Instant::now();
+        let mut s = session(start);
+        assert_eq!(s.update(false, start + Duration::from_secs(30)), SessionState::Starting);
+        assert_eq!(s.update(true, start + Duration::from_secs(60)), SessionState::Running);
+        // Once seen, the game going away ends the session regardless of the timeout
+        assert_eq!(s.update(false, start + Duration::from_secs(90)), SessionState::Exited);
+
+        let mut never = session(start);
+        assert_eq!(never.update(false, start + STARTUP_TIMEOUT + Duration::from_secs(1)), SessionState::NeverStarted);
+    }
+
+    #[test]
+    fn test_java_process_matches_instance() {
+        let instance = Path::new("/home/me/PrismLauncher/instances/Create");
+        let by_cwd = JavaProcess {
+            args: vec!["java".into()],
+            cwd: Some(instance.join(".minecraft")),
+            memory: None,
+        };
+        assert!(by_cwd.belongs_to(instance));
+
+        let by_arg = JavaProcess {
+            args: vec!["java".into(), "-Djava.library.path=/home/me/PrismLauncher/instances/Create/natives".into()],
+            cwd: None,
+            memory: None,
+        };
+        assert!(by_arg.belongs_to(instance));
+        assert!(!by_arg.belongs_to(Path::new("/home/me/PrismLauncher/instances/Other")));
+        assert!(!by_arg.belongs_to(Path::new("/home/me/PrismLauncher/instances/Crea")));
+    }
+
+    #[test]
+    fn test_peak_resident_memory() {
+        let status = "Name:\tjava\nVmPeak:\t 9000000 kB\nVmHWM:\t 2048000 kB\nVmRSS:\t 1024000 kB\nThreads:\t60\n";
+        assert_eq!(peak_resident_memory(status), Some(2048000 * 1024));
+        assert_eq!(peak_resident_memory("Name:\tjava\n"), None);
+    }
+}
@@ -0,0 +1,542 @@
+//! A Source RCON client for servers we host, the console screen built on it,
+//! and `minecraft-tui rcon` for running commands from scripts.
+//!
+//! Packets are `length, id, type, body\0\0` with little-endian `i32`s. Long
+//! replies arrive split over several packets, so every command is followed
+//! by an empty packet of an unused type: the server answers it with the
+//! same id once the real reply is complete.
+
+use crate::config::{data_dir, Config, RconServer};
+use crate::ping;
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use serde_json::Value;
+use std::{
+    fs,
+    io::{self, BufRead, Read, Write},
+    net::TcpStream,
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+pub const DEFAULT_PORT: u16 = 25575;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Commands like `save-all flush` can take a while on a big world.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+/// The longest command body the vanilla server accepts.
+const MAX_COMMAND_LEN: usize = 1446;
+const HISTORY_LEN: usize = 200;
+
+const TYPE_RESPONSE: i32 = 0;
+const TYPE_COMMAND: i32 = 2;
+const TYPE_LOGIN: i32 = 3;
+
+pub struct Rcon {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl Rcon {
+    /// Connects and logs in. A wrong password is reported as such.
+    pub fn connect(address: &str, password: &str) -> Result<Self> {
+        let (host, port) = ping::parse_address(address, DEFAULT_PORT)?;
+        let stream = ping::connect(&host, port, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(COMMAND_TIMEOUT))?;
+        let mut rcon = Rcon { stream, next_id: 1 };
+
+        let id = rcon.send(TYPE_LOGIN, password)?;
+        loop {
+            let (reply_id, kind, _) = rcon.receive().context("logging in")?;
+            // Source servers send an empty response before the login result
+            if kind == TYPE_RESPONSE && reply_id == id {
+                continue;
+            }
+            if reply_id == -1 {
+                bail!("RCON password was rejected by {}", address);
+            }
+            if reply_id == id {
+                return Ok(rcon);
+            }
+        }
+    }
+
+    /// Runs `command` and returns the server's reply.
+    pub fn command(&mut self, command: &str) -> Result<String> {
+        if command.len() > MAX_COMMAND_LEN {
+            bail!("Command is longer than {} bytes", MAX_COMMAND_LEN);
+        }
+        let id = self.send(TYPE_COMMAND, command)?;
+        let marker = self.send(TYPE_RESPONSE, "")?;
+        let mut reply = String::new();
+        loop {
+            let (reply_id, _, body) = self.receive()?;
+            if reply_id == marker {
+                return Ok(reply);
+            }
+            if reply_id == id {
+                reply.push_str(&body);
+            }
+        }
+    }
+
+    fn send(&mut self, kind: i32, body: &str) -> Result<i32> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+        let mut packet = Vec::with_capacity(body.len() + 14);
+        packet.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&kind.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        self.stream.write_all(&packet)?;
+        Ok(id)
+    }
+
+    fn receive(&mut self) -> Result<(i32, i32, String)> {
+        let mut header = [0; 12];
+        self.stream.read_exact(&mut header)?;
+        let int = |i: usize| i32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let (length, id, kind) = (int(0), int(4), int(8));
+        if !(10..=1 << 20).contains(&length) {
+            bail!("bad RCON packet length {}", length);
+        }
+        let mut body = vec![0; length as usize - 8];
+        self.stream.read_exact(&mut body)?;
+        body.truncate(body.len() - 2);
+        Ok((id, kind, String::from_utf8_lossy(&body).into_owned()))
+    }
+}
+
+/// Removes `§` formatting codes, for printing replies to a plain terminal.
+fn plain_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// `minecraft-tui rcon <server> [command…]`, where `<server>` is the game
+/// address the credentials were saved under, written any way the server list
+/// accepts. Without commands, one command is read per line from stdin; blank
+/// lines and `#` comments are skipped.
+pub fn run_cli(args: &[String]) -> Result<()> {
+    let Some(name) = args.first() else {
+        bail!("Usage: minecraft-tui rcon <server> [command…]");
+    };
+    let config = Config::load();
+    let key = ping::normalize_address(name).unwrap_or_else(|_| name.clone());
+    let Some(server) = config.rcon.get(name).or_else(|| config.rcon.get(&key)) else {
+        let known: Vec<&str> = config.rcon.keys().map(String::as_str).collect();
+        if known.is_empty() {
+            bail!("No RCON server named {}; add it under \"rcon\" in {}", name, Config::path().display());
+        }
+        bail!("No RCON server named {} (known: {})", name, known.join(", "));
+    };
+    let commands: Vec<String> = if args.len() > 1 {
+        args[1..].to_vec()
+    } else {
+        io::stdin()
+            .lock()
+            .lines()
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect()
+    };
+
+    let mut rcon = Rcon::connect(&server.address, &server.password)?;
+    for command in commands {
+        let command = command.strip_prefix('/').unwrap_or(&command);
+        let reply = rcon.command(command).with_context(|| format!("running {}", command))?;
+        if !reply.is_empty() {
+            println!("{}", plain_text(&reply));
+        }
+    }
+    Ok(())
+}
+
+/// Asks for the RCON address and password of a server with none saved.
+pub struct CredentialsForm {
+    /// The name the credentials are saved under.
+    pub name: String,
+    pub address: String,
+    pub password: String,
+    pub on_password: bool,
+}
+
+impl CredentialsForm {
+    pub fn new(name: &str, host: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            address: format!("{}:{}", host, DEFAULT_PORT),
+            password: String::new(),
+            on_password: true,
+        }
+    }
+
+    pub fn toggle_field(&mut self) {
+        self.on_password = !self.on_password;
+    }
+
+    pub fn input(&mut self, c: char) {
+        if self.on_password {
+            self.password.push(c);
+        } else {
+            self.address.push(c);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.on_password {
+            self.password.pop();
+        } else {
+            self.address.pop();
+        }
+    }
+
+    pub fn server(&self) -> RconServer {
+        RconServer {
+            address: self.address.trim().to_string(),
+            password: self.password.clone(),
+        }
+    }
+}
+
+pub enum Entry {
+    Command(String),
+    Output(String),
+    Error(String),
+    Info(String),
+}
+
+/// Console lines from the connection thread; the flag marks the reply to a command.
+type Event = (Entry, bool);
+
+pub struct Console {
+    pub name: String,
+    pub address: String,
+    pub entries: Vec<Entry>,
+    pub input: String,
+    history: Vec<String>,
+    history_pos: Option<usize>,
+    /// Lines scrolled back from the bottom.
+    pub scroll: usize,
+    /// Commands sent but not answered yet.
+    pub pending: usize,
+    commands: mpsc::Sender<String>,
+    events: mpsc::Receiver<Event>,
+}
+
+impl Console {
+    /// Opens a console; the connection is made on a background thread that
+    /// lives as long as the console and reconnects after errors.
+    pub fn open(name: &str, server: &RconServer) -> Self {
+        let (commands, inbox) = mpsc::channel::<String>();
+        let (outbox, events) = mpsc::channel();
+        let (address, password) = (server.address.clone(), server.password.clone());
+        thread::spawn(move || {
+            let connect = || Rcon::connect(&address, &password);
+            let mut rcon = match connect() {
+                Ok(rcon) => {
+                    let _ = outbox.send((Entry::Info(format!("Connected to {}", address)), false));
+                    Some(rcon)
+                }
+                Err(e) => {
+                    let _ = outbox.send((Entry::Error(format!("{:#}", e)), false));
+                    None
+                }
+            };
+            for command in inbox {
+                let result = match rcon {
+                    Some(ref mut rcon) => rcon.command(&command),
+                    None => connect().and_then(|mut fresh| {
+                        let reply = fresh.command(&command);
+                        rcon = Some(fresh);
+                        reply
+                    }),
+                };
+                let entry = match result {
+                    Ok(reply) => Entry::Output(reply),
+                    Err(e) => {
+                        rcon = None;
+                        Entry::Error(format!("{:#}", e))
+                    }
+                };
+                let _ = outbox.send((entry, true));
+            }
+        });
+        Self {
+            name: name.to_string(),
+            address: server.address.clone(),
+            entries: Vec::new(),
+            input: String::new(),
+            history: load_history(name),
+            history_pos: None,
+            scroll: 0,
+            pending: 0,
+            commands,
+            events,
+        }
+    }
+
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((entry, reply)) = self.events.try_recv() {
+            if reply {
+                self.pending = self.pending.saturating_sub(1);
+            }
+            // Commands like `say` have no reply worth a blank line
+            if !matches!(entry, Entry::Output(ref text) if text.is_empty()) {
+                self.entries.push(entry);
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn submit(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        let command = input.trim();
+        let command = command.strip_prefix('/').unwrap_or(command).to_string();
+        self.history_pos = None;
+        self.scroll = 0;
+        if command.is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&command) {
+            self.history.push(command.clone());
+            if self.history.len() > HISTORY_LEN {
+                self.history.remove(0);
+            }
+            save_history(&self.name, &self.history);
+        }
+        self.entries.push(Entry::Command(command.clone()));
+        if self.commands.send(command).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    /// Steps through earlier commands, like a shell's ↑.
+    pub fn history_previous(&mut self) {
+        let pos = match self.history_pos {
+            Some(0) => 0,
+            Some(pos) => pos - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.history_pos = Some(pos);
+        self.input = self.history[pos].clone();
+    }
+
+    pub fn history_next(&mut self) {
+        let Some(pos) = self.history_pos else {
+            return;
+        };
+        if pos + 1 < self.history.len() {
+            self.history_pos = Some(pos + 1);
+            self.input = self.history[pos + 1].clone();
+        } else {
+            self.history_pos = None;
+            self.input.clear();
+        }
+    }
+}
+
+fn history_path(name: &str) -> PathBuf {
+    data_dir().join("rcon-history").join(format!("{}.txt", name.replace('/', "_")))
+}
+
+fn load_history(name: &str) -> Vec<String> {
+    fs::read_to_string(history_path(name))
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn save_history(name: &str, history: &[String]) {
+    let path = history_path(name);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(path, history.join("\n") + "\n");
+}
+
+pub fn render(f: &mut Frame, area: Rect, console: &mut Console) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for entry in &console.entries {
+        match entry {
+            Entry::Command(command) => lines.push(Line::from(Span::styled(
+                format!("> {}", command),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ))),
+            Entry::Output(text) => {
+                lines.extend(ping::styled_lines(&Value::String(text.clone()), Style::default().fg(Color::Gray)))
+            }
+            Entry::Error(text) => lines.push(Line::from(Span::styled(text.clone(), Style::default().fg(Color::Red)))),
+            Entry::Info(text) => {
+                lines.push(Line::from(Span::styled(text.clone(), Style::default().fg(Color::DarkGray))))
+            }
+        }
+    }
+    let height = chunks[0].height.saturating_sub(2) as usize;
+    console.scroll = console.scroll.min(lines.len().saturating_sub(height));
+    let top = lines.len().saturating_sub(height + console.scroll);
+    let mut title = format!(" RCON — {} ({}) ", console.name, console.address);
+    if console.scroll > 0 {
+        title.push_str(&format!("↑{} ", console.scroll));
+    }
+    let output = Paragraph::new(lines).scroll((top as u16, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(title),
+    );
+    f.render_widget(output, chunks[0]);
+
+    let title = if console.pending > 0 {
+        format!(" Command ({} running) ", console.pending)
+    } else {
+        " Command ".to_string()
+    };
+    let input = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Yellow)),
+        Span::styled(format!("{}█", console.input), Style::default().fg(Color::White)),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(title),
+    );
+    f.render_widget(input, chunks[1]);
+}
+
+pub fn render_credentials(f: &mut Frame, area: Rect, form: &CredentialsForm) {
+    let popup = crate::centered_rect(60, 7, area);
+    let field = |label: &str, value: String, focused: bool| {
+        let style = if focused {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        Line::from(vec![
+            Span::styled(format!("{:<10}", label), Style::default().fg(Color::Cyan)),
+            Span::styled(value, style),
+            Span::styled(if focused { "█" } else { "" }, style),
+        ])
+    };
+    let lines = vec![
+        field("Address:", form.address.clone(), !form.on_password),
+        field("Password:", "•".repeat(form.password.chars().count()), form.on_password),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Tab field • Enter save and connect • Esc cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    let dialog = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(format!(" RCON for {} ", form.name)),
+    );
+    f.render_widget(Clear, popup);
+    f.render_widget(dialog, popup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn read_packet(stream: &mut TcpStream) -> (i32, i32, String) {
+        let mut header = [0; 12];
+        stream.read_exact(&mut header).unwrap();
+        let int = |i: usize| i32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+        let mut body = vec![0; int(0) as usize - 8];
+        stream.read_exact(&mut body).unwrap();
+        body.truncate(body.len() - 2);
+        (int(4), int(8), String::from_utf8(body).unwrap())
+    }
+
+    fn write_packet(stream: &mut TcpStream, id: i32, kind: i32, body: &str) {
+        let mut packet = (body.len() as i32 + 10).to_le_bytes().to_vec();
+        packet.extend(id.to_le_bytes());
+        packet.extend(kind.to_le_bytes());
+        packet.extend(body.as_bytes());
+        packet.extend([0, 0]);
+        stream.write_all(&packet).unwrap();
+    }
+
+    /// A vanilla-like server: long replies are split into 4096-byte packets
+    /// and unknown packet types get an "Unknown request" reply.
+    fn stub_server(password: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let (id, kind, body) = read_packet(&mut stream);
+            assert_eq!(kind, TYPE_LOGIN);
+            if body != password {
+                write_packet(&mut stream, -1, TYPE_COMMAND, "");
+                return;
+            }
+            write_packet(&mut stream, id, TYPE_COMMAND, "");
+            loop {
+                let (id, kind, body) = read_packet(&mut stream);
+                if kind != TYPE_COMMAND {
+                    write_packet(&mut stream, id, TYPE_RESPONSE, &format!("Unknown request {:x}", kind));
+                    continue;
+                }
+                let reply = match body.as_str() {
+                    "list" => "There are 1 of a max of 20 players online: §eAlex".to_string(),
+                    "help" => "x".repeat(5000),
+                    _ => String::new(),
+                };
+                for chunk in reply.as_bytes().chunks(4096) {
+                    write_packet(&mut stream, id, TYPE_RESPONSE, std::str::from_utf8(chunk).unwrap());
+                }
+                if reply.is_empty() {
+                    write_packet(&mut stream, id, TYPE_RESPONSE, "");
+                }
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_commands_and_split_replies() {
+        let port = stub_server("secret");
+        let mut rcon = Rcon::connect(&format!("127.0.0.1:{}", port), "secret").unwrap();
+        let reply = rcon.command("list").unwrap();
+        assert_eq!(plain_text(&reply), "There are 1 of a max of 20 players online: Alex");
+        assert_eq!(rcon.command("help").unwrap().len(), 5000);
+        assert_eq!(rcon.command("save-all").unwrap(), "");
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let port = stub_server("secret");
+        let err = Rcon::connect(&format!("127.0.0.1:{}", port), "guess").err().unwrap();
+        assert!(err.to_string().contains("rejected"));
+    }
+}
@@ -0,0 +1,470 @@
+//! Reader for Anvil region files (`region/r.<x>.<z>.mca`), reduced to what an
+//! overhead map needs: the topmost block of every column.
+//!
+//! A region holds 32×32 chunks. Chunks are NBT, individually compressed, and
+//! come in three layouts: 1.18+ (`sections[].block_states`), 1.13–1.17
+//! (`Level.Sections[].Palette` + `BlockStates`) and pre-flattening
+//! (`Level.Sections[].Blocks` with numeric ids).
+
+use crate::nbt::{self, Tag};
+use anyhow::{bail, Context, Result};
+use std::{fs, path::Path};
+
+const SECTOR: usize = 4096;
+
+/// 20w17a stopped packed values from spanning two longs.
+const NON_SPANNING_DATA_VERSION: i64 = 2529;
+
+/// One column's surface: the top block and how deep the water above the ground is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Column {
+    /// Index into the chunk's palette.
+    pub block: u16,
+    pub y: i32,
+    pub water_depth: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChunkSurface {
+    pub palette: Vec<String>,
+    /// 256 columns, indexed `z * 16 + x`. `None` where the column is empty.
+    pub columns: Vec<Option<Column>>,
+}
+
+/// The surfaces of the chunks in a region, indexed `z * 32 + x`.
+pub struct RegionSurface {
+    pub chunks: Vec<Option<ChunkSurface>>,
+}
+
+pub fn region_file(region_dir: &Path, rx: i32, rz: i32) -> std::path::PathBuf {
+    region_dir.join(format!("r.{}.{}.mca", rx, rz))
+}
+
+/// Coordinates of every region file in a folder.
+pub fn list_regions(region_dir: &Path) -> Vec<(i32, i32)> {
+    let Ok(entries) = fs::read_dir(region_dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.metadata().is_ok_and(|m| m.len() >= 2 * SECTOR as u64))
+        .filter_map(|e| {
+            let name = e.file_name();
+            let mut parts = name.to_str()?.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+            let x = parts.next()?.parse().ok()?;
+            let z = parts.next()?.parse().ok()?;
+            Some((x, z))
+        })
+        .collect()
+}
+
+/// Reads a region and finds the surface of each chunk. Chunks that are missing,
+/// not fully generated or unreadable are left as `None`.
+pub fn load_surface(region_dir: &Path, rx: i32, rz: i32) -> Result<RegionSurface> {
+    let path = region_file(region_dir, rx, rz);
+    let data = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+    if data.len() < 2 * SECTOR {
+        bail!("{} is too short to be a region file", path.display());
+    }
+
+    let chunks = (0..1024)
+        .map(|index| {
+            let chunk_x = rx * 32 + (index % 32) as i32;
+            let chunk_z = rz * 32 + (index / 32) as i32;
+            read_chunk(&data, index, region_dir, chunk_x, chunk_z)
+                .ok()
+                .flatten()
+                .and_then(|chunk| surface(&chunk))
+        })
+        .collect();
+    Ok(RegionSurface { chunks })
+}
+
+fn read_chunk(data: &[u8], index: usize, region_dir: &Path, chunk_x: i32, chunk_z: i32) -> Result<Option<Tag>> {
+    let entry = &data[index * 4..index * 4 + 4];
+    let offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize * SECTOR;
+    if offset == 0 || entry[3] == 0 {
+        return Ok(None);
+    }
+    let Some(header) = data.get(offset..offset + 5) else {
+        bail!("chunk {} points past the end of the file", index);
+    };
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if length == 0 {
+        bail!("chunk {} has an empty length header", index);
+    }
+    let compression = header[4];
+
+    // Oversized chunks live in their own c.<x>.<z>.mcc file
+    let external;
+    let payload = if compression & 0x80 != 0 {
+        external = fs::read(region_dir.join(format!("c.{}.{}.mcc", chunk_x, chunk_z)))?;
+        &external[..]
+    } else {
+        data.get(offset + 5..offset + 4 + length).context("chunk length past end of file")?
+    };
+
+    match compression & 0x7f {
+        // gzip, zlib and uncompressed are told apart by their magic bytes
+        1..=3 => Ok(Some(nbt::from_bytes(payload)?)),
+        4 => bail!("LZ4-compressed chunks are not supported"),
+        other => bail!("unknown chunk compression {}", other),
+    }
+}
+
+fn is_air(name: &str) -> bool {
+    matches!(name, "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air")
+}
+
+fn is_water(name: &str) -> bool {
+    matches!(
+        name,
+        "minecraft:water"
+            | "minecraft:seagrass"
+            | "minecraft:tall_seagrass"
+            | "minecraft:kelp"
+            | "minecraft:kelp_plant"
+            | "minecraft:bubble_column"
+    )
+}
+
+/// Block storage for one 16×16×16 section.
+enum Section<'a> {
+    Palette {
+        names: Vec<String>,
+        data: &'a [i64],
+        bits: usize,
+        spanning: bool,
+    },
+    Legacy(&'a [i8]),
+}
+
+impl Section<'_> {
+    fn block(&self, index: usize) -> Option<&str> {
+        match self {
+            Section::Palette { names, data, bits, spanning } => {
+                if names.len() == 1 || *bits == 0 {
+                    return names.first().map(String::as_str);
+                }
+                let i = unpack(data, *bits, index, *spanning)? as usize;
+                names.get(i).map(String::as_str)
+            }
+            Section::Legacy(blocks) => blocks.get(index).map(|&id| legacy_name(id as u8)),
+        }
+    }
+}
+
+fn parse_section(section: &Tag, spanning: bool) -> Option<(i64, Section<'_>)> {
+    let y = section.get("Y").and_then(Tag::as_i64)?;
+
+    // 1.18+ nests the palette and data under block_states
+    let (palette, data) = match section.get("block_states") {
+        Some(states) => (states.get("palette"), states.get("data")),
+        None => (section.get("Palette"), section.get("BlockStates")),
+    };
+    if let Some(palette) = palette.and_then(Tag::as_list) {
+        let names: Vec<String> = palette
+            .iter()
+            .map(|entry| entry.get("Name").and_then(Tag::as_str).unwrap_or("minecraft:air").to_string())
+            .collect();
+        let data: &[i64] = match data {
+            Some(Tag::LongArray(longs)) => longs,
+            _ => &[],
+        };
+        let bits = if data.is_empty() { 0 } else { infer_bits(data.len(), 4096, spanning)? };
+        return Some((y, Section::Palette { names, data, bits, spanning }));
+    }
+
+    match section.get("Blocks") {
+        Some(Tag::ByteArray(blocks)) => Some((y, Section::Legacy(blocks))),
+        _ => None,
+    }
+}
+
+/// Works out how many bits per value a packed long array uses from its length.
+fn infer_bits(longs: usize, count: usize, spanning: bool) -> Option<usize> {
+    (1..=32).find(|&bits| {
+        let expected = if spanning {
+            (count * bits).div_ceil(64)
+        } else {
+            count.div_ceil(64 / bits)
+        };
+        expected == longs
+    })
+}
+
+/// Reads the `index`th value from a packed long array.
+fn unpack(data: &[i64], bits: usize, index: usize, spanning: bool) -> Option<u64> {
+    let mask = (1u64 << bits) - 1;
+    if spanning {
+        let bit = index * bits;
+        let (word, offset) = (bit / 64, bit % 64);
+        let mut value = *data.get(word)? as u64 >> offset;
+        if offset + bits > 64 {
+            value |= (*data.get(word + 1)? as u64) << (64 - offset);
+        }
+        Some(value & mask)
+    } else {
+        let per_long = 64 / bits;
+        let word = *data.get(index / per_long)? as u64;
+        Some((word >> ((index % per_long) * bits)) & mask)
+    }
+}
+
+fn heightmap(level: &Tag, name: &str, spanning: bool) -> Option<Vec<i32>> {
+    match level.path(&["Heightmaps", name])? {
+        Tag::LongArray(data) => {
+            let bits = infer_bits(data.len(), 256, spanning)?;
+            (0..256).map(|i| unpack(data, bits, i, spanning).map(|v| v as i32)).collect()
+        }
+        _ => None,
+    }
+}
+
+/// Finds the top block of every column in a chunk.
+fn surface(chunk: &Tag) -> Option<ChunkSurface> {
+    let data_version = chunk.get("DataVersion").and_then(Tag::as_i64).unwrap_or(0);
+    let level = chunk.get("Level").unwrap_or(chunk);
+    let spanning = data_version < NON_SPANNING_DATA_VERSION;
+
+    if let Some(status) = level.get("Status").and_then(Tag::as_str) {
+        let status = status.trim_start_matches("minecraft:");
+        if !matches!(status, "full" | "fullchunk" | "postprocessed") {
+            return None;
+        }
+    }
+
+    let sections: Vec<(i64, Section)> = level
+        .get("sections")
+        .or(level.get("Sections"))
+        .and_then(Tag::as_list)?
+        .iter()
+        .filter_map(|s| parse_section(s, spanning))
+        .collect();
+    let block_at = |x: usize, y: i32, z: usize| -> Option<&str> {
+        let section_y = y.div_euclid(16) as i64;
+        let (_, section) = sections.iter().find(|(sy, _)| *sy == section_y)?;
+        section.block(y.rem_euclid(16) as usize * 256 + z * 16 + x)
+    };
+    let top = sections.iter().map(|(y, _)| *y).max()? as i32 * 16 + 15;
+    let bottom = sections.iter().map(|(y, _)| *y).min()? as i32 * 16;
+
+    // Heightmaps count from the world's minimum height; 1.18+ records it as yPos
+    let min_y = level.get("yPos").and_then(Tag::as_i64).map_or(0, |y| (y as i32).saturating_mul(16));
+    let surface_map = heightmap(level, "WORLD_SURFACE", spanning).or_else(|| match level.get("HeightMap") {
+        Some(Tag::IntArray(heights)) if heights.len() == 256 => Some(heights.clone()),
+        _ => None,
+    });
+
+    let mut palette: Vec<String> = Vec::new();
+    let mut columns = Vec::with_capacity(256);
+    for i in 0..256 {
+        let (x, z) = (i % 16, i / 16);
+        // A corrupt height that overflows leaves the column blank
+        let start = match surface_map {
+            Some(ref h) => match min_y.checked_add(h[i]).and_then(|y| y.checked_sub(1)) {
+                Some(y) => y.min(top),
+                None => {
+                    columns.push(None);
+                    continue;
+                }
+            },
+            None => top,
+        };
+
+        // Heightmaps can be stale, so walk down past any air they point at
+        let mut y = start;
+        let mut found = None;
+        while y >= bottom {
+            match block_at(x, y, z) {
+                Some(name) if !is_air(name) => {
+                    found = Some(name);
+                    break;
+                }
+                _ => y -= 1,
+            }
+        }
+        let Some(name) = found else {
+            columns.push(None);
+            continue;
+        };
+
+        let mut water_depth = 0;
+        if is_water(name) {
+            let mut below = y - 1;
+            while below >= bottom && block_at(x, below, z).is_some_and(is_water) && water_depth < 64 {
+                water_depth += 1;
+                below -= 1;
+            }
+            water_depth += 1;
+        }
+
+        let block = match palette.iter().position(|p| p == name) {
+            Some(index) => index,
+            None => {
+                palette.push(name.to_string());
+                palette.len() - 1
+            }
+        } as u16;
+        columns.push(Some(Column { block, y, water_depth }));
+    }
+    Some(ChunkSurface { palette, columns })
+}
+
+/// Names for the pre-1.13 numeric block ids that commonly show up on the surface.
+fn legacy_name(id: u8) -> &'static str {
+    match id {
+        0 => "minecraft:air",
+        1 => "minecraft:stone",
+        2 => "minecraft:grass_block",
+        3 => "minecraft:dirt",
+        4 => "minecraft:cobblestone",
+        5 => "minecraft:oak_planks",
+        7 => "minecraft:bedrock",
+        8 | 9 => "minecraft:water",
+        10 | 11 => "minecraft:lava",
+        12 => "minecraft:sand",
+        13 => "minecraft:gravel",
+        17 => "minecraft:oak_log",
+        18 => "minecraft:oak_leaves",
+        20 => "minecraft:glass",
+        24 => "minecraft:sandstone",
+        31 => "minecraft:short_grass",
+        35 => "minecraft:white_wool",
+        37 | 38 => "minecraft:poppy",
+        48 => "minecraft:mossy_cobblestone",
+        49 => "minecraft:obsidian",
+        78 => "minecraft:snow",
+        79 => "minecraft:ice",
+        80 => "minecraft:snow_block",
+        81 => "minecraft:cactus",
+        82 => "minecraft:clay",
+        83 => "minecraft:sugar_cane",
+        87 => "minecraft:netherrack",
+        88 => "minecraft:soul_sand",
+        110 => "minecraft:mycelium",
+        121 => "minecraft:end_stone",
+        159 => "minecraft:terracotta",
+        161 => "minecraft:acacia_leaves",
+        162 => "minecraft:acacia_log",
+        172 => "minecraft:terracotta",
+        174 => "minecraft:packed_ice",
+        _ => "minecraft:stone",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpack_both_layouts() {
+        // Non-spanning, 5 bits: 12 values per long, the top 4 bits unused
+        let value = (3i64 << 5) | 7 | (1i64 << 60);
+        assert_eq!(unpack(&[value], 5, 0, false), Some(7));
+        assert_eq!(unpack(&[value], 5, 1, false), Some(3));
+
+        // Spanning, 5 bits: the 13th value straddles the two longs
+        let low = 0b1011i64 << 60;
+        let high = 0b1i64;
+        assert_eq!(unpack(&[low, high], 5, 12, true), Some(0b11011));
+    }
+
+    #[test]
+    fn test_infer_bits() {
+        assert_eq!(infer_bits(256, 4096, false), Some(4));
+        assert_eq!(infer_bits(342, 4096, false), Some(5));
+        assert_eq!(infer_bits(37, 256, false), Some(9));
+        assert_eq!(infer_bits(36, 256, true), Some(9));
+    }
+
+    fn section(y: i8, palette: &[&str], data: Vec<i64>) -> Tag {
+        let palette = palette
+            .iter()
+            .map(|name| Tag::Compound(vec![("Name".into(), Tag::String(name.to_string()))]))
+            .collect();
+        let mut states = vec![("palette".into(), Tag::List(10, palette))];
+        if !data.is_empty() {
+            states.push(("data".into(), Tag::LongArray(data)));
+        }
+        Tag::Compound(vec![
+            ("Y".into(), Tag::Byte(y)),
+            ("block_states".into(), Tag::Compound(states)),
+        ])
+    }
+
+    #[test]
+    fn test_modern_chunk_surface() {
+        // Section 0 is stone up to y=2 with one water block at (0, 3, 0) and air
+        // above; section -1 is all stone
+        let mut data = vec![0i64; 256];
+        for y in 0..3 {
+            for z in 0..16 {
+                // 4 bits per block, 16 blocks per long: one long is one row along x
+                data[y * 16 + z] = 0x1111_1111_1111_1111;
+            }
+        }
+        data[3 * 16] = 2;
+        let chunk = Tag::Compound(vec![
+            ("DataVersion".into(), Tag::Int(3700)),
+            ("Status".into(), Tag::String("minecraft:full".into())),
+            ("yPos".into(), Tag::Int(-1)),
+            (
+                "sections".into(),
+                Tag::List(
+                    10,
+                    vec![
+                        section(-1, &["minecraft:stone"], vec![]),
+                        section(0, &["minecraft:air", "minecraft:stone", "minecraft:water"], data),
+                    ],
+                ),
+            ),
+        ]);
+
+        let surface = surface(&chunk).unwrap();
+        let water = surface.columns[0].unwrap();
+        assert_eq!(surface.palette[water.block as usize], "minecraft:water");
+        assert_eq!((water.y, water.water_depth), (3, 1));
+        let stone = surface.columns[1].unwrap();
+        assert_eq!(surface.palette[stone.block as usize], "minecraft:stone");
+        assert_eq!(stone.y, 2);
+        assert_eq!(surface.columns[255].unwrap().y, 2);
+    }
+
+    #[test]
+    fn test_corrupt_heights_leave_columns_blank() {
+        let mut heights = vec![20; 256];
+        heights[0] = i32::MIN;
+        let chunk = Tag::Compound(vec![
+            ("DataVersion".into(), Tag::Int(3700)),
+            ("yPos".into(), Tag::Int(-1)),
+            ("HeightMap".into(), Tag::IntArray(heights)),
+            ("sections".into(), Tag::List(10, vec![section(0, &["minecraft:stone"], vec![])])),
+        ]);
+        let surface = surface(&chunk).unwrap();
+        assert!(surface.columns[0].is_none());
+        assert_eq!(surface.columns[1].unwrap().y, 3);
+    }
+
+    #[test]
+    fn test_unfinished_chunks_are_skipped() {
+        let chunk = Tag::Compound(vec![
+            ("Status".into(), Tag::String("minecraft:features".into())),
+            ("sections".into(), Tag::List(10, vec![section(0, &["minecraft:stone"], vec![])])),
+        ]);
+        assert!(surface(&chunk).is_none());
+    }
+
+    #[test]
+    fn test_damaged_chunk_headers_are_errors() {
+        // One chunk at sector 2; the location table and timestamps fill sectors 0-1
+        let mut data = vec![0u8; 3 * SECTOR];
+        data[..4].copy_from_slice(&[0, 0, 2, 1]);
+        let dir = Path::new("/nonexistent");
+        assert!(read_chunk(&data, 0, dir, 0, 0).is_err());
+
+        // A length running past the end of the file
+        data[2 * SECTOR..2 * SECTOR + 5].copy_from_slice(&[0, 0, 0x20, 0, 2]);
+        assert!(read_chunk(&data, 0, dir, 0, 0).is_err());
+    }
+}
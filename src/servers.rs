@@ -0,0 +1,857 @@
+//! The multiplayer server list in an instance's `servers.dat`, and the servers screen.
+//!
+//! `servers.dat` is uncompressed NBT: `{servers: [{name, ip, icon, acceptTextures}, …]}`.
+//! Entries are rewritten from their original compounds, so fields this screen
+//! doesn't know about survive an edit.
+//!
+//! Each server is pinged in the background while the screen is open, and again
+//! every [`REFRESH_INTERVAL`]. Worlds opened to LAN are listed underneath.
+
+use crate::icon::{GraphicsProtocol, Icon, ICON_COLS, ICON_ROWS};
+use crate::lan::{LanGame, LanListener};
+use crate::nbt::{Compression, Document, Tag};
+use crate::ping::{self, ServerStatus};
+use crate::rcon::{self, CredentialsForm};
+use crate::{centered_rect, detail_line, game_dir, step_selection, Instance};
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct Server {
+    pub name: String,
+    pub ip: String,
+    /// Server resource packs: `None` prompts, otherwise always accept or decline.
+    pub accept_textures: Option<bool>,
+    pub icon: Option<Arc<Icon>>,
+    /// Hidden entries come from Direct Connect and are not shown in the game's list.
+    pub hidden: bool,
+    /// The compound as read, written back with the known fields updated.
+    tag: Vec<(String, Tag)>,
+}
+
+impl Server {
+    fn from_tag(tag: &Tag) -> Option<Self> {
+        let Tag::Compound(entries) = tag else {
+            return None;
+        };
+        let string = |key: &str| tag.get(key).and_then(Tag::as_str).unwrap_or_default().to_string();
+        Some(Self {
+            name: string("name"),
+            ip: string("ip"),
+            accept_textures: tag.get("acceptTextures").and_then(Tag::as_i64).map(|v| v != 0),
+            icon: tag.get("icon").and_then(Tag::as_str).and_then(Icon::from_base64).map(Arc::new),
+            hidden: tag.get("hidden").and_then(Tag::as_i64) == Some(1),
+            tag: entries.clone(),
+        })
+    }
+
+    fn to_tag(&self) -> Tag {
+        let mut entries = self.tag.clone();
+        let mut set = |key: &str, value: Option<Tag>| {
+            let existing = entries.iter().position(|(k, _)| k == key);
+            match (existing, value) {
+                (Some(i), Some(value)) => entries[i].1 = value,
+                (None, Some(value)) => entries.push((key.to_string(), value)),
+                (Some(i), None) => {
+                    entries.remove(i);
+                }
+                (None, None) => {}
+            }
+        };
+        set("name", Some(Tag::String(self.name.clone())));
+        set("ip", Some(Tag::String(self.ip.clone())));
+        set("acceptTextures", self.accept_textures.map(|accept| Tag::Byte(accept as i8)));
+        Tag::Compound(entries)
+    }
+
+    pub fn resource_packs(&self) -> &'static str {
+        match self.accept_textures {
+            None => "Prompt",
+            Some(true) => "Enabled",
+            Some(false) => "Disabled",
+        }
+    }
+}
+
+pub fn load_servers(path: &Path) -> Result<Vec<Server>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let document = Document::load(path)?;
+    Ok(document
+        .root
+        .get("servers")
+        .and_then(Tag::as_list)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(Server::from_tag)
+        .collect())
+}
+
+/// Which field of the add/edit form has focus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormField {
+    Name,
+    Address,
+    ResourcePacks,
+}
+
+pub struct ServerForm {
+    /// The entry being edited, or `None` when adding.
+    pub index: Option<usize>,
+    pub name: String,
+    pub ip: String,
+    pub accept_textures: Option<bool>,
+    pub field: FormField,
+}
+
+impl ServerForm {
+    pub fn next_field(&mut self) {
+        self.field = match self.field {
+            FormField::Name => FormField::Address,
+            FormField::Address => FormField::ResourcePacks,
+            FormField::ResourcePacks => FormField::Name,
+        };
+    }
+
+    pub fn previous_field(&mut self) {
+        self.field = match self.field {
+            FormField::Name => FormField::ResourcePacks,
+            FormField::Address => FormField::Name,
+            FormField::ResourcePacks => FormField::Address,
+        };
+    }
+
+    /// Types into the focused text field, or cycles the resource pack setting.
+    pub fn input(&mut self, c: char) {
+        match self.field {
+            FormField::Name => self.name.push(c),
+            FormField::Address => self.ip.push(c),
+            FormField::ResourcePacks => self.cycle_resource_packs(),
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        match self.field {
+            FormField::Name => {
+                self.name.pop();
+            }
+            FormField::Address => {
+                self.ip.pop();
+            }
+            FormField::ResourcePacks => {}
+        }
+    }
+
+    pub fn cycle_resource_packs(&mut self) {
+        self.accept_textures = match self.accept_textures {
+            None => Some(true),
+            Some(true) => Some(false),
+            Some(false) => None,
+        };
+    }
+}
+
+pub enum PingState {
+    Pinging,
+    /// The status and its decoded favicon.
+    Online(ServerStatus, Option<Arc<Icon>>),
+    Failed(String),
+}
+
+type PingResult = (String, Result<(ServerStatus, Option<Arc<Icon>>)>);
+
+pub struct ServerList {
+    pub instance: Instance,
+    path: PathBuf,
+    pub servers: Vec<Server>,
+    pub error: Option<String>,
+    pub state: ListState,
+    pub form: Option<ServerForm>,
+    /// Asks for RCON credentials before opening a console.
+    pub rcon_form: Option<CredentialsForm>,
+    /// Set once the original file has been copied aside this session.
+    backed_up: bool,
+    /// Ping results by address.
+    pub statuses: HashMap<String, PingState>,
+    /// Whether servers are pinged; off for a list made by [`ServerList::load`].
+    pinging: bool,
+    sender: mpsc::Sender<PingResult>,
+    results: mpsc::Receiver<PingResult>,
+    last_refresh: Instant,
+    /// Listens for LAN worlds, or why it couldn't. Only started by [`ServerList::open`].
+    pub lan: Option<Result<LanListener, String>>,
+    pub lan_state: ListState,
+    /// Whether the LAN panel has the selection instead of the saved servers.
+    pub lan_focus: bool,
+}
+
+impl ServerList {
+    /// Reads `servers.dat`, starts pinging its servers and listens for LAN worlds.
+    pub fn open(instance: &Instance) -> Self {
+        let mut list = Self::load(instance);
+        list.lan = Some(LanListener::open().map_err(|e| format!("{:#}", e)));
+        list.pinging = true;
+        list.refresh();
+        list
+    }
+
+    /// Reads `servers.dat` without touching the network.
+    pub fn load(instance: &Instance) -> Self {
+        let (sender, results) = mpsc::channel();
+        let mut list = Self {
+            instance: instance.clone(),
+            path: game_dir(&instance.path).join("servers.dat"),
+            servers: Vec::new(),
+            error: None,
+            state: ListState::default(),
+            form: None,
+            rcon_form: None,
+            backed_up: false,
+            statuses: HashMap::new(),
+            pinging: false,
+            sender,
+            results,
+            last_refresh: Instant::now(),
+            lan: None,
+            lan_state: ListState::default(),
+            lan_focus: false,
+        };
+        match load_servers(&list.path) {
+            Ok(servers) => list.servers = servers,
+            Err(e) => list.error = Some(format!("{:#}", e)),
+        }
+        if !list.servers.is_empty() {
+            list.state.select(Some(0));
+        }
+        list
+    }
+
+    /// Pings every server that isn't already being pinged.
+    pub fn refresh(&mut self) {
+        self.last_refresh = Instant::now();
+        let addresses: Vec<String> = self.servers.iter().map(|s| s.ip.clone()).collect();
+        for address in addresses {
+            self.ping(address);
+        }
+    }
+
+    fn ping(&mut self, address: String) {
+        if !self.pinging || matches!(self.statuses.get(&address), Some(PingState::Pinging)) {
+            return;
+        }
+        self.statuses.insert(address.clone(), PingState::Pinging);
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let result = ping::ping(&address, ping::TIMEOUT).map(|status| {
+                let icon = status.favicon.as_deref().and_then(Icon::from_base64).map(Arc::new);
+                (status, icon)
+            });
+            let _ = sender.send((address, result));
+        });
+    }
+
+    /// Collects finished pings, and starts the next round when one is due.
+    /// Returns true if anything changed.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((address, result)) = self.results.try_recv() {
+            let state = match result {
+                Ok((status, icon)) => PingState::Online(status, icon),
+                Err(e) => PingState::Failed(format!("{:#}", e)),
+            };
+            self.statuses.insert(address, state);
+            changed = true;
+        }
+        if self.last_refresh.elapsed() >= REFRESH_INTERVAL {
+            self.refresh();
+            changed = true;
+        }
+        if let Some(Ok(listener)) = self.lan.as_mut()
+            && listener.poll()
+        {
+            let count = listener.games.len();
+            match self.lan_state.selected() {
+                _ if count == 0 => self.lan_state.select(None),
+                Some(i) if i >= count => self.lan_state.select(Some(count - 1)),
+                None => self.lan_state.select(Some(0)),
+                Some(_) => {}
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn lan_games(&self) -> &[LanGame] {
+        match self.lan {
+            Some(Ok(ref listener)) => &listener.games,
+            _ => &[],
+        }
+    }
+
+    pub fn selected_lan_game(&self) -> Option<&LanGame> {
+        self.lan_state.selected().and_then(|i| self.lan_games().get(i))
+    }
+
+    pub fn toggle_focus(&mut self) {
+        self.lan_focus = !self.lan_focus;
+    }
+
+    /// The address `l` should join: the LAN game or saved server in focus.
+    pub fn join_address(&self) -> Option<String> {
+        if self.lan_focus {
+            self.selected_lan_game().map(|g| g.address.clone())
+        } else {
+            self.selected().map(|s| s.ip.clone())
+        }
+    }
+
+    pub fn status(&self, server: &Server) -> Option<&PingState> {
+        self.statuses.get(&server.ip)
+    }
+
+    pub fn selected(&self) -> Option<&Server> {
+        self.state.selected().and_then(|i| self.servers.get(i))
+    }
+
+    pub fn next(&mut self) {
+        if self.lan_focus {
+            let count = self.lan_games().len();
+            step_selection(&mut self.lan_state, count, true);
+        } else {
+            step_selection(&mut self.state, self.servers.len(), true);
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if self.lan_focus {
+            let count = self.lan_games().len();
+            step_selection(&mut self.lan_state, count, false);
+        } else {
+            step_selection(&mut self.state, self.servers.len(), false);
+        }
+    }
+
+    /// Writes the list back, keeping the file's root name and any other root
+    /// entries. The first write of a session copies the old file aside.
+    fn save(&mut self) -> Result<()> {
+        if self.error.is_some() {
+            bail!("servers.dat could not be read, so it won't be overwritten");
+        }
+        let mut document = if self.path.exists() {
+            Document::load(&self.path)?
+        } else {
+            Document {
+                name: String::new(),
+                root: Tag::Compound(Vec::new()),
+                compression: Compression::None,
+            }
+        };
+        let Tag::Compound(root) = &mut document.root else {
+            bail!("servers.dat root is not a compound");
+        };
+        let list = Tag::List(10, self.servers.iter().map(Server::to_tag).collect());
+        match root.iter_mut().find(|(k, _)| k == "servers") {
+            Some((_, servers)) => *servers = list,
+            None => root.push(("servers".to_string(), list)),
+        }
+        if self.backed_up {
+            document.save(&self.path)?;
+        } else {
+            document.save_with_backup(&self.path)?;
+            self.backed_up = true;
+        }
+        Ok(())
+    }
+
+    pub fn begin_add(&mut self) {
+        self.form = Some(ServerForm {
+            index: None,
+            name: "Minecraft Server".to_string(),
+            ip: String::new(),
+            accept_textures: None,
+            field: FormField::Address,
+        });
+    }
+
+    pub fn begin_edit(&mut self) {
+        let Some(index) = self.state.selected() else {
+            return;
+        };
+        let Some(server) = self.servers.get(index) else {
+            return;
+        };
+        self.form = Some(ServerForm {
+            index: Some(index),
+            name: server.name.clone(),
+            ip: server.ip.clone(),
+            accept_textures: server.accept_textures,
+            field: FormField::Name,
+        });
+    }
+
+    /// Applies the open form and saves. The form stays open if the input is invalid.
+    pub fn submit_form(&mut self) -> Result<String> {
+        let form = self.form.as_ref().context("No form open")?;
+        let (name, ip) = (form.name.trim().to_string(), form.ip.trim().to_string());
+        if ip.is_empty() {
+            bail!("Server address cannot be empty");
+        }
+        let name = if name.is_empty() { "Minecraft Server".to_string() } else { name };
+        let (index, accept_textures) = (form.index, form.accept_textures);
+
+        let previous = self.servers.clone();
+        let index = match index {
+            Some(index) => {
+                let server = self.servers.get_mut(index).context("Server no longer exists")?;
+                server.name = name.clone();
+                server.ip = ip;
+                server.accept_textures = accept_textures;
+                index
+            }
+            None => {
+                self.servers.push(Server {
+                    name: name.clone(),
+                    ip,
+                    accept_textures,
+                    icon: None,
+                    hidden: false,
+                    tag: Vec::new(),
+                });
+                self.servers.len() - 1
+            }
+        };
+        if let Err(e) = self.save() {
+            self.servers = previous;
+            return Err(e);
+        }
+        self.form = None;
+        self.state.select(Some(index));
+        if !self.statuses.contains_key(&self.servers[index].ip) {
+            self.ping(self.servers[index].ip.clone());
+        }
+        Ok(format!("Saved {}", name))
+    }
+
+    /// Moves the selected server up (`-1`) or down (`1`) the list.
+    pub fn move_selected(&mut self, delta: isize) -> Result<()> {
+        let Some(index) = self.state.selected() else {
+            return Ok(());
+        };
+        let Some(target) = index.checked_add_signed(delta).filter(|&t| t < self.servers.len()) else {
+            return Ok(());
+        };
+        self.servers.swap(index, target);
+        if let Err(e) = self.save() {
+            self.servers.swap(index, target);
+            return Err(e);
+        }
+        self.state.select(Some(target));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<String> {
+        if index >= self.servers.len() {
+            bail!("Server no longer exists");
+        }
+        let removed = self.servers.remove(index);
+        if let Err(e) = self.save() {
+            self.servers.insert(index, removed);
+            return Err(e);
+        }
+        if self.servers.is_empty() {
+            self.state.select(None);
+        } else {
+            self.state.select(Some(index.min(self.servers.len() - 1)));
+        }
+        Ok(format!("Removed {}", removed.name))
+    }
+}
+
+/// Draws the server list. Returns where a graphics-protocol icon should be painted.
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    list: &mut ServerList,
+    protocol: GraphicsProtocol,
+) -> Option<(Rect, Arc<Icon>)> {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(8)])
+        .split(chunks[0]);
+    let focus_color = |focused: bool| if focused { Color::Cyan } else { Color::DarkGray };
+
+    let title = format!(" Servers — {} ", list.instance.name);
+    let items: Vec<ListItem> = list
+        .servers
+        .iter()
+        .map(|server| {
+            let summary = match list.status(server) {
+                Some(PingState::Online(status, _)) => Span::styled(
+                    format!("  {}/{} • {} ms", status.online, status.max, status.latency.as_millis()),
+                    Style::default().fg(latency_color(status.latency)),
+                ),
+                Some(PingState::Failed(_)) => Span::styled("  offline", Style::default().fg(Color::Red)),
+                Some(PingState::Pinging) | None => Span::styled("  pinging…", Style::default().fg(Color::DarkGray)),
+            };
+            let mut name = vec![Span::styled(
+                server.name.clone(),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )];
+            if server.hidden {
+                name.push(Span::styled(" (hidden)", Style::default().fg(Color::DarkGray)));
+            }
+            ListItem::new(vec![
+                Line::from(name),
+                Line::from(vec![
+                    Span::styled(format!("  {}", server.ip), Style::default().fg(Color::DarkGray)),
+                    summary,
+                ]),
+            ])
+        })
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(focus_color(!list.lan_focus)))
+        .title(title);
+    if items.is_empty() {
+        let message = list.error.clone().unwrap_or_else(|| "No servers yet; press a to add one".to_string());
+        let color = if list.error.is_some() { Color::Red } else { Color::Yellow };
+        let paragraph = Paragraph::new(message)
+            .style(Style::default().fg(color))
+            .block(block)
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, columns[0]);
+    } else {
+        let widget = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Rgb(50, 50, 80))
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(widget, columns[0], &mut list.state);
+    }
+    render_lan(f, columns[1], list, focus_color(list.lan_focus));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Blue))
+        .title(" Server Details ");
+    let mut text_area = block.inner(chunks[1]);
+    f.render_widget(block, chunks[1]);
+
+    let mut placement = None;
+    if list.lan_focus {
+        if let Some(game) = list.selected_lan_game() {
+            let lines = vec![
+                detail_line("World: ", game.motd.clone()),
+                detail_line("Address: ", game.address.clone()),
+                detail_line("Last Seen: ", format!("{}s ago", game.last_seen.elapsed().as_secs())),
+                Line::from(""),
+                Line::from(Span::styled(
+                    "Opened to LAN on your local network",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ];
+            f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), text_area);
+        }
+    } else if let Some(server) = list.selected() {
+        let status = list.status(server);
+        let live_icon = match status {
+            Some(PingState::Online(_, icon)) => icon.clone(),
+            _ => None,
+        };
+        if let Some(icon) = live_icon.or_else(|| server.icon.clone()) {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(ICON_ROWS + 1), Constraint::Min(0)])
+                .split(text_area);
+            let icon_area = Rect {
+                width: ICON_COLS.min(split[0].width),
+                height: ICON_ROWS.min(split[0].height),
+                ..split[0]
+            };
+            text_area = split[1];
+            if protocol == GraphicsProtocol::HalfBlocks {
+                f.render_widget(Paragraph::new(icon.half_blocks(icon_area.width, icon_area.height)), icon_area);
+            } else {
+                placement = Some((icon_area, icon));
+            }
+        }
+        let mut lines = vec![
+            detail_line("Name: ", server.name.clone()),
+            detail_line("Address: ", server.ip.clone()),
+            detail_line("Resource Packs: ", server.resource_packs()),
+            Line::from(""),
+        ];
+        match status {
+            Some(PingState::Online(status, _)) => status_lines(&mut lines, status),
+            Some(PingState::Failed(error)) => {
+                lines.push(Line::from(Span::styled("Offline", Style::default().fg(Color::Red))));
+                lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::DarkGray))));
+            }
+            Some(PingState::Pinging) | None => {
+                lines.push(Line::from(Span::styled("Pinging…", Style::default().fg(Color::DarkGray))));
+            }
+        }
+        f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), text_area);
+    }
+
+    if let Some(ref form) = list.form {
+        render_form(f, area, form);
+    }
+    if let Some(ref form) = list.rcon_form {
+        rcon::render_credentials(f, area, form);
+    }
+    placement
+}
+
+fn render_lan(f: &mut Frame, area: Rect, list: &mut ServerList, border: Color) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border))
+        .title(" LAN Worlds ");
+    let error = match list.lan {
+        Some(Ok(ref listener)) => listener.error.as_ref(),
+        Some(Err(ref error)) => Some(error),
+        None => None,
+    };
+    if let Some(error) = error {
+        let paragraph = Paragraph::new(format!("LAN discovery unavailable: {}", error))
+            .style(Style::default().fg(Color::Red))
+            .block(block)
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+        return;
+    }
+    let games = match list.lan {
+        Some(Ok(ref listener)) => listener.games.as_slice(),
+        _ => &[],
+    };
+    if games.is_empty() {
+        let paragraph = Paragraph::new("Scanning for games on your local network…")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block)
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+        return;
+    }
+    let items: Vec<ListItem> = games
+        .iter()
+        .map(|game| {
+            ListItem::new(vec![
+                Line::from(Span::styled(
+                    game.motd.clone(),
+                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                )),
+                Line::from(Span::styled(format!("  {}", game.address), Style::default().fg(Color::DarkGray))),
+            ])
+        })
+        .collect();
+    let widget = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 80))
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(widget, area, &mut list.lan_state);
+}
+
+fn latency_color(latency: Duration) -> Color {
+    match latency.as_millis() {
+        0..150 => Color::Green,
+        150..300 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+fn status_lines(lines: &mut Vec<Line<'static>>, status: &ServerStatus) {
+    let label = Style::default().fg(Color::Cyan);
+    lines.extend(ping::styled_lines(&status.description, Style::default().fg(Color::Gray)));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("Latency: ", label),
+        Span::styled(
+            format!("{} ms", status.latency.as_millis()),
+            Style::default().fg(latency_color(status.latency)),
+        ),
+    ]));
+    lines.push(detail_line("Players: ", format!("{}/{}", status.online, status.max)));
+    for name in &status.sample {
+        let mut line = ping::styled_lines(&serde_json::Value::String(name.clone()), Style::default().fg(Color::White));
+        line[0].spans.insert(0, Span::raw("  "));
+        lines.extend(line);
+    }
+    let mut version = vec![Span::styled("Version: ", label)];
+    if let Some(line) = ping::styled_lines(&serde_json::Value::String(status.version.clone()), Style::default())
+        .into_iter()
+        .next()
+    {
+        version.extend(line.spans);
+    }
+    lines.push(Line::from(version));
+    let protocol = match status.protocol {
+        Some(protocol) => protocol.to_string(),
+        None => "unknown".to_string(),
+    };
+    let protocol = if status.legacy { format!("{} (legacy ping)", protocol) } else { protocol };
+    lines.push(detail_line("Protocol: ", protocol));
+}
+
+fn render_form(f: &mut Frame, area: Rect, form: &ServerForm) {
+    let popup = centered_rect(60, 7, area);
+    let field = |label: &str, value: String, field: FormField| {
+        let focused = form.field == field;
+        let style = if focused {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        Line::from(vec![
+            Span::styled(format!("{:<16}", label), Style::default().fg(Color::Cyan)),
+            Span::styled(value, style),
+            Span::styled(if focused { "█" } else { "" }, style),
+        ])
+    };
+    let resource_packs = match form.accept_textures {
+        None => "Prompt",
+        Some(true) => "Enabled",
+        Some(false) => "Disabled",
+    };
+    let lines = vec![
+        field("Server Name:", form.name.clone(), FormField::Name),
+        field("Server Address:", form.ip.clone(), FormField::Address),
+        field("Resource Packs:", format!("◂ {} ▸", resource_packs), FormField::ResourcePacks),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Tab/↑↓ field • Space cycles packs • Enter save • Esc cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    let title = if form.index.is_some() { " Edit Server " } else { " Add Server " };
+    let dialog = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(title),
+    );
+    f.render_widget(Clear, popup);
+    f.render_widget(dialog, popup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_instance(name: &str) -> Instance {
+        let dir = std::env::temp_dir().join(format!("mctui-servers-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".minecraft")).unwrap();
+        Instance {
+            name: "Test".into(),
+            path: dir,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_keeps_unknown_fields() {
+        let instance = temp_instance("round-trip");
+        let path = game_dir(&instance.path).join("servers.dat");
+        let document = Document {
+            name: String::new(),
+            root: Tag::Compound(vec![(
+                "servers".into(),
+                Tag::List(
+                    10,
+                    vec![
+                        Tag::Compound(vec![
+                            ("ip".into(), Tag::String("play.example.net".into())),
+                            ("name".into(), Tag::String("Example".into())),
+                            ("acceptTextures".into(), Tag::Byte(1)),
+                            ("preventsChatReports".into(), Tag::Byte(1)),
+                        ]),
+                        Tag::Compound(vec![
+                            ("ip".into(), Tag::String("localhost".into())),
+                            ("name".into(), Tag::String("Local".into())),
+                        ]),
+                    ],
+                ),
+            )]),
+            compression: Compression::None,
+        };
+        document.save(&path).unwrap();
+
+        let mut list = ServerList::load(&instance);
+        assert_eq!(list.servers.len(), 2);
+        assert_eq!(list.servers[0].resource_packs(), "Enabled");
+        assert_eq!(list.servers[1].resource_packs(), "Prompt");
+
+        list.move_selected(1).unwrap();
+        assert_eq!(list.state.selected(), Some(1));
+        list.begin_edit();
+        let form = list.form.as_mut().unwrap();
+        form.field = FormField::ResourcePacks;
+        form.input(' ');
+        form.input(' ');
+        list.submit_form().unwrap();
+
+        let saved = Document::load(&path).unwrap();
+        assert_eq!(saved.compression, Compression::None);
+        let servers = saved.root.get("servers").and_then(Tag::as_list).unwrap();
+        assert_eq!(servers[0].get("name").and_then(Tag::as_str), Some("Local"));
+        assert_eq!(servers[1].get("acceptTextures"), None);
+        assert_eq!(servers[1].get("preventsChatReports"), Some(&Tag::Byte(1)));
+
+        let backups = fs::read_dir(game_dir(&instance.path))
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with(".bak"))
+            .count();
+        assert_eq!(backups, 1);
+        fs::remove_dir_all(&instance.path).unwrap();
+    }
+
+    #[test]
+    fn test_add_and_remove_without_a_file() {
+        let instance = temp_instance("add");
+        let mut list = ServerList::load(&instance);
+        assert!(list.servers.is_empty());
+
+        list.begin_add();
+        assert!(list.submit_form().is_err());
+        list.form.as_mut().unwrap().ip = "mc.example.org:25566".into();
+        list.submit_form().unwrap();
+        assert_eq!(load_servers(&list.path).unwrap()[0].ip, "mc.example.org:25566");
+        assert!(list.statuses.is_empty());
+
+        list.remove(0).unwrap();
+        assert!(load_servers(&list.path).unwrap().is_empty());
+        fs::remove_dir_all(&instance.path).unwrap();
+    }
+}
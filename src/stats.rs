@@ -0,0 +1,465 @@
+//! Player statistics and advancements from each world's `stats/` and
+//! `advancements/` folders, and the stats screen.
+//!
+//! Both folders hold one JSON file per player UUID. Names come from the
+//! instance's `usercache.json`. Stats files from before 1.13 use flat
+//! `stat.*` keys and are translated to the modern names.
+
+use crate::world::{load_worlds, World};
+use crate::{detail_line, format_duration, step_selection, Instance};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+/// How many entries the "most mined" and "most killed" lists show.
+const TOP_N: usize = 5;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayerStats {
+    pub play_time_ticks: u64,
+    pub deaths: u64,
+    /// Walking, sprinting and sneaking combined.
+    pub walked_cm: u64,
+    pub jumps: u64,
+    pub mob_kills: u64,
+    pub player_kills: u64,
+    pub mined: HashMap<String, u64>,
+    pub killed: HashMap<String, u64>,
+    pub advancements_done: usize,
+    pub advancements_in_progress: usize,
+    /// Completed advancements per tab (story, nether, end, adventure, husbandry, …).
+    pub advancement_tabs: BTreeMap<String, usize>,
+}
+
+impl PlayerStats {
+    fn merge(&mut self, other: &PlayerStats) {
+        self.play_time_ticks += other.play_time_ticks;
+        self.deaths += other.deaths;
+        self.walked_cm += other.walked_cm;
+        self.jumps += other.jumps;
+        self.mob_kills += other.mob_kills;
+        self.player_kills += other.player_kills;
+        for (block, count) in &other.mined {
+            *self.mined.entry(block.clone()).or_default() += count;
+        }
+        for (entity, count) in &other.killed {
+            *self.killed.entry(entity.clone()).or_default() += count;
+        }
+        self.advancements_done += other.advancements_done;
+        self.advancements_in_progress += other.advancements_in_progress;
+        for (tab, count) in &other.advancement_tabs {
+            *self.advancement_tabs.entry(tab.clone()).or_default() += count;
+        }
+    }
+
+    fn apply_stats(&mut self, json: &Value) {
+        if let Some(stats) = json.get("stats") {
+            let custom = |key: &str| stats.pointer(&format!("/minecraft:custom/minecraft:{}", key)).and_then(Value::as_u64);
+            // 1.17 renamed play_one_minute (which always counted ticks) to play_time
+            self.play_time_ticks = custom("play_time").or(custom("play_one_minute")).unwrap_or(0);
+            self.deaths = custom("deaths").unwrap_or(0);
+            self.walked_cm = ["walk_one_cm", "sprint_one_cm", "crouch_one_cm"]
+                .iter()
+                .filter_map(|key| custom(key))
+                .sum();
+            self.jumps = custom("jump").unwrap_or(0);
+            self.mob_kills = custom("mob_kills").unwrap_or(0);
+            self.player_kills = custom("player_kills").unwrap_or(0);
+            self.mined = counts(stats.get("minecraft:mined"));
+            self.killed = counts(stats.get("minecraft:killed"));
+            return;
+        }
+
+        let Some(flat) = json.as_object() else {
+            return;
+        };
+        let stat = |key: &str| flat.get(key).and_then(Value::as_u64).unwrap_or(0);
+        self.play_time_ticks = stat("stat.playOneMinute");
+        self.deaths = stat("stat.deaths");
+        self.walked_cm = stat("stat.walkOneCm") + stat("stat.sprintOneCm") + stat("stat.crouchOneCm");
+        self.jumps = stat("stat.jump");
+        self.mob_kills = stat("stat.mobKills");
+        self.player_kills = stat("stat.playerKills");
+        for (key, value) in flat {
+            let count = value.as_u64().unwrap_or(0);
+            if let Some(block) = key.strip_prefix("stat.mineBlock.") {
+                // "stat.mineBlock.minecraft.stone"
+                let block = block.replacen('.', ":", 1);
+                *self.mined.entry(block).or_default() += count;
+            } else if let Some(entity) = key.strip_prefix("stat.killEntity.") {
+                *self.killed.entry(entity.to_string()).or_default() += count;
+            }
+        }
+    }
+
+    fn apply_advancements(&mut self, json: &Value) {
+        let Some(entries) = json.as_object() else {
+            return;
+        };
+        for (id, progress) in entries {
+            // Recipe unlocks are stored as advancements but are not shown in the game
+            let path = id.split_once(':').map_or(id.as_str(), |(_, path)| path);
+            if path.starts_with("recipes/") || !progress.is_object() {
+                continue;
+            }
+            if progress.get("done").and_then(Value::as_bool) == Some(true) {
+                self.advancements_done += 1;
+                let tab = path.split('/').next().unwrap_or(path).to_string();
+                *self.advancement_tabs.entry(tab).or_default() += 1;
+            } else {
+                self.advancements_in_progress += 1;
+            }
+        }
+    }
+}
+
+fn counts(value: Option<&Value>) -> HashMap<String, u64> {
+    value
+        .and_then(Value::as_object)
+        .map(|map| {
+            map.iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_u64()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The `n` largest entries, biggest first.
+fn top(map: &HashMap<String, u64>, n: usize) -> Vec<(&str, u64)> {
+    let mut entries: Vec<(&str, u64)> = map.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    entries.truncate(n);
+    entries
+}
+
+/// "minecraft:deepslate_iron_ore" → "deepslate iron ore".
+fn display_id(id: &str) -> String {
+    id.strip_prefix("minecraft:").unwrap_or(id).replace('_', " ")
+}
+
+fn format_distance(cm: u64) -> String {
+    if cm >= 100_000 {
+        format!("{:.1} km", cm as f64 / 100_000.0)
+    } else {
+        format!("{} m", cm / 100)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CachedUser {
+    name: String,
+    uuid: String,
+}
+
+/// UUID → player name from the instance's `usercache.json`.
+fn load_usercache(game_dir: &Path) -> HashMap<String, String> {
+    let Ok(content) = fs::read_to_string(game_dir.join("usercache.json")) else {
+        return HashMap::new();
+    };
+    serde_json::from_str::<Vec<CachedUser>>(&content)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|user| (user.uuid, user.name))
+        .collect()
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+pub struct WorldStats {
+    pub name: String,
+    /// Player name (or UUID when unknown) and their stats.
+    pub players: Vec<(String, PlayerStats)>,
+    pub total: PlayerStats,
+}
+
+impl WorldStats {
+    fn load(world_dir: &Path, world: &World, names: &HashMap<String, String>) -> Self {
+        let mut players: BTreeMap<String, PlayerStats> = BTreeMap::new();
+        for (folder, is_stats) in [("stats", true), ("advancements", false)] {
+            let Ok(entries) = fs::read_dir(world_dir.join(folder)) else {
+                continue;
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                let Some(uuid) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                let Some(json) = read_json(&path) else {
+                    continue;
+                };
+                let stats = players.entry(uuid.to_string()).or_default();
+                if is_stats {
+                    stats.apply_stats(&json);
+                } else {
+                    stats.apply_advancements(&json);
+                }
+            }
+        }
+
+        let mut total = PlayerStats::default();
+        for stats in players.values() {
+            total.merge(stats);
+        }
+        let players = players
+            .into_iter()
+            .map(|(uuid, stats)| (names.get(&uuid).cloned().unwrap_or(uuid), stats))
+            .collect();
+        Self {
+            name: world.display_name().to_string(),
+            players,
+            total,
+        }
+    }
+}
+
+pub struct StatsView {
+    pub instance_name: String,
+    pub worlds: Vec<WorldStats>,
+    /// Everything from every world added together.
+    pub all: PlayerStats,
+    /// Row 0 is the instance-wide summary, then one row per world.
+    pub state: ListState,
+}
+
+impl StatsView {
+    pub fn open(instance: &Instance, selected_world: Option<&str>) -> Self {
+        let game_dir = crate::game_dir(&instance.path);
+        let names = load_usercache(&game_dir);
+        let saves = game_dir.join("saves");
+        let worlds_on_disk = load_worlds(&game_dir);
+
+        let worlds: Vec<WorldStats> = worlds_on_disk
+            .iter()
+            .map(|world| WorldStats::load(&saves.join(&world.folder), world, &names))
+            .collect();
+        let mut all = PlayerStats::default();
+        for world in &worlds {
+            all.merge(&world.total);
+        }
+
+        let selected = selected_world
+            .and_then(|folder| worlds_on_disk.iter().position(|w| w.folder == folder))
+            .map_or(0, |i| i + 1);
+        let mut state = ListState::default();
+        state.select(Some(selected));
+        Self {
+            instance_name: instance.name.clone(),
+            worlds,
+            all,
+            state,
+        }
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.worlds.len() + 1, true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.worlds.len() + 1, false);
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, view: &mut StatsView) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+
+    let mut items = vec![ListItem::new(vec![
+        Line::from(Span::styled(
+            "All worlds",
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            format!("  {} worlds • {}", view.worlds.len(), format_duration(view.all.play_time_ticks / 20)),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ])];
+    items.extend(view.worlds.iter().map(|world| {
+        ListItem::new(vec![
+            Line::from(Span::styled(
+                world.name.clone(),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(Span::styled(
+                format!(
+                    "  {} • {} advancements",
+                    format_duration(world.total.play_time_ticks / 20),
+                    world.total.advancements_done
+                ),
+                Style::default().fg(Color::DarkGray),
+            )),
+        ])
+    }));
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(format!(" Stats — {} ", view.instance_name)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 80))
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[0], &mut view.state);
+
+    let (title, stats, players) = match view.state.selected() {
+        Some(i) if i > 0 && i <= view.worlds.len() => {
+            let world = &view.worlds[i - 1];
+            (world.name.as_str(), &world.total, Some(&world.players))
+        }
+        _ => ("All worlds", &view.all, None),
+    };
+    let details = Paragraph::new(stats_lines(stats, players))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(format!(" {} ", title)),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, chunks[1]);
+}
+
+fn stats_lines(stats: &PlayerStats, players: Option<&Vec<(String, PlayerStats)>>) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if let Some(players) = players {
+        if players.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "No statistics recorded for this world",
+                Style::default().fg(Color::Yellow),
+            )));
+            return lines;
+        }
+        let names: Vec<&str> = players.iter().map(|(name, _)| name.as_str()).collect();
+        lines.push(detail_line("Players: ", names.join(", ")));
+        lines.push(Line::from(""));
+    }
+
+    lines.push(detail_line("Play Time: ", format_duration(stats.play_time_ticks / 20)));
+    lines.push(detail_line("Deaths: ", stats.deaths.to_string()));
+    lines.push(detail_line("Distance Walked: ", format_distance(stats.walked_cm)));
+    lines.push(detail_line("Jumps: ", stats.jumps.to_string()));
+    lines.push(detail_line("Mobs Killed: ", stats.mob_kills.to_string()));
+    if stats.player_kills > 0 {
+        lines.push(detail_line("Players Killed: ", stats.player_kills.to_string()));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(detail_line(
+        "Advancements: ",
+        format!("{} completed, {} in progress", stats.advancements_done, stats.advancements_in_progress),
+    ));
+    if !stats.advancement_tabs.is_empty() {
+        let tabs: Vec<String> = stats
+            .advancement_tabs
+            .iter()
+            .map(|(tab, count)| format!("{} {}", display_id(tab), count))
+            .collect();
+        lines.push(Line::from(Span::styled(
+            format!("  {}", tabs.join(" • ")),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    for (heading, map) in [("Most Mined:", &stats.mined), ("Most Killed:", &stats.killed)] {
+        let entries = top(map, TOP_N);
+        if entries.is_empty() {
+            continue;
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            heading,
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        )));
+        for (id, count) in entries {
+            lines.push(Line::from(format!("  {:>7}  {}", count, display_id(id))));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_modern_stats() {
+        let mut stats = PlayerStats::default();
+        stats.apply_stats(&json!({
+            "stats": {
+                "minecraft:custom": {
+                    "minecraft:play_time": 72000,
+                    "minecraft:deaths": 3,
+                    "minecraft:walk_one_cm": 150000,
+                    "minecraft:sprint_one_cm": 50000,
+                    "minecraft:mob_kills": 12
+                },
+                "minecraft:mined": {"minecraft:stone": 40, "minecraft:dirt": 90, "minecraft:oak_log": 5}
+            },
+            "DataVersion": 3700
+        }));
+        assert_eq!(stats.play_time_ticks, 72000);
+        assert_eq!(stats.deaths, 3);
+        assert_eq!(format_distance(stats.walked_cm), "2.0 km");
+        assert_eq!(top(&stats.mined, 2), vec![("minecraft:dirt", 90), ("minecraft:stone", 40)]);
+    }
+
+    #[test]
+    fn test_legacy_stats() {
+        let mut stats = PlayerStats::default();
+        stats.apply_stats(&json!({
+            "stat.playOneMinute": 1200,
+            "stat.deaths": 1,
+            "stat.mineBlock.minecraft.stone": 7,
+            "stat.killEntity.Zombie": 2
+        }));
+        assert_eq!(stats.play_time_ticks, 1200);
+        assert_eq!(stats.mined.get("minecraft:stone"), Some(&7));
+        assert_eq!(stats.killed.get("Zombie"), Some(&2));
+    }
+
+    #[test]
+    fn test_advancements_and_merge() {
+        let json = json!({
+            "minecraft:story/root": {"criteria": {}, "done": true},
+            "minecraft:story/mine_stone": {"criteria": {}, "done": true},
+            "minecraft:nether/root": {"criteria": {}, "done": false},
+            "minecraft:recipes/misc/charcoal": {"criteria": {}, "done": true},
+            "DataVersion": 3700
+        });
+        let mut stats = PlayerStats::default();
+        stats.apply_advancements(&json);
+        assert_eq!(stats.advancements_done, 2);
+        assert_eq!(stats.advancements_in_progress, 1);
+        assert_eq!(stats.advancement_tabs.get("story"), Some(&2));
+
+        let mut total = PlayerStats::default();
+        total.merge(&stats);
+        total.merge(&stats);
+        assert_eq!(total.advancements_done, 4);
+        assert_eq!(total.advancement_tabs.get("story"), Some(&4));
+    }
+}
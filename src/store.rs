@@ -0,0 +1,388 @@
+//! Content-addressed snapshot store for incremental backups.
+//!
+//! Every file (for worlds, mostly `.mca` region files) is stored once as a
+//! zlib-compressed blob named by the SHA-256 of its contents, under
+//! `<backup dir>/store/objects/ab/cdef…`. A snapshot is a small JSON manifest
+//! listing paths and hashes, so backing up a world again only writes the region
+//! files that changed. Blobs no manifest refers to are removed by [`collect_garbage`].
+
+use anyhow::{bail, Context, Result};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const MANIFEST_EXTENSION: &str = ".snapshot.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Seconds since the epoch when the snapshot started.
+    pub created: u64,
+    pub dirs: Vec<String>,
+    pub files: Vec<FileEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// Relative to the snapshotted folder, `/`-separated.
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+    /// Seconds since the epoch; with `size`, lets unchanged files skip re-hashing.
+    pub mtime: u64,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+}
+
+pub struct Store {
+    objects: PathBuf,
+}
+
+/// Counts what a snapshot actually added to the store.
+#[derive(Debug, Default, PartialEq)]
+pub struct SnapshotStats {
+    pub files: usize,
+    pub new_objects: usize,
+    pub new_bytes: u64,
+}
+
+impl Store {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            objects: root.join("objects"),
+        }
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.objects.join(&hash[..2]).join(&hash[2..])
+    }
+
+    /// Writes a manifest of `source` to `manifest_path`, storing any file contents
+    /// not already in the store. `previous` is the target's last manifest, used to
+    /// skip hashing files whose size and mtime have not changed.
+    pub fn snapshot(
+        &self,
+        source: &Path,
+        manifest_path: &Path,
+        previous: Option<&Manifest>,
+        skip: &[&str],
+    ) -> Result<SnapshotStats> {
+        // Files modified in the same second the previous snapshot started could
+        // have changed after it read them, so only older files are trusted
+        let known: HashMap<&str, &FileEntry> = previous
+            .map(|m| {
+                m.files
+                    .iter()
+                    .filter(|f| f.mtime < m.created)
+                    .map(|f| (f.path.as_str(), f))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut manifest = Manifest {
+            created: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            ..Default::default()
+        };
+        let mut stats = SnapshotStats::default();
+        let mut pending = vec![(source.to_path_buf(), String::new())];
+        while let Some((dir, rel)) = pending.pop() {
+            let mut entries: Vec<_> = fs::read_dir(&dir)
+                .with_context(|| format!("reading {}", dir.display()))?
+                .filter_map(|e| e.ok())
+                .collect();
+            entries.sort_by_key(|e| e.file_name());
+
+            for entry in entries {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if skip.contains(&name.as_str()) {
+                    continue;
+                }
+                let path = entry.path();
+                let rel = if rel.is_empty() { name } else { format!("{}/{}", rel, name) };
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    manifest.dirs.push(rel.clone());
+                    pending.push((path, rel));
+                } else if file_type.is_file() {
+                    let metadata = entry.metadata()?;
+                    let size = metadata.len();
+                    let mtime = metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map_or(0, |d| d.as_secs());
+
+                    let unchanged = known
+                        .get(rel.as_str())
+                        .filter(|f| f.size == size && f.mtime == mtime && self.object_path(&f.hash).exists());
+                    let hash = match unchanged {
+                        Some(f) => f.hash.clone(),
+                        None => {
+                            let hash = hash_file(&path)?;
+                            if let Some(written) = self.store_object(&hash, &path)? {
+                                stats.new_objects += 1;
+                                stats.new_bytes += written;
+                            }
+                            hash
+                        }
+                    };
+                    stats.files += 1;
+                    manifest.files.push(FileEntry { path: rel, hash, size, mtime });
+                }
+                // Symlinks and special files are not part of worlds or instances
+            }
+        }
+
+        manifest.dirs.sort();
+        manifest.files.sort_by(|a, b| a.path.cmp(&b.path));
+        write_atomic(manifest_path, serde_json::to_string(&manifest)?.as_bytes())?;
+        Ok(stats)
+    }
+
+    /// Stores a file under its hash unless it is already there. Returns the compressed size if written.
+    fn store_object(&self, hash: &str, path: &Path) -> Result<Option<u64>> {
+        let object = self.object_path(hash);
+        if object.exists() {
+            return Ok(None);
+        }
+        let dir = object.parent().expect("object paths have a fan-out folder");
+        fs::create_dir_all(dir)?;
+        let partial = temp_path(dir);
+        let result = (|| -> Result<()> {
+            let mut encoder = ZlibEncoder::new(File::create(&partial)?, Compression::default());
+            io::copy(&mut File::open(path)?, &mut encoder)?;
+            encoder.finish()?.sync_all()?;
+            Ok(())
+        })();
+        if let Err(err) = result {
+            let _ = fs::remove_file(&partial);
+            return Err(err).with_context(|| format!("storing {}", path.display()));
+        }
+        fs::rename(&partial, &object)?;
+        Ok(Some(fs::metadata(&object)?.len()))
+    }
+
+    /// Recreates a snapshot's files under `dest`, verifying every blob's hash.
+    pub fn extract(&self, manifest: &Manifest, dest: &Path) -> Result<()> {
+        fs::create_dir_all(dest)?;
+        for dir in &manifest.dirs {
+            fs::create_dir_all(dest.join(safe_relative(dir)?))?;
+        }
+        for file in &manifest.files {
+            let path = dest.join(safe_relative(&file.path)?);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if file.hash.len() != 64 || !file.hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                bail!("invalid hash for {} in snapshot", file.path);
+            }
+            let object = self.object_path(&file.hash);
+            let blob = File::open(&object).with_context(|| format!("missing object for {}", file.path))?;
+            let mut reader = HashingReader::new(ZlibDecoder::new(blob));
+            io::copy(&mut reader, &mut File::create(&path)?)
+                .with_context(|| format!("restoring {}", file.path))?;
+            if reader.hex_digest() != file.hash {
+                bail!("object for {} is corrupt", file.path);
+            }
+            let mtime = UNIX_EPOCH + std::time::Duration::from_secs(file.mtime);
+            let _ = File::options().write(true).open(&path).and_then(|f| f.set_modified(mtime));
+        }
+        Ok(())
+    }
+
+    /// Deletes every object no manifest under `backup_root` refers to.
+    /// Returns the number of objects and bytes freed.
+    pub fn collect_garbage(&self, backup_root: &Path) -> Result<(usize, u64)> {
+        let mut referenced = HashSet::new();
+        for manifest_path in find_manifests(backup_root) {
+            let manifest = Manifest::load(&manifest_path)?;
+            referenced.extend(manifest.files.into_iter().map(|f| f.hash));
+        }
+
+        let mut removed = 0;
+        let mut freed = 0;
+        let Ok(fan_out) = fs::read_dir(&self.objects) else {
+            return Ok((0, 0));
+        };
+        for dir in fan_out.filter_map(|e| e.ok()) {
+            let prefix = dir.file_name().to_string_lossy().into_owned();
+            for object in fs::read_dir(dir.path())?.filter_map(|e| e.ok()) {
+                let hash = format!("{}{}", prefix, object.file_name().to_string_lossy());
+                if !referenced.contains(&hash) {
+                    freed += object.metadata().map_or(0, |m| m.len());
+                    fs::remove_file(object.path())?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok((removed, freed))
+    }
+
+    /// Total bytes and number of objects in the store.
+    pub fn usage(&self) -> (u64, usize) {
+        let Ok(fan_out) = fs::read_dir(&self.objects) else {
+            return (0, 0);
+        };
+        fan_out
+            .filter_map(|e| e.ok())
+            .filter_map(|dir| fs::read_dir(dir.path()).ok())
+            .flatten()
+            .filter_map(|e| e.ok()?.metadata().ok())
+            .fold((0, 0), |(bytes, count), m| (bytes + m.len(), count + 1))
+    }
+}
+
+/// Every snapshot manifest below `root`.
+fn find_manifests(root: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                pending.push(path);
+            } else if entry.file_name().to_string_lossy().ends_with(MANIFEST_EXTENSION) {
+                found.push(path);
+            }
+        }
+    }
+    found
+}
+
+/// Rejects manifest paths that would escape the restore folder.
+fn safe_relative(path: &str) -> Result<&Path> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || relative
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        bail!("unsafe path in snapshot: {}", path);
+    }
+    Ok(relative)
+}
+
+fn temp_path(dir: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!(".tmp-{}-{}", std::process::id(), n))
+}
+
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path.parent().context("manifest has no parent folder")?;
+    fs::create_dir_all(dir)?;
+    let partial = temp_path(dir);
+    fs::write(&partial, contents)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let mut reader = HashingReader::new(File::open(path).with_context(|| format!("opening {}", path.display()))?);
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(reader.hex_digest())
+}
+
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    fn hex_digest(self) -> String {
+        self.hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minecraft-tui-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_snapshots_share_unchanged_files() {
+        let root = temp_dir("store");
+        let world = root.join("World");
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::create_dir_all(world.join("data")).unwrap();
+        fs::write(world.join("level.dat"), b"level").unwrap();
+        fs::write(world.join("region/r.0.0.mca"), vec![7u8; 8192]).unwrap();
+        fs::write(world.join("region/r.0.1.mca"), vec![9u8; 8192]).unwrap();
+        fs::write(world.join("session.lock"), b"lock").unwrap();
+
+        let store = Store::new(&root.join("backups/store"));
+        let first_path = root.join("backups/w/first.snapshot.json");
+        let first = store.snapshot(&world, &first_path, None, &["session.lock"]).unwrap();
+        assert_eq!(first.files, 3);
+        assert_eq!(first.new_objects, 3);
+
+        fs::write(world.join("region/r.0.1.mca"), vec![1u8; 8192]).unwrap();
+        let previous = Manifest::load(&first_path).unwrap();
+        let second_path = root.join("backups/w/second.snapshot.json");
+        let second = store.snapshot(&world, &second_path, Some(&previous), &["session.lock"]).unwrap();
+        assert_eq!(second.files, 3);
+        assert_eq!(second.new_objects, 1);
+
+        // Restoring the first snapshot brings back the old region file and the empty folder
+        let restored = root.join("restored");
+        store.extract(&Manifest::load(&first_path).unwrap(), &restored).unwrap();
+        assert_eq!(fs::read(restored.join("region/r.0.1.mca")).unwrap(), vec![9u8; 8192]);
+        assert!(restored.join("data").is_dir());
+        assert!(!restored.join("session.lock").exists());
+
+        // Once the first snapshot is gone only its replaced region file is unreferenced
+        fs::remove_file(&first_path).unwrap();
+        assert_eq!(store.collect_garbage(&root.join("backups")).unwrap().0, 1);
+        assert_eq!(store.usage().1, 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unsafe_manifest_paths_are_rejected() {
+        assert!(safe_relative("region/r.0.0.mca").is_ok());
+        assert!(safe_relative("../escape").is_err());
+        assert!(safe_relative("/etc/passwd").is_err());
+        assert!(safe_relative("").is_err());
+    }
+}
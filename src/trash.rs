@@ -0,0 +1,132 @@
+//! Moving files to the freedesktop.org trash instead of deleting them.
+//!
+//! Items go to the home trash (`$XDG_DATA_HOME/Trash`): the item itself under
+//! `files/` and a `.trashinfo` under `info/` recording where it came from, so
+//! file managers can restore it.
+
+use crate::backup::{civil_from_days, now_secs};
+use anyhow::{bail, Context, Result};
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+fn home_trash() -> Result<PathBuf> {
+    let data_home = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").context("HOME is not set")?).join(".local/share"),
+    };
+    Ok(data_home.join("Trash"))
+}
+
+/// Moves `path` to the trash and returns where it ended up.
+pub fn trash(path: &Path) -> Result<PathBuf> {
+    trash_into(&home_trash()?, path, now_secs())
+}
+
+fn trash_into(trash_dir: &Path, path: &Path, deleted_secs: u64) -> Result<PathBuf> {
+    let path = fs::canonicalize(path).with_context(|| format!("finding {}", path.display()))?;
+    let name = path
+        .file_name()
+        .context("cannot trash a filesystem root")?
+        .to_string_lossy()
+        .into_owned();
+    let files = trash_dir.join("files");
+    let info = trash_dir.join("info");
+    fs::create_dir_all(&files).with_context(|| format!("creating {}", files.display()))?;
+    fs::create_dir_all(&info).with_context(|| format!("creating {}", info.display()))?;
+
+    // Claim a free name by creating its .trashinfo exclusively, as the spec asks
+    for n in 1.. {
+        let candidate = if n == 1 { name.clone() } else { format!("{}.{}", name, n) };
+        let info_path = info.join(format!("{}.trashinfo", candidate));
+        let mut info_file = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("creating {}", info_path.display())),
+        };
+        let destination = files.join(&candidate);
+        if destination.exists() {
+            drop(info_file);
+            fs::remove_file(&info_path)?;
+            continue;
+        }
+        write!(
+            info_file,
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(&path),
+            deletion_date(deleted_secs)
+        )?;
+
+        if let Err(e) = fs::rename(&path, &destination) {
+            let _ = fs::remove_file(&info_path);
+            if e.kind() == ErrorKind::CrossesDevices {
+                bail!("{} is on a different filesystem from the trash", path.display());
+            }
+            return Err(e).with_context(|| format!("moving {} to the trash", path.display()));
+        }
+        return Ok(destination);
+    }
+    unreachable!()
+}
+
+/// Percent-encodes everything except unreserved characters and `/`.
+fn encode_path(path: &Path) -> String {
+    let mut out = String::new();
+    for &b in path.as_os_str().as_encoded_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// `YYYY-MM-DDThh:mm:ss`. The spec wants local time, but without a timezone
+/// database UTC is the best available.
+fn deletion_date(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_into() {
+        let dir = env::temp_dir().join(format!("mctui-trash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let trash_dir = dir.join("Trash");
+        for _ in 0..2 {
+            let world = dir.join("saves/My World");
+            fs::create_dir_all(&world).unwrap();
+            fs::write(world.join("level.dat"), b"x").unwrap();
+            trash_into(&trash_dir, &world, 86400).unwrap();
+            assert!(!world.exists());
+        }
+
+        assert!(trash_dir.join("files/My World/level.dat").is_file());
+        assert!(trash_dir.join("files/My World.2").is_dir());
+        let info = fs::read_to_string(trash_dir.join("info/My World.trashinfo")).unwrap();
+        let expected_path = encode_path(&fs::canonicalize(&dir).unwrap().join("saves/My World"));
+        assert!(expected_path.ends_with("/saves/My%20World"));
+        assert_eq!(
+            info,
+            format!("[Trash Info]\nPath={}\nDeletionDate=1970-01-02T00:00:00\n", expected_path)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
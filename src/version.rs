@@ -0,0 +1,271 @@
+//! Minecraft version strings and their ordering.
+//!
+//! Plain string order gets `1.9` vs `1.20` wrong and has no idea where
+//! `24w14a` or `1.20.5-pre1` belong. Releases compare by their numeric parts;
+//! pre-releases and release candidates sort just before the release they lead
+//! up to; weekly snapshots are mapped to their target release through a table
+//! of the week each snapshot cycle started.
+
+use std::{cmp::Ordering, fmt};
+
+/// First snapshot week of each development cycle and the release it produced.
+const SNAPSHOT_CYCLES: &[(u32, u32, &[u32])] = &[
+    (12, 15, &[1, 3, 1]),
+    (12, 32, &[1, 4, 2]),
+    (13, 1, &[1, 5]),
+    (13, 16, &[1, 6, 1]),
+    (13, 36, &[1, 7, 2]),
+    (13, 47, &[1, 7, 4]),
+    (14, 2, &[1, 8]),
+    (15, 31, &[1, 9]),
+    (16, 14, &[1, 9, 3]),
+    (16, 20, &[1, 10]),
+    (16, 32, &[1, 11]),
+    (16, 50, &[1, 11, 1]),
+    (17, 6, &[1, 12]),
+    (17, 31, &[1, 12, 1]),
+    (17, 43, &[1, 13]),
+    (18, 30, &[1, 13, 1]),
+    (18, 43, &[1, 14]),
+    (19, 34, &[1, 15]),
+    (20, 6, &[1, 16]),
+    (20, 27, &[1, 16, 2]),
+    (20, 45, &[1, 17]),
+    (21, 37, &[1, 18]),
+    (22, 3, &[1, 18, 2]),
+    (22, 11, &[1, 19]),
+    (22, 24, &[1, 19, 1]),
+    (22, 42, &[1, 19, 3]),
+    (23, 3, &[1, 19, 4]),
+    (23, 12, &[1, 20]),
+    (23, 31, &[1, 20, 2]),
+    (23, 40, &[1, 20, 3]),
+    (23, 51, &[1, 20, 5]),
+    (24, 18, &[1, 21]),
+    (24, 33, &[1, 21, 2]),
+    (24, 44, &[1, 21, 4]),
+    (25, 2, &[1, 21, 5]),
+    (25, 15, &[1, 21, 6]),
+    (25, 31, &[1, 21, 9]),
+    (25, 41, &[1, 21, 11]),
+];
+
+#[derive(Debug, Clone)]
+enum Kind {
+    /// `a1.2.6`, `b1.7.3` and other pre-1.0 builds; `era` 0 is alpha, 1 is beta.
+    Legacy { era: u8, numbers: Vec<u32> },
+    Release(Vec<u32>),
+    PreRelease(Vec<u32>, u32),
+    ReleaseCandidate(Vec<u32>, u32),
+    /// Weekly snapshot such as `24w14a`.
+    Snapshot { year: u32, week: u32, build: char },
+    /// Snapshot named after its target, such as `26.1-snapshot-1`.
+    TargetedSnapshot(Vec<u32>, u32),
+    /// April Fools builds and anything else we can't place.
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct McVersion {
+    raw: String,
+    kind: Kind,
+}
+
+impl McVersion {
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim();
+        McVersion {
+            raw: raw.to_string(),
+            kind: parse_kind(raw).unwrap_or(Kind::Other),
+        }
+    }
+
+    pub fn is_release(&self) -> bool {
+        matches!(self.kind, Kind::Release(_))
+    }
+
+    /// Whether this version is `prefix` or a point release of it (`1.20.4` starts with `1.20`).
+    pub fn starts_with(&self, prefix: &McVersion) -> bool {
+        match (self.numbers(), &prefix.kind) {
+            (Some(numbers), Kind::Release(prefix)) => {
+                let numbers = trim_zeros(numbers);
+                let prefix = trim_zeros(prefix);
+                numbers.len() >= prefix.len() && numbers[..prefix.len()] == *prefix
+            }
+            _ => self == prefix,
+        }
+    }
+
+    fn numbers(&self) -> Option<&[u32]> {
+        match &self.kind {
+            Kind::Release(n) | Kind::PreRelease(n, _) | Kind::ReleaseCandidate(n, _) => Some(n),
+            Kind::TargetedSnapshot(n, _) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Sort key: (era, target release, phase within the cycle, ordinal).
+    fn key(&self) -> (u8, &[u32], u8, (u32, u32, u32), &str) {
+        // Phases within a release cycle: snapshots, pre-releases, release candidates, release.
+        match &self.kind {
+            Kind::Legacy { era, numbers } => (*era, trim_zeros(numbers), 3, (0, 0, 0), ""),
+            Kind::Snapshot { year, week, build } => {
+                let target = snapshot_target(*year, *week);
+                (2, target, 0, (*year, *week, *build as u32), "")
+            }
+            Kind::TargetedSnapshot(n, i) => (2, trim_zeros(n), 0, (0, 0, *i), ""),
+            Kind::PreRelease(n, i) => (2, trim_zeros(n), 1, (*i, 0, 0), ""),
+            Kind::ReleaseCandidate(n, i) => (2, trim_zeros(n), 2, (*i, 0, 0), ""),
+            Kind::Release(n) => (2, trim_zeros(n), 3, (0, 0, 0), ""),
+            Kind::Other => (3, &[], 0, (0, 0, 0), &self.raw),
+        }
+    }
+}
+
+impl fmt::Display for McVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl PartialEq for McVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for McVersion {}
+
+impl PartialOrd for McVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for McVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+fn trim_zeros(numbers: &[u32]) -> &[u32] {
+    let len = numbers.iter().rposition(|&n| n != 0).map_or(0, |i| i + 1);
+    &numbers[..len]
+}
+
+fn snapshot_target(year: u32, week: u32) -> &'static [u32] {
+    SNAPSHOT_CYCLES
+        .iter()
+        .rev()
+        .find(|(y, w, _)| (year, week) >= (*y, *w))
+        .or(SNAPSHOT_CYCLES.first())
+        .map(|(_, _, target)| *target)
+        .unwrap_or(&[])
+}
+
+fn parse_numbers(s: &str) -> Option<Vec<u32>> {
+    if s.is_empty() {
+        return None;
+    }
+    s.split('.').map(|part| part.parse().ok()).collect()
+}
+
+fn parse_kind(raw: &str) -> Option<Kind> {
+    if let Some(numbers) = parse_numbers(raw) {
+        return Some(Kind::Release(numbers));
+    }
+
+    if let Some(snapshot) = parse_weekly_snapshot(raw) {
+        return Some(snapshot);
+    }
+
+    if let Some(rest) = raw.strip_prefix('b') {
+        return Some(Kind::Legacy { era: 1, numbers: parse_numbers(rest)? });
+    }
+    if let Some(rest) = raw.strip_prefix('a') {
+        return Some(Kind::Legacy { era: 0, numbers: parse_numbers(rest)? });
+    }
+
+    // `1.20.5-pre1`, `1.14 Pre-Release 2`, `1.20.4-rc1`, `26.1-snapshot-1`
+    let lower = raw.to_lowercase();
+    let (base, suffix) = lower.split_once(['-', ' '])?;
+    let numbers = parse_numbers(base)?;
+    let suffix = suffix.replace([' ', '-'], "");
+    if let Some(n) = suffix.strip_prefix("prerelease").or(suffix.strip_prefix("pre")) {
+        return Some(Kind::PreRelease(numbers, n.parse().ok()?));
+    }
+    if let Some(n) = suffix.strip_prefix("rc") {
+        return Some(Kind::ReleaseCandidate(numbers, n.parse().ok()?));
+    }
+    if let Some(n) = suffix.strip_prefix("snapshot") {
+        return Some(Kind::TargetedSnapshot(numbers, n.parse().ok()?));
+    }
+    None
+}
+
+fn parse_weekly_snapshot(raw: &str) -> Option<Kind> {
+    let (year, rest) = raw.split_once('w')?;
+    if year.len() != 2 || rest.len() != 3 {
+        return None;
+    }
+    let build = rest.chars().last()?;
+    if !build.is_ascii_lowercase() {
+        return None;
+    }
+    Some(Kind::Snapshot {
+        year: year.parse().ok()?,
+        week: rest[..2].parse().ok()?,
+        build,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> McVersion {
+        McVersion::parse(s)
+    }
+
+    #[test]
+    fn test_release_numeric_order() {
+        assert!(v("1.9") < v("1.20"));
+        assert!(v("1.20") < v("1.20.1"));
+        assert_eq!(v("1.20"), v("1.20.0"));
+        assert!(v("1.21.11") < v("26.1"));
+    }
+
+    #[test]
+    fn test_prerelease_and_rc_before_release() {
+        assert!(v("1.20.5-pre1") < v("1.20.5-pre2"));
+        assert!(v("1.20.5-pre4") < v("1.20.5-rc1"));
+        assert!(v("1.20.5-rc1") < v("1.20.5"));
+        assert!(v("1.20.4") < v("1.20.5-pre1"));
+        assert!(v("1.14 Pre-Release 2") < v("1.14"));
+    }
+
+    #[test]
+    fn test_snapshots_fall_in_their_cycle() {
+        assert!(v("1.20.4") < v("24w14a"));
+        assert!(v("24w14a") < v("1.20.5-pre1"));
+        assert!(v("24w13a") < v("24w14a"));
+        assert!(v("24w18a") > v("1.20.6"));
+        assert!(v("24w18a") < v("1.21"));
+        assert!(v("26.1-snapshot-1") < v("26.1"));
+        assert!(v("26.1-snapshot-1") > v("1.21.11"));
+    }
+
+    #[test]
+    fn test_legacy_before_release() {
+        assert!(v("a1.2.6") < v("b1.7.3"));
+        assert!(v("b1.7.3") < v("1.0"));
+    }
+
+    #[test]
+    fn test_starts_with() {
+        assert!(v("1.20.4").starts_with(&v("1.20")));
+        assert!(v("1.20").starts_with(&v("1.20")));
+        assert!(!v("1.2").starts_with(&v("1.20")));
+        assert!(!v("1.21").starts_with(&v("1.20")));
+    }
+}
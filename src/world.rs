@@ -0,0 +1,720 @@
+//! Singleplayer worlds in an instance's `saves/` folder and the world browser screen.
+
+use crate::nbt::{self, Document, Tag};
+use crate::{centered_rect, detail_line, dir_size, format_size, format_timestamp, step_selection, Instance};
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use std::{
+    cmp::Reverse,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::SystemTime,
+};
+
+/// Characters Minecraft replaces with `_` when turning a world name into a folder name.
+const ILLEGAL_FOLDER_CHARS: &[char] = &['/', '\\', '\n', '\r', '\t', '\0', '\x0c', '`', '?', '*', '<', '>', '|', '"', ':'];
+
+#[derive(Debug, Clone, Default)]
+pub struct World {
+    pub folder: String,
+    pub level_name: Option<String>,
+    pub game_mode: Option<i64>,
+    pub difficulty: Option<String>,
+    pub hardcore: bool,
+    pub cheats: bool,
+    pub seed: Option<i64>,
+    pub version: Option<String>,
+    pub data_version: Option<i64>,
+    /// Milliseconds since the epoch.
+    pub last_played: Option<u64>,
+    pub day_time: Option<i64>,
+    /// Block x and z of the world spawn.
+    pub spawn: Option<(i32, i32)>,
+    /// Block x and z of the singleplayer player, when they are in the overworld.
+    pub player: Option<(i32, i32)>,
+    /// Folder size in bytes, filled in by the world browser once it's measured.
+    pub size: Option<u64>,
+    /// Set when `level.dat` is missing or unreadable; the folder is still listed.
+    pub error: Option<String>,
+}
+
+impl World {
+    pub fn load(path: &Path) -> World {
+        let folder = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut world = World {
+            folder,
+            ..Default::default()
+        };
+
+        match nbt::read_file(&path.join("level.dat")) {
+            Ok(root) => match root.get("Data") {
+                Some(data) => world.apply_level_data(data),
+                None => world.error = Some("level.dat has no Data compound".to_string()),
+            },
+            Err(err) => world.error = Some(format!("{:#}", err)),
+        }
+        world
+    }
+
+    fn apply_level_data(&mut self, data: &Tag) {
+        let int = |key: &str| data.get(key).and_then(Tag::as_i64);
+
+        self.level_name = data.get("LevelName").and_then(Tag::as_str).map(str::to_string);
+        self.game_mode = int("GameType");
+        self.hardcore = int("hardcore") == Some(1);
+        self.cheats = int("allowCommands") == Some(1);
+        self.last_played = int("LastPlayed").map(|t| t.max(0) as u64);
+        self.day_time = int("DayTime");
+        self.data_version = int("DataVersion");
+        self.version = data.path(&["Version", "Name"]).and_then(Tag::as_str).map(str::to_string);
+
+        // 1.16 moved the seed into WorldGenSettings
+        self.seed = data
+            .path(&["WorldGenSettings", "seed"])
+            .or(data.get("RandomSeed"))
+            .and_then(Tag::as_i64);
+
+        // Recent versions replaced SpawnX/Y/Z with a spawn compound holding a position array
+        self.spawn = match (int("SpawnX"), int("SpawnZ")) {
+            (Some(x), Some(z)) => Some((x as i32, z as i32)),
+            _ => match data.path(&["spawn", "pos"]) {
+                Some(Tag::IntArray(pos)) if pos.len() == 3 => Some((pos[0], pos[2])),
+                _ => None,
+            },
+        };
+        let player = data.get("Player");
+        let in_overworld = player.and_then(|p| p.get("Dimension")).is_none_or(|dimension| {
+            dimension.as_str() == Some("minecraft:overworld") || dimension.as_i64() == Some(0)
+        });
+        self.player = player
+            .and_then(|p| p.get("Pos"))
+            .and_then(Tag::as_list)
+            .filter(|_| in_overworld)
+            .and_then(|pos| Some((pos.first()?.as_f64()?.floor() as i32, pos.get(2)?.as_f64()?.floor() as i32)));
+
+        // Newer versions store difficulty by name in difficulty_settings
+        self.difficulty = match data.path(&["difficulty_settings", "difficulty"]).and_then(Tag::as_str) {
+            Some(name) => Some(capitalize(name)),
+            None => int("Difficulty").map(|d| difficulty_name(d).to_string()),
+        };
+    }
+
+    pub fn display_name(&self) -> &str {
+        self.level_name.as_deref().unwrap_or(&self.folder)
+    }
+
+    fn size_text(&self) -> String {
+        self.size.map(format_size).unwrap_or_else(|| "…".to_string())
+    }
+}
+
+pub fn game_mode_name(mode: i64) -> &'static str {
+    match mode {
+        0 => "Survival",
+        1 => "Creative",
+        2 => "Adventure",
+        3 => "Spectator",
+        _ => "Unknown",
+    }
+}
+
+pub fn difficulty_name(difficulty: i64) -> &'static str {
+    match difficulty {
+        0 => "Peaceful",
+        1 => "Easy",
+        2 => "Normal",
+        3 => "Hard",
+        _ => "Unknown",
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Formats `DayTime` ticks as in-game day and clock time. Tick 0 is 06:00 on day 1.
+pub fn format_day_time(ticks: i64) -> String {
+    let ticks = ticks.max(0);
+    let day = ticks / 24000 + 1;
+    let time = ticks % 24000;
+    let hours = (time / 1000 + 6) % 24;
+    let minutes = (time % 1000) * 60 / 1000;
+    format!("Day {}, {:02}:{:02}", day, hours, minutes)
+}
+
+/// Every folder in `saves/`, most recently played first.
+pub fn load_worlds(game_dir: &Path) -> Vec<World> {
+    let Ok(entries) = fs::read_dir(game_dir.join("saves")) else {
+        return Vec::new();
+    };
+    let mut worlds: Vec<World> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .map(|p| World::load(&p))
+        .collect();
+    sort_worlds(&mut worlds, WorldSort::LastPlayed);
+    worlds
+}
+
+/// Folders of the worlds whose `level.dat` was written at or after `since`.
+pub fn modified_since(game_dir: &Path, since: SystemTime) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(game_dir.join("saves")) else {
+        return Vec::new();
+    };
+    let mut folders: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            fs::metadata(e.path().join("level.dat"))
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified >= since)
+        })
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    folders.sort();
+    folders
+}
+
+/// A folder name for `name` in `saves` that is not taken, following Minecraft's
+/// scheme of appending " (2)", " (3)", … `current` counts as free, so renaming a
+/// world to its own name keeps its folder.
+pub fn available_folder(saves: &Path, name: &str, current: Option<&str>) -> String {
+    let mut base: String = name
+        .trim()
+        .chars()
+        .map(|c| if ILLEGAL_FOLDER_CHARS.contains(&c) { '_' } else { c })
+        .collect();
+    // Folder names must not start with a dot or end with one, or be empty
+    base = base.trim_matches('.').to_string();
+    if base.is_empty() {
+        base = "World".to_string();
+    }
+    let free = |folder: &str| Some(folder) == current || !saves.join(folder).exists();
+    if free(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{} ({})", base, n))
+        .find(|folder| free(folder))
+        .expect("some suffix is free")
+}
+
+/// Rewrites `LevelName` in a world's `level.dat`.
+fn set_level_name(world_dir: &Path, name: &str) -> Result<()> {
+    let path = world_dir.join("level.dat");
+    let mut document = Document::load(&path)?;
+    let Some(Tag::Compound(data)) = (match &mut document.root {
+        Tag::Compound(entries) => entries.iter_mut().find(|(k, _)| k == "Data").map(|(_, v)| v),
+        _ => None,
+    }) else {
+        bail!("level.dat has no Data compound");
+    };
+    match data.iter_mut().find(|(k, _)| k == "LevelName") {
+        Some((_, value)) => *value = Tag::String(name.to_string()),
+        None => data.push(("LevelName".to_string(), Tag::String(name.to_string()))),
+    }
+    document.save(&path)
+}
+
+/// Renames a world in-game and on disk. Returns the new folder name.
+pub fn rename_world(saves: &Path, folder: &str, name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("World name cannot be empty");
+    }
+    let world_dir = saves.join(folder);
+    set_level_name(&world_dir, name)?;
+    let new_folder = available_folder(saves, name, Some(folder));
+    if new_folder != folder {
+        fs::rename(&world_dir, saves.join(&new_folder))
+            .with_context(|| format!("renaming {} to {}", folder, new_folder))?;
+    }
+    Ok(new_folder)
+}
+
+/// Copies a world into `dest_saves` under `name`. Returns the new folder name.
+pub fn duplicate_world(world_dir: &Path, dest_saves: &Path, name: &str) -> Result<String> {
+    fs::create_dir_all(dest_saves).with_context(|| format!("creating {}", dest_saves.display()))?;
+    let folder = available_folder(dest_saves, name, None);
+    let staging = dest_saves.join(format!(".{}.copying", folder));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let copied = copy_dir(world_dir, &staging).and_then(|_| set_level_name(&staging, name));
+    if let Err(e) = copied {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    fs::rename(&staging, dest_saves.join(&folder))?;
+    Ok(folder)
+}
+
+/// Recursive copy that leaves out the game's `session.lock`.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).with_context(|| format!("creating {}", to.display()))?;
+    for entry in fs::read_dir(from).with_context(|| format!("reading {}", from.display()))? {
+        let entry = entry?;
+        let source: PathBuf = entry.path();
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&source, &target)?;
+        } else if file_type.is_file() && entry.file_name() != "session.lock" {
+            fs::copy(&source, &target).with_context(|| format!("copying {}", source.display()))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn count_worlds(game_dir: &Path) -> Option<usize> {
+    let entries = fs::read_dir(game_dir.join("saves")).ok()?;
+    Some(
+        entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join("level.dat").is_file())
+            .count(),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorldSort {
+    LastPlayed,
+    Name,
+    Size,
+}
+
+impl WorldSort {
+    fn next(&self) -> Self {
+        match self {
+            WorldSort::LastPlayed => WorldSort::Name,
+            WorldSort::Name => WorldSort::Size,
+            WorldSort::Size => WorldSort::LastPlayed,
+        }
+    }
+
+    fn display(&self) -> &str {
+        match self {
+            WorldSort::LastPlayed => "Last Played",
+            WorldSort::Name => "Name",
+            WorldSort::Size => "Size",
+        }
+    }
+}
+
+fn sort_worlds(worlds: &mut [World], sort: WorldSort) {
+    match sort {
+        WorldSort::LastPlayed => worlds.sort_by_key(|w| Reverse(w.last_played)),
+        WorldSort::Name => worlds.sort_by_key(|w| w.display_name().to_lowercase()),
+        // Worlds still being measured go last
+        WorldSort::Size => worlds.sort_by_key(|w| Reverse(w.size)),
+    }
+}
+
+/// Choosing which instance to copy a world into.
+pub struct CopyPicker {
+    pub instances: Vec<Instance>,
+    pub state: ListState,
+}
+
+pub struct WorldBrowser {
+    pub instance: Instance,
+    pub worlds: Vec<World>,
+    pub state: ListState,
+    sort: WorldSort,
+    /// The new name being typed while renaming.
+    pub rename_input: Option<String>,
+    pub copy_picker: Option<CopyPicker>,
+    /// Folder sizes still being measured, by world folder.
+    sizes: Option<mpsc::Receiver<(String, Option<u64>)>>,
+}
+
+impl WorldBrowser {
+    pub fn open(instance: &Instance) -> Self {
+        let worlds = load_worlds(&crate::game_dir(&instance.path));
+        let mut state = ListState::default();
+        if !worlds.is_empty() {
+            state.select(Some(0));
+        }
+        let mut browser = Self {
+            instance: instance.clone(),
+            worlds,
+            state,
+            sort: WorldSort::LastPlayed,
+            rename_input: None,
+            copy_picker: None,
+            sizes: None,
+        };
+        browser.measure_sizes();
+        browser
+    }
+
+    /// Measures the world folders on a background thread; big worlds take a while to walk.
+    fn measure_sizes(&mut self) {
+        let saves = self.saves_dir();
+        let folders: Vec<String> = self.worlds.iter().map(|w| w.folder.clone()).collect();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for folder in folders {
+                let size = dir_size(&saves.join(&folder)).ok();
+                if sender.send((folder, size)).is_err() {
+                    return;
+                }
+            }
+        });
+        self.sizes = Some(receiver);
+    }
+
+    /// Fills in world sizes as they're measured. Returns true if anything changed.
+    pub fn poll(&mut self) -> bool {
+        let Some(ref sizes) = self.sizes else {
+            return false;
+        };
+        loop {
+            match sizes.try_recv() {
+                Ok((folder, size)) => {
+                    if let Some(world) = self.worlds.iter_mut().find(|w| w.folder == folder) {
+                        world.size = size;
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+        self.sizes = None;
+        if self.sort == WorldSort::Size {
+            let selected = self.selected().map(|w| w.folder.clone());
+            sort_worlds(&mut self.worlds, self.sort);
+            if let Some(folder) = selected {
+                self.select_folder(&folder);
+            }
+        }
+        true
+    }
+
+    pub fn saves_dir(&self) -> PathBuf {
+        crate::game_dir(&self.instance.path).join("saves")
+    }
+
+    /// Re-reads the worlds from disk, keeping the sort and the selected folder.
+    pub fn reload(&mut self) {
+        let selected = self.selected().map(|w| w.folder.clone());
+        self.worlds = load_worlds(&crate::game_dir(&self.instance.path));
+        sort_worlds(&mut self.worlds, self.sort);
+        let index = selected
+            .and_then(|folder| self.worlds.iter().position(|w| w.folder == folder))
+            .or(if self.worlds.is_empty() { None } else { Some(0) });
+        self.state.select(index);
+        self.measure_sizes();
+    }
+
+    pub fn selected(&self) -> Option<&World> {
+        self.state.selected().and_then(|i| self.worlds.get(i))
+    }
+
+    pub fn select_folder(&mut self, folder: &str) {
+        if let Some(index) = self.worlds.iter().position(|w| w.folder == folder) {
+            self.state.select(Some(index));
+        }
+    }
+
+    pub fn next(&mut self) {
+        step_selection(&mut self.state, self.worlds.len(), true);
+    }
+
+    pub fn previous(&mut self) {
+        step_selection(&mut self.state, self.worlds.len(), false);
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        sort_worlds(&mut self.worlds, self.sort);
+        if !self.worlds.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    pub fn sort_display(&self) -> &str {
+        self.sort.display()
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, browser: &mut WorldBrowser) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+
+    let title = format!(" Worlds — {} ", browser.instance.name);
+    if browser.worlds.is_empty() {
+        let message = Paragraph::new("No worlds in this instance")
+            .style(Style::default().fg(Color::Yellow))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan))
+                    .title(title),
+            );
+        f.render_widget(message, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = browser
+        .worlds
+        .iter()
+        .map(|world| {
+            let mut title_spans = vec![Span::styled(
+                world.display_name().to_string(),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )];
+            if world.hardcore {
+                title_spans.push(Span::styled(" ♥ HARDCORE", Style::default().fg(Color::Red)));
+            }
+            if world.error.is_some() {
+                title_spans.push(Span::styled(" ⚠", Style::default().fg(Color::Yellow)));
+            }
+
+            let mut info_parts = Vec::new();
+            if let Some(mode) = world.game_mode {
+                info_parts.push(game_mode_name(mode).to_string());
+            }
+            if let Some(last_played) = world.last_played {
+                info_parts.push(format!("Last played: {}", format_timestamp(last_played)));
+            }
+            info_parts.push(world.size_text());
+
+            ListItem::new(vec![
+                Line::from(title_spans),
+                Line::from(Span::styled(
+                    format!("  {}", info_parts.join(" • ")),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(50, 50, 80))
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[0], &mut browser.state);
+
+    let lines = browser.selected().map(world_details).unwrap_or_default();
+    let details = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(" World Details "),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, chunks[1]);
+
+    if let Some(ref name) = browser.rename_input {
+        let area = centered_rect(50, 3, area);
+        let input = Paragraph::new(format!("{}█", name))
+            .style(Style::default().fg(Color::Yellow))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(" Rename world (Enter to apply, Esc to cancel) "),
+            );
+        f.render_widget(Clear, area);
+        f.render_widget(input, area);
+    }
+
+    if let Some(ref mut picker) = browser.copy_picker {
+        let height = (picker.instances.len() as u16 + 2).min(area.height);
+        let area = centered_rect(50, height, area);
+        let current = &browser.instance.path;
+        let items: Vec<ListItem> = picker
+            .instances
+            .iter()
+            .map(|instance| {
+                let label = if instance.path == *current {
+                    format!("{} (this instance)", instance.name)
+                } else {
+                    instance.name.clone()
+                };
+                ListItem::new(label)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(" Duplicate into… (Enter to copy, Esc to cancel) "),
+            )
+            .highlight_style(Style::default().bg(Color::Rgb(50, 50, 80)).fg(Color::Yellow))
+            .highlight_symbol(">> ");
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut picker.state);
+    }
+}
+
+fn world_details(world: &World) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        detail_line("Name: ", world.display_name()),
+        detail_line("Folder: ", &world.folder),
+    ];
+
+    if let Some(ref error) = world.error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Yellow))));
+        lines.push(Line::from(""));
+        lines.push(detail_line("Size: ", world.size_text()));
+        return lines;
+    }
+
+    if let Some(ref version) = world.version {
+        lines.push(detail_line("Version: ", version));
+    }
+    lines.push(Line::from(""));
+    if let Some(mode) = world.game_mode {
+        lines.push(detail_line("Game Mode: ", game_mode_name(mode)));
+    }
+    if let Some(ref difficulty) = world.difficulty {
+        lines.push(detail_line("Difficulty: ", difficulty));
+    }
+    lines.push(detail_line("Hardcore: ", if world.hardcore { "Yes" } else { "No" }));
+    lines.push(detail_line("Cheats: ", if world.cheats { "Allowed" } else { "Off" }));
+    if let Some(seed) = world.seed {
+        lines.push(detail_line("Seed: ", seed.to_string()));
+    }
+    if let Some((x, z)) = world.spawn {
+        lines.push(detail_line("Spawn: ", format!("{}, {}", x, z)));
+    }
+    if let Some((x, z)) = world.player {
+        lines.push(detail_line("Player: ", format!("{}, {}", x, z)));
+    }
+    lines.push(Line::from(""));
+    if let Some(last_played) = world.last_played {
+        lines.push(detail_line("Last Played: ", format_timestamp(last_played)));
+    }
+    if let Some(day_time) = world.day_time {
+        lines.push(detail_line("Time: ", format_day_time(day_time)));
+    }
+    lines.push(detail_line("Size: ", world.size_text()));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_day_time() {
+        assert_eq!(format_day_time(0), "Day 1, 06:00");
+        assert_eq!(format_day_time(18000), "Day 1, 00:00");
+        assert_eq!(format_day_time(24000 * 3 + 6500), "Day 4, 12:30");
+    }
+
+    #[test]
+    fn test_level_data_fields() {
+        let data = Tag::Compound(vec![
+            ("LevelName".into(), Tag::String("My World".into())),
+            ("GameType".into(), Tag::Int(0)),
+            ("hardcore".into(), Tag::Byte(1)),
+            ("Difficulty".into(), Tag::Byte(3)),
+            ("LastPlayed".into(), Tag::Long(1_700_000_000_000)),
+            ("WorldGenSettings".into(), Tag::Compound(vec![("seed".into(), Tag::Long(-42))])),
+            ("Version".into(), Tag::Compound(vec![("Name".into(), Tag::String("1.20.1".into()))])),
+            ("SpawnX".into(), Tag::Int(100)),
+            ("SpawnZ".into(), Tag::Int(-20)),
+            (
+                "Player".into(),
+                Tag::Compound(vec![
+                    ("Dimension".into(), Tag::String("minecraft:overworld".into())),
+                    ("Pos".into(), Tag::List(6, vec![Tag::Double(-3.5), Tag::Double(64.0), Tag::Double(7.2)])),
+                ]),
+            ),
+        ]);
+        let mut world = World::default();
+        world.apply_level_data(&data);
+
+        assert_eq!(world.display_name(), "My World");
+        assert_eq!(world.game_mode, Some(0));
+        assert!(world.hardcore);
+        assert!(!world.cheats);
+        assert_eq!(world.difficulty.as_deref(), Some("Hard"));
+        assert_eq!(world.seed, Some(-42));
+        assert_eq!(world.version.as_deref(), Some("1.20.1"));
+        assert_eq!(world.last_played, Some(1_700_000_000_000));
+        assert_eq!(world.spawn, Some((100, -20)));
+        assert_eq!(world.player, Some((-4, 7)));
+    }
+
+    #[test]
+    fn test_difficulty_settings_by_name() {
+        let data = Tag::Compound(vec![(
+            "difficulty_settings".into(),
+            Tag::Compound(vec![("difficulty".into(), Tag::String("normal".into()))]),
+        )]);
+        let mut world = World::default();
+        world.apply_level_data(&data);
+        assert_eq!(world.difficulty.as_deref(), Some("Normal"));
+    }
+
+    #[test]
+    fn test_rename_and_duplicate() {
+        let dir = std::env::temp_dir().join(format!("mctui-world-ops-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let saves = dir.join("saves");
+        let world_dir = saves.join("New World");
+        fs::create_dir_all(&world_dir).unwrap();
+        let level = Document {
+            name: String::new(),
+            root: Tag::Compound(vec![(
+                "Data".into(),
+                Tag::Compound(vec![("LevelName".into(), Tag::String("New World".into()))]),
+            )]),
+            compression: nbt::Compression::Gzip,
+        };
+        level.save(&world_dir.join("level.dat")).unwrap();
+        fs::write(world_dir.join("session.lock"), b"x").unwrap();
+        fs::create_dir_all(saves.join("Base_ Camp")).unwrap();
+
+        assert_eq!(available_folder(&saves, "New World", Some("New World")), "New World");
+        assert_eq!(available_folder(&saves, "New World", None), "New World (2)");
+        assert_eq!(available_folder(&saves, "...", None), "World");
+
+        let folder = rename_world(&saves, "New World", "Base: Camp").unwrap();
+        assert_eq!(folder, "Base_ Camp (2)");
+        let renamed = World::load(&saves.join(&folder));
+        assert_eq!(renamed.display_name(), "Base: Camp");
+
+        let other_saves = dir.join("other/saves");
+        let copy = duplicate_world(&saves.join(&folder), &other_saves, "Base: Camp").unwrap();
+        assert_eq!(copy, "Base_ Camp");
+        assert!(other_saves.join(&copy).join("level.dat").is_file());
+        assert!(!other_saves.join(&copy).join("session.lock").exists());
+        assert_eq!(World::load(&other_saves.join(&copy)).display_name(), "Base: Camp");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}